@@ -0,0 +1,378 @@
+use crate::bot_lib::http::UpstreamError;
+use crate::bot_lib::metrics::metrics;
+use crate::config::CacheConfig;
+use std::any::Any;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::{debug, warn};
+
+/// A cached response along with when it was fetched
+#[derive(Debug)]
+struct Entry {
+    value: Arc<dyn Any + Send + Sync>,
+    fetched_at: Instant,
+    ttl: Duration,
+    /// Whether a background refresh is already running
+    refreshing: bool,
+}
+
+/// Result of looking up a key
+enum Lookup<T> {
+    /// Within its time to live
+    Fresh(T),
+    /// Expired but within the stale period, along with whether the caller should refresh it
+    Stale(T, bool),
+    Miss,
+}
+
+/// Time-to-live cache for upstream responses, shared by all commands
+///
+/// Entries are keyed by provider and request. Once an entry expired it is still served for
+/// `stale_secs` while a single background task fetches a new one (stale-while-revalidate).
+/// Concurrent misses for the same key wait for a single fetch instead of each calling the
+/// upstream.
+#[derive(Debug)]
+pub struct ResponseCache {
+    config: CacheConfig,
+    entries: Mutex<HashMap<String, Entry>>,
+    /// Locks held while a key is fetched after a miss
+    fetching: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+impl ResponseCache {
+    pub fn new(config: &CacheConfig) -> Self {
+        Self {
+            config: config.clone(),
+            entries: Mutex::default(),
+            fetching: Mutex::default(),
+        }
+    }
+
+    /// The time to live for a provider, which the configuration may override
+    pub fn ttl(&self, provider: &str, default: Duration) -> Duration {
+        self.config
+            .ttl_secs
+            .get(provider)
+            .map_or(default, |&secs| Duration::from_secs(secs))
+    }
+
+    fn lookup<T: Clone + 'static>(&self, key: &str) -> Lookup<T> {
+        let mut entries = self.entries.lock().expect("Cache lock poisoned");
+        let Some(entry) = entries.get_mut(key) else {
+            return Lookup::Miss;
+        };
+        let Some(value) = entry.value.downcast_ref::<T>().cloned() else {
+            return Lookup::Miss;
+        };
+
+        let age = entry.fetched_at.elapsed();
+        if age < entry.ttl {
+            Lookup::Fresh(value)
+        } else if age < entry.ttl + Duration::from_secs(self.config.stale_secs) {
+            let refresh = !entry.refreshing;
+            entry.refreshing = true;
+            Lookup::Stale(value, refresh)
+        } else {
+            Lookup::Miss
+        }
+    }
+
+    fn insert<T: Send + Sync + 'static>(&self, key: String, value: T, ttl: Duration) {
+        let mut entries = self.entries.lock().expect("Cache lock poisoned");
+        if entries.len() >= self.config.max_entries && !entries.contains_key(&key) {
+            let stale = Duration::from_secs(self.config.stale_secs);
+            entries.retain(|_, entry| entry.fetched_at.elapsed() < entry.ttl + stale);
+        }
+        while entries.len() >= self.config.max_entries && !entries.contains_key(&key) {
+            let Some(oldest) = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.fetched_at)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            entries.remove(&oldest);
+        }
+
+        entries.insert(
+            key,
+            Entry {
+                value: Arc::new(value),
+                fetched_at: Instant::now(),
+                ttl,
+                refreshing: false,
+            },
+        );
+    }
+
+    /// The lock to hold while fetching a key after a miss
+    fn fetch_lock(&self, key: &str) -> Arc<tokio::sync::Mutex<()>> {
+        self.fetching
+            .lock()
+            .expect("Cache lock poisoned")
+            .entry(key.to_string())
+            .or_default()
+            .clone()
+    }
+
+    /// Forgets the lock of a fetched key, unless it was replaced in the meantime
+    fn finish_fetch(&self, key: &str, lock: &Arc<tokio::sync::Mutex<()>>) {
+        let mut fetching = self.fetching.lock().expect("Cache lock poisoned");
+        if fetching
+            .get(key)
+            .is_some_and(|current| Arc::ptr_eq(current, lock))
+        {
+            fetching.remove(key);
+        }
+    }
+
+    fn finish_refresh(&self, key: &str) {
+        if let Some(entry) = self
+            .entries
+            .lock()
+            .expect("Cache lock poisoned")
+            .get_mut(key)
+        {
+            entry.refreshing = false;
+        }
+    }
+
+    /// Returns the cached value for the key, or fetches and caches it
+    ///
+    /// A stale value is returned right away while `fetch` runs in the background. Errors are
+    /// never cached. Caching is skipped if it is disabled or `ttl` is zero.
+    pub async fn get_or_fetch<T, F, Fut>(
+        self: &Arc<Self>,
+        provider: &'static str,
+        key: String,
+        ttl: Duration,
+        fetch: F,
+    ) -> Result<T, UpstreamError>
+    where
+        T: Clone + Send + Sync + 'static,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, UpstreamError>> + Send + 'static,
+    {
+        if !self.config.enabled || ttl.is_zero() {
+            return fetch().await;
+        }
+
+        match self.lookup::<T>(&key) {
+            Lookup::Fresh(value) => {
+                record(provider, "hit");
+                Ok(value)
+            }
+            Lookup::Stale(value, refresh) => {
+                record(provider, "stale");
+                if refresh {
+                    debug!(provider, key = %key, "Refreshing stale response");
+                    let cache = self.clone();
+                    let future = fetch();
+                    tokio::spawn(async move {
+                        match future.await {
+                            Ok(value) => cache.insert(key, value, ttl),
+                            Err(e) => {
+                                warn!(error = %e, "Failed to refresh cached response");
+                                cache.finish_refresh(&key);
+                            }
+                        }
+                    });
+                }
+                Ok(value)
+            }
+            Lookup::Miss => {
+                let lock = self.fetch_lock(&key);
+                let _fetching = lock.lock().await;
+                // Another caller may have fetched the value while this one waited
+                if let Lookup::Fresh(value) = self.lookup::<T>(&key) {
+                    record(provider, "hit");
+                    return Ok(value);
+                }
+
+                record(provider, "miss");
+                let result = fetch().await;
+                if let Ok(value) = &result {
+                    self.insert(key.clone(), value.clone(), ttl);
+                }
+                self.finish_fetch(&key, &lock);
+                result
+            }
+        }
+    }
+}
+
+/// Counts a lookup by its result
+fn record(provider: &str, result: &str) {
+    metrics()
+        .cache_requests
+        .with_label_values(&[provider, result])
+        .inc();
+}
+
+#[cfg(test)]
+impl ResponseCache {
+    /// Number of cached responses
+    fn len(&self) -> usize {
+        self.entries.lock().expect("Cache lock poisoned").len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot_lib::http::UpstreamErrorKind;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn cache(stale_secs: u64, max_entries: usize) -> Arc<ResponseCache> {
+        Arc::new(ResponseCache::new(&CacheConfig {
+            stale_secs,
+            max_entries,
+            ..CacheConfig::default()
+        }))
+    }
+
+    /// Fetches the number of previous fetches, so tests can tell cached values apart
+    async fn fetch_counted(
+        cache: &Arc<ResponseCache>,
+        key: &str,
+        ttl: Duration,
+        calls: &Arc<AtomicU32>,
+    ) -> u32 {
+        let calls = calls.clone();
+        cache
+            .get_or_fetch("test", key.to_string(), ttl, move || async move {
+                Ok(calls.fetch_add(1, Ordering::SeqCst))
+            })
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn serves_fresh_values_from_cache() {
+        let cache = cache(0, 10);
+        let calls = Arc::new(AtomicU32::new(0));
+
+        assert_eq!(
+            fetch_counted(&cache, "a", Duration::from_secs(60), &calls).await,
+            0
+        );
+        assert_eq!(
+            fetch_counted(&cache, "a", Duration::from_secs(60), &calls).await,
+            0
+        );
+        assert_eq!(
+            fetch_counted(&cache, "b", Duration::from_secs(60), &calls).await,
+            1
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn refetches_expired_values() {
+        let cache = cache(0, 10);
+        let calls = Arc::new(AtomicU32::new(0));
+        let ttl = Duration::from_secs(60);
+
+        assert_eq!(fetch_counted(&cache, "a", ttl, &calls).await, 0);
+        tokio::time::advance(ttl).await;
+        assert_eq!(fetch_counted(&cache, "a", ttl, &calls).await, 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn serves_stale_values_while_refreshing() {
+        let cache = cache(60, 10);
+        let calls = Arc::new(AtomicU32::new(0));
+        let ttl = Duration::from_secs(60);
+
+        assert_eq!(fetch_counted(&cache, "a", ttl, &calls).await, 0);
+        tokio::time::advance(ttl).await;
+
+        // The stale value is returned right away, the refreshed one once the refresh ran
+        assert_eq!(fetch_counted(&cache, "a", ttl, &calls).await, 0);
+        tokio::task::yield_now().await;
+        assert_eq!(fetch_counted(&cache, "a", ttl, &calls).await, 1);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn coalesces_concurrent_misses() {
+        let cache = cache(0, 10);
+        let calls = Arc::new(AtomicU32::new(0));
+
+        let mut tasks = tokio::task::JoinSet::new();
+        for _ in 0..5 {
+            let cache = cache.clone();
+            let calls = calls.clone();
+            tasks.spawn(async move {
+                cache
+                    .get_or_fetch(
+                        "test",
+                        "a".to_string(),
+                        Duration::from_secs(60),
+                        move || async move {
+                            tokio::time::sleep(Duration::from_secs(1)).await;
+                            Ok(calls.fetch_add(1, Ordering::SeqCst))
+                        },
+                    )
+                    .await
+                    .unwrap()
+            });
+        }
+
+        assert_eq!(tasks.join_all().await, [0; 5]);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(cache.fetching.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn does_not_cache_errors() {
+        let cache = cache(0, 10);
+        let result: Result<u32, _> = cache
+            .get_or_fetch("test", "a".to_string(), Duration::from_secs(60), || async {
+                Err(UpstreamError {
+                    provider: "test",
+                    kind: UpstreamErrorKind::Timeout,
+                })
+            })
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(cache.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn evicts_oldest_entries_when_full() {
+        let cache = cache(0, 2);
+        let calls = Arc::new(AtomicU32::new(0));
+        let ttl = Duration::from_secs(60);
+
+        fetch_counted(&cache, "a", ttl, &calls).await;
+        fetch_counted(&cache, "b", ttl, &calls).await;
+        fetch_counted(&cache, "c", ttl, &calls).await;
+        assert_eq!(cache.len(), 2);
+
+        // "a" was evicted, "c" is still cached
+        assert_eq!(fetch_counted(&cache, "a", ttl, &calls).await, 3);
+        assert_eq!(fetch_counted(&cache, "c", ttl, &calls).await, 2);
+    }
+
+    #[test]
+    fn configured_ttls_override_defaults() {
+        let cache = ResponseCache::new(&CacheConfig {
+            ttl_secs: HashMap::from([("xkcd".to_string(), 5)]),
+            ..CacheConfig::default()
+        });
+
+        assert_eq!(
+            cache.ttl("xkcd", Duration::from_secs(900)),
+            Duration::from_secs(5)
+        );
+        assert_eq!(
+            cache.ttl("viewbits", Duration::from_secs(900)),
+            Duration::from_secs(900)
+        );
+    }
+}
@@ -0,0 +1,77 @@
+use crate::bot_lib::i18n::tr;
+use crate::{Context, Data, Error};
+use poise::serenity_prelude as serenity;
+use std::fmt;
+
+/// Reason a command was refused by [`command_check`], shown to the user
+#[derive(Debug)]
+pub struct CommandUnavailable(pub String);
+
+impl fmt::Display for CommandUnavailable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for CommandUnavailable {}
+
+/// The top-level command being invoked, so subcommands share their parent's rules
+fn root_command(ctx: Context<'_>) -> &poise::Command<Data, Error> {
+    ctx.parent_commands()
+        .first()
+        .copied()
+        .unwrap_or_else(|| ctx.command())
+}
+
+/// The channel a command is used in, followed by the channel it belongs to if it's a thread
+async fn invocation_channels(ctx: Context<'_>) -> Result<Vec<u64>, Error> {
+    let mut channels = vec![ctx.channel_id().get()];
+    if let serenity::Channel::GuildThread(thread) =
+        ctx.channel_id().to_channel(ctx, ctx.guild_id()).await?
+    {
+        channels.push(thread.parent_id.get());
+    }
+    Ok(channels)
+}
+
+/// Global check run before every command
+///
+/// Refuses commands which an owner disabled at runtime with `/admin toggle`, and commands
+/// denied in the current guild or channel with `/config commands`. In threads, rules for the
+/// channel the thread belongs to apply as well.
+pub async fn command_check(ctx: Context<'_>) -> Result<bool, Error> {
+    let command = root_command(ctx);
+    let name = command.name.to_string();
+
+    let disabled = ctx
+        .data()
+        .disabled_commands
+        .read()
+        .expect("Disabled commands lock poisoned")
+        .contains(&name);
+    if disabled {
+        return Err(Box::new(CommandUnavailable(tr!(
+            ctx,
+            "check-disabled",
+            command = name
+        ))));
+    }
+
+    if let Some(guild_id) = ctx.guild_id() {
+        let data = ctx.data();
+        let rules = data.command_rules.get(&data.storage, guild_id).await?;
+        if rules.is_empty() {
+            return Ok(true);
+        }
+        let channels = invocation_channels(ctx).await?;
+        if !rules.is_allowed(&name, command.category.as_deref(), &channels) {
+            return Err(Box::new(CommandUnavailable(tr!(
+                ctx,
+                "check-denied",
+                command = name
+            ))));
+        }
+    }
+
+    Ok(true)
+}
@@ -0,0 +1,51 @@
+use crate::{Context, Data, Error};
+use poise::serenity_prelude as serenity;
+use std::future::Future;
+use std::sync::Arc;
+
+/// The parts of an invocation the logic of a command depends on
+///
+/// Commands are thin wrappers handing poise's [`Context`] to functions generic over this trait,
+/// which tests call with a [`FakeContext`](super::fake_context::FakeContext) instead.
+pub trait CommandContext: Copy + Send + Sync {
+    fn data(self) -> Arc<Data>;
+
+    fn guild_id(self) -> Option<serenity::GuildId>;
+
+    /// The invoker's Discord language, which prefix commands don't have
+    fn discord_locale(self) -> Option<String>;
+
+    /// Acknowledges the invocation, for commands which take a while to reply
+    fn defer(self) -> impl Future<Output = Result<(), Error>> + Send;
+
+    fn send(self, reply: poise::CreateReply<'_>) -> impl Future<Output = Result<(), Error>> + Send;
+
+    /// Replies with plain text
+    fn say(self, content: String) -> impl Future<Output = Result<(), Error>> + Send {
+        self.send(poise::CreateReply::default().content(content))
+    }
+}
+
+impl CommandContext for Context<'_> {
+    fn data(self) -> Arc<Data> {
+        poise::Context::data(self)
+    }
+
+    fn guild_id(self) -> Option<serenity::GuildId> {
+        poise::Context::guild_id(self)
+    }
+
+    fn discord_locale(self) -> Option<String> {
+        poise::Context::locale(self).map(str::to_string)
+    }
+
+    async fn defer(self) -> Result<(), Error> {
+        poise::Context::defer(self).await?;
+        Ok(())
+    }
+
+    async fn send(self, reply: poise::CreateReply<'_>) -> Result<(), Error> {
+        poise::Context::send(self, reply).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,327 @@
+use crate::storage::Storage;
+use poise::serenity_prelude as serenity;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::RwLock;
+use tracing::warn;
+
+/// Key under which a guild's rules are stored in the guild settings
+const SETTING_KEY: &str = "command_rules";
+
+/// Commands which can never be disabled, so a guild can't lock itself out
+pub const PROTECTED_COMMANDS: &[&str] = &["admin", "config"];
+
+/// What a rule applies to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "kind", content = "name")]
+pub enum Target {
+    Command(String),
+    Category(String),
+}
+
+/// Whether a rule allows or denies its target
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Effect {
+    Allow,
+    Deny,
+}
+
+/// A single allow/deny rule, either guild-wide or for one channel
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rule {
+    pub target: Target,
+    pub effect: Effect,
+    /// Channel the rule is limited to, or `None` for the whole guild
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel_id: Option<u64>,
+}
+
+/// The command rules of a single guild
+///
+/// The most specific matching rule wins: channel rules beat guild-wide rules, and within the
+/// same scope a rule for the command beats one for its category. Without a matching rule
+/// the command is allowed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CommandRules {
+    rules: Vec<Rule>,
+}
+
+impl CommandRules {
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    fn effect_for(&self, target: &Target, channel_id: Option<u64>) -> Option<Effect> {
+        self.rules
+            .iter()
+            .find(|rule| rule.target == *target && rule.channel_id == channel_id)
+            .map(|rule| rule.effect)
+    }
+
+    /// Whether a command may be used in a channel
+    ///
+    /// `channel_ids` are the channel the command is used in followed by the channels it is
+    /// part of, e.g. a thread and the channel the thread was started in, so rules for the
+    /// inner channel win.
+    pub fn is_allowed(&self, command: &str, category: Option<&str>, channel_ids: &[u64]) -> bool {
+        if PROTECTED_COMMANDS.contains(&command) {
+            return true;
+        }
+
+        let command = Target::Command(command.to_string());
+        let category = category.map(|name| Target::Category(name.to_string()));
+
+        let mut candidates = Vec::with_capacity(2 * (channel_ids.len() + 1));
+        let scopes = channel_ids.iter().copied().map(Some).chain([None]);
+        for scope in scopes {
+            candidates.push((&command, scope));
+            if let Some(category) = &category {
+                candidates.push((category, scope));
+            }
+        }
+
+        candidates
+            .into_iter()
+            .find_map(|(target, scope)| self.effect_for(target, scope))
+            .is_none_or(|effect| effect == Effect::Allow)
+    }
+
+    /// Adds a rule, replacing any existing rule for the same target and channel
+    pub fn set(&mut self, rule: Rule) {
+        self.remove(&rule.target, rule.channel_id);
+        self.rules.push(rule);
+    }
+
+    /// Removes the rule for a target and channel, returning whether it existed
+    pub fn remove(&mut self, target: &Target, channel_id: Option<u64>) -> bool {
+        let before = self.rules.len();
+        self.rules
+            .retain(|rule| !(rule.target == *target && rule.channel_id == channel_id));
+        self.rules.len() != before
+    }
+}
+
+/// Per-guild command rules, cached in memory as they're checked before every command
+#[derive(Debug, Default)]
+pub struct GuildCommandRules {
+    cache: RwLock<HashMap<serenity::GuildId, CommandRules>>,
+}
+
+impl GuildCommandRules {
+    /// Returns the rules of a guild
+    pub async fn get(
+        &self,
+        storage: &Storage,
+        guild_id: serenity::GuildId,
+    ) -> Result<CommandRules, sqlx::Error> {
+        if let Some(rules) = self
+            .cache
+            .read()
+            .expect("Command rule cache lock poisoned")
+            .get(&guild_id)
+        {
+            return Ok(rules.clone());
+        }
+
+        let rules = load(storage, guild_id).await?;
+        self.cache
+            .write()
+            .expect("Command rule cache lock poisoned")
+            .insert(guild_id, rules.clone());
+        Ok(rules)
+    }
+
+    /// Replaces the rules of a guild
+    pub async fn set(
+        &self,
+        storage: &Storage,
+        guild_id: serenity::GuildId,
+        rules: CommandRules,
+    ) -> Result<(), sqlx::Error> {
+        save(storage, guild_id, &rules).await?;
+        self.cache
+            .write()
+            .expect("Command rule cache lock poisoned")
+            .insert(guild_id, rules);
+        Ok(())
+    }
+}
+
+/// Loads the rules of a guild, treating missing or malformed rules as empty
+async fn load(storage: &Storage, guild_id: serenity::GuildId) -> Result<CommandRules, sqlx::Error> {
+    let Some(json) = storage.guild_setting(guild_id, SETTING_KEY).await? else {
+        return Ok(CommandRules::default());
+    };
+
+    Ok(serde_json::from_str(&json).unwrap_or_else(|e| {
+        warn!(%guild_id, error = %e, "Ignoring malformed command rules");
+        CommandRules::default()
+    }))
+}
+
+/// Persists the rules of a guild, removing the setting entirely once no rules are left
+async fn save(
+    storage: &Storage,
+    guild_id: serenity::GuildId,
+    rules: &CommandRules,
+) -> Result<(), sqlx::Error> {
+    if rules.is_empty() {
+        storage.delete_guild_setting(guild_id, SETTING_KEY).await?;
+    } else {
+        let json = serde_json::to_string(rules).expect("Command rules are always serializable");
+        storage
+            .set_guild_setting(guild_id, SETTING_KEY, &json)
+            .await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STUDY: u64 = 1;
+    const OFF_TOPIC: u64 = 2;
+    const STUDY_THREAD: u64 = 3;
+
+    fn rule(target: Target, effect: Effect, channel_id: Option<u64>) -> Rule {
+        Rule {
+            target,
+            effect,
+            channel_id,
+        }
+    }
+
+    #[test]
+    fn allows_everything_without_rules() {
+        let rules = CommandRules::default();
+        assert!(rules.is_allowed("say", Some("Fun"), &[STUDY]));
+    }
+
+    #[test]
+    fn channel_rule_beats_guild_rule() {
+        let mut rules = CommandRules::default();
+        rules.set(rule(
+            Target::Command("pickup_line".into()),
+            Effect::Deny,
+            None,
+        ));
+        rules.set(rule(
+            Target::Command("pickup_line".into()),
+            Effect::Allow,
+            Some(OFF_TOPIC),
+        ));
+
+        assert!(!rules.is_allowed("pickup_line", Some("Fun"), &[STUDY]));
+        assert!(rules.is_allowed("pickup_line", Some("Fun"), &[OFF_TOPIC]));
+    }
+
+    #[test]
+    fn channel_rules_apply_in_threads() {
+        let mut rules = CommandRules::default();
+        rules.set(rule(
+            Target::Category("Fun".into()),
+            Effect::Deny,
+            Some(STUDY),
+        ));
+
+        assert!(!rules.is_allowed("say", Some("Fun"), &[STUDY_THREAD, STUDY]));
+        assert!(rules.is_allowed("say", Some("Fun"), &[STUDY_THREAD]));
+
+        rules.set(rule(
+            Target::Category("Fun".into()),
+            Effect::Allow,
+            Some(STUDY_THREAD),
+        ));
+        assert!(rules.is_allowed("say", Some("Fun"), &[STUDY_THREAD, STUDY]));
+    }
+
+    #[test]
+    fn command_rule_beats_category_rule() {
+        let mut rules = CommandRules::default();
+        rules.set(rule(Target::Category("Fun".into()), Effect::Deny, None));
+        rules.set(rule(Target::Command("xkcd".into()), Effect::Allow, None));
+
+        assert!(!rules.is_allowed("say", Some("Fun"), &[STUDY]));
+        assert!(rules.is_allowed("xkcd", Some("Fun"), &[STUDY]));
+        assert!(rules.is_allowed("ping", Some("Utility"), &[STUDY]));
+    }
+
+    #[test]
+    fn protected_commands_cannot_be_denied() {
+        let mut rules = CommandRules::default();
+        rules.set(rule(Target::Command("config".into()), Effect::Deny, None));
+        rules.set(rule(Target::Category("Admin".into()), Effect::Deny, None));
+
+        assert!(rules.is_allowed("config", Some("Admin"), &[STUDY]));
+    }
+
+    #[test]
+    fn set_replaces_and_remove_deletes() {
+        let mut rules = CommandRules::default();
+        let target = Target::Command("say".into());
+        rules.set(rule(target.clone(), Effect::Deny, None));
+        rules.set(rule(target.clone(), Effect::Allow, None));
+        assert_eq!(rules.rules().len(), 1);
+        assert!(rules.is_allowed("say", None, &[STUDY]));
+
+        assert!(rules.remove(&target, None));
+        assert!(!rules.remove(&target, None));
+        assert!(rules.is_empty());
+    }
+
+    #[tokio::test]
+    async fn rules_round_trip_through_storage() {
+        let storage = Storage::in_memory().await.unwrap();
+        let guild = serenity::GuildId::new(42);
+
+        let mut rules = CommandRules::default();
+        rules.set(rule(
+            Target::Command("say".into()),
+            Effect::Deny,
+            Some(STUDY),
+        ));
+        save(&storage, guild, &rules).await.unwrap();
+        assert_eq!(load(&storage, guild).await.unwrap(), rules);
+
+        save(&storage, guild, &CommandRules::default())
+            .await
+            .unwrap();
+        assert_eq!(
+            storage.guild_setting(guild, SETTING_KEY).await.unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn rules_are_cached_until_replaced() {
+        let storage = Storage::in_memory().await.unwrap();
+        let guild = serenity::GuildId::new(42);
+        let cache = GuildCommandRules::default();
+        assert!(cache.get(&storage, guild).await.unwrap().is_empty());
+
+        let mut rules = CommandRules::default();
+        rules.set(rule(Target::Command("say".into()), Effect::Deny, None));
+        cache.set(&storage, guild, rules.clone()).await.unwrap();
+        assert_eq!(cache.get(&storage, guild).await.unwrap(), rules);
+
+        // Changes made behind the cache's back aren't seen
+        save(&storage, guild, &CommandRules::default())
+            .await
+            .unwrap();
+        assert_eq!(cache.get(&storage, guild).await.unwrap(), rules);
+        assert!(
+            GuildCommandRules::default()
+                .get(&storage, guild)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,377 @@
+use crate::storage::Storage;
+use poise::serenity_prelude as serenity;
+use rand::seq::IndexedRandom;
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+/// Most entries a guild can add to a single pack
+pub const MAX_GUILD_ENTRIES: usize = 100;
+
+/// Longest entry a guild can add, in characters
+pub const MAX_ENTRY_LEN: usize = 500;
+
+/// Kinds of content the fun commands fall back to when their upstream is unavailable
+#[derive(Debug, Clone, Copy, PartialEq, Eq, poise::ChoiceParameter)]
+pub enum PackKind {
+    Advice,
+    #[name = "Dad Joke"]
+    DadJoke,
+    Fact,
+    Fortune,
+    Pickup,
+    Breakup,
+}
+
+impl PackKind {
+    /// Name used for the bundled file, the guild setting and the message catalogues
+    pub fn key(self) -> &'static str {
+        match self {
+            Self::Advice => "advice",
+            Self::DadJoke => "dad_joke",
+            Self::Fact => "fact",
+            Self::Fortune => "fortune",
+            Self::Pickup => "pickup",
+            Self::Breakup => "breakup",
+        }
+    }
+
+    /// Source of the bundled pack, one entry per line with `#` starting a comment
+    fn bundled_source(self) -> &'static str {
+        match self {
+            Self::Advice => include_str!("../assets/content_packs/advice.txt"),
+            Self::DadJoke => include_str!("../assets/content_packs/dad_joke.txt"),
+            Self::Fact => include_str!("../assets/content_packs/fact.txt"),
+            Self::Fortune => include_str!("../assets/content_packs/fortune.txt"),
+            Self::Pickup => include_str!("../assets/content_packs/pickup.txt"),
+            Self::Breakup => include_str!("../assets/content_packs/breakup.txt"),
+        }
+    }
+
+    /// The entries shipped with the bot
+    pub fn bundled(self) -> impl Iterator<Item = &'static str> {
+        self.bundled_source()
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+    }
+
+    fn setting_key(self) -> String {
+        format!("content_pack.{}", self.key())
+    }
+}
+
+/// How an entry is picked from a pack
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pick {
+    /// A different one on every call
+    Random,
+    /// The same one for the whole (UTC) day
+    Daily,
+}
+
+/// Why an entry couldn't be added to a guild's pack
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidEntry {
+    Empty,
+    TooLong,
+    Duplicate,
+    PackFull,
+}
+
+impl InvalidEntry {
+    /// Identifies the reason in the message catalogues
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Empty => "empty",
+            Self::TooLong => "too-long",
+            Self::Duplicate => "duplicate",
+            Self::PackFull => "full",
+        }
+    }
+}
+
+/// Loads the entries a guild added to a pack
+pub async fn guild_entries(
+    storage: &Storage,
+    guild_id: serenity::GuildId,
+    kind: PackKind,
+) -> Result<Vec<String>, sqlx::Error> {
+    let Some(raw) = storage.guild_setting(guild_id, &kind.setting_key()).await? else {
+        return Ok(Vec::new());
+    };
+    Ok(serde_json::from_str(&raw).unwrap_or_else(|e| {
+        warn!(%guild_id, pack = kind.key(), error = %e, "Ignoring malformed content pack");
+        Vec::new()
+    }))
+}
+
+/// Locks serializing changes to a guild's packs, as every change rewrites a whole pack
+static PACK_LOCKS: LazyLock<Mutex<HashMap<serenity::GuildId, Arc<tokio::sync::Mutex<()>>>>> =
+    LazyLock::new(Mutex::default);
+
+/// Waits until no other change to a guild's packs is in progress
+async fn lock_packs(guild_id: serenity::GuildId) -> tokio::sync::OwnedMutexGuard<()> {
+    let lock = PACK_LOCKS
+        .lock()
+        .expect("Pack lock map poisoned")
+        .entry(guild_id)
+        .or_default()
+        .clone();
+    lock.lock_owned().await
+}
+
+async fn save_guild_entries(
+    storage: &Storage,
+    guild_id: serenity::GuildId,
+    kind: PackKind,
+    entries: &[String],
+) -> Result<(), sqlx::Error> {
+    if entries.is_empty() {
+        storage
+            .delete_guild_setting(guild_id, &kind.setting_key())
+            .await?;
+    } else {
+        let raw = serde_json::to_string(entries).expect("Strings always serialize");
+        storage
+            .set_guild_setting(guild_id, &kind.setting_key(), &raw)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Adds an entry to a guild's pack
+///
+/// The outer error is a database failure, the inner one a rejected entry.
+pub async fn add_entry(
+    storage: &Storage,
+    guild_id: serenity::GuildId,
+    kind: PackKind,
+    entry: &str,
+) -> Result<Result<(), InvalidEntry>, sqlx::Error> {
+    let entry = entry.trim();
+    if entry.is_empty() {
+        return Ok(Err(InvalidEntry::Empty));
+    }
+    if entry.chars().count() > MAX_ENTRY_LEN {
+        return Ok(Err(InvalidEntry::TooLong));
+    }
+
+    let _lock = lock_packs(guild_id).await;
+    let mut entries = guild_entries(storage, guild_id, kind).await?;
+    if entries.iter().any(|existing| existing == entry) {
+        return Ok(Err(InvalidEntry::Duplicate));
+    }
+    if entries.len() >= MAX_GUILD_ENTRIES {
+        return Ok(Err(InvalidEntry::PackFull));
+    }
+
+    entries.push(entry.to_string());
+    save_guild_entries(storage, guild_id, kind, &entries).await?;
+    Ok(Ok(()))
+}
+
+/// Removes the entry at the given index from a guild's pack, returning it if it existed
+pub async fn remove_entry(
+    storage: &Storage,
+    guild_id: serenity::GuildId,
+    kind: PackKind,
+    index: usize,
+) -> Result<Option<String>, sqlx::Error> {
+    let _lock = lock_packs(guild_id).await;
+    let mut entries = guild_entries(storage, guild_id, kind).await?;
+    if index >= entries.len() {
+        return Ok(None);
+    }
+    let removed = entries.remove(index);
+    save_guild_entries(storage, guild_id, kind, &entries).await?;
+    Ok(Some(removed))
+}
+
+/// Picks an entry from the bundled pack and, in a guild, the entries the guild added
+///
+/// Failing to load the guild's entries isn't fatal, as this is the fallback when something
+/// else already went wrong.
+pub async fn pick_entry(
+    storage: &Storage,
+    guild_id: Option<serenity::GuildId>,
+    kind: PackKind,
+    pick: Pick,
+) -> String {
+    let mut entries: Vec<String> = kind.bundled().map(str::to_string).collect();
+    if let Some(guild_id) = guild_id {
+        match guild_entries(storage, guild_id, kind).await {
+            Ok(guild) => entries.extend(guild),
+            Err(e) => warn!(%guild_id, error = %e, "Failed to load guild content pack"),
+        }
+    }
+    choose(&entries, pick).to_string()
+}
+
+fn choose(entries: &[String], pick: Pick) -> &str {
+    let entry = match pick {
+        Pick::Random => entries.choose(&mut rand::rng()),
+        Pick::Daily => {
+            let days = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs() / 86_400);
+            entries.get(days as usize % entries.len().max(1))
+        }
+    };
+    entry.map_or("", String::as_str)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KINDS: [PackKind; 6] = [
+        PackKind::Advice,
+        PackKind::DadJoke,
+        PackKind::Fact,
+        PackKind::Fortune,
+        PackKind::Pickup,
+        PackKind::Breakup,
+    ];
+
+    #[test]
+    fn bundled_packs_are_usable() {
+        for kind in KINDS {
+            let entries: Vec<_> = kind.bundled().collect();
+            assert!(entries.len() >= 10, "{} pack is too small", kind.key());
+            for entry in entries {
+                assert!(
+                    entry.chars().count() <= MAX_ENTRY_LEN,
+                    "{} pack has an overly long entry: {}",
+                    kind.key(),
+                    entry
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn daily_pick_is_stable() {
+        let entries: Vec<String> = PackKind::Fortune.bundled().map(str::to_string).collect();
+        assert_eq!(choose(&entries, Pick::Daily), choose(&entries, Pick::Daily));
+        let random = choose(&entries, Pick::Random);
+        assert!(entries.iter().any(|entry| entry == random));
+    }
+
+    #[tokio::test]
+    async fn guild_entries_extend_bundled_pack() {
+        let storage = Storage::in_memory().await.unwrap();
+        let guild = serenity::GuildId::new(3);
+        let other = serenity::GuildId::new(4);
+
+        add_entry(&storage, guild, PackKind::Advice, "  Touch grass.  ")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            guild_entries(&storage, guild, PackKind::Advice)
+                .await
+                .unwrap(),
+            vec!["Touch grass.".to_string()]
+        );
+        assert!(
+            guild_entries(&storage, other, PackKind::Advice)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(
+            guild_entries(&storage, guild, PackKind::Fact)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        assert_eq!(
+            remove_entry(&storage, guild, PackKind::Advice, 0)
+                .await
+                .unwrap(),
+            Some("Touch grass.".to_string())
+        );
+        assert_eq!(
+            remove_entry(&storage, guild, PackKind::Advice, 0)
+                .await
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            storage
+                .guild_setting(guild, "content_pack.advice")
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_entries() {
+        let storage = Storage::in_memory().await.unwrap();
+        let guild = serenity::GuildId::new(3);
+        let add = |entry: String| {
+            let storage = storage.clone();
+            async move {
+                add_entry(&storage, guild, PackKind::Fact, &entry)
+                    .await
+                    .unwrap()
+            }
+        };
+
+        assert_eq!(add(" ".to_string()).await, Err(InvalidEntry::Empty));
+        assert_eq!(
+            add("a".repeat(MAX_ENTRY_LEN + 1)).await,
+            Err(InvalidEntry::TooLong)
+        );
+        assert_eq!(add("Water is wet.".to_string()).await, Ok(()));
+        assert_eq!(
+            add("Water is wet.".to_string()).await,
+            Err(InvalidEntry::Duplicate)
+        );
+
+        for i in 1..MAX_GUILD_ENTRIES {
+            assert_eq!(add(format!("Fact {}", i)).await, Ok(()));
+        }
+        assert_eq!(
+            add("One too many".to_string()).await,
+            Err(InvalidEntry::PackFull)
+        );
+    }
+
+    #[tokio::test]
+    async fn concurrent_changes_keep_every_entry() {
+        let storage = Storage::in_memory().await.unwrap();
+        let guild = serenity::GuildId::new(5);
+
+        let mut tasks = tokio::task::JoinSet::new();
+        for i in 0..MAX_GUILD_ENTRIES + 10 {
+            let storage = storage.clone();
+            tasks.spawn(async move {
+                add_entry(
+                    &storage,
+                    guild,
+                    PackKind::Fortune,
+                    &format!("Fortune {}", i),
+                )
+                .await
+                .unwrap()
+            });
+        }
+        let added = tasks.join_all().await;
+        assert_eq!(
+            added.iter().filter(|result| result.is_ok()).count(),
+            MAX_GUILD_ENTRIES
+        );
+        assert_eq!(
+            guild_entries(&storage, guild, PackKind::Fortune)
+                .await
+                .unwrap()
+                .len(),
+            MAX_GUILD_ENTRIES
+        );
+    }
+}
@@ -0,0 +1,132 @@
+use crate::bot_lib::command_context::CommandContext;
+#[cfg(test)]
+use crate::config::Config;
+use crate::{Data, Error};
+use poise::serenity_prelude as serenity;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// A reply captured by [`FakeContext`], with embeds and components as the JSON sent to Discord
+#[derive(Debug, Clone, Default)]
+pub struct SentReply {
+    pub content: Option<String>,
+    pub embeds: Vec<serde_json::Value>,
+    pub components: Vec<serde_json::Value>,
+    pub attachments: Vec<SentAttachment>,
+    pub ephemeral: bool,
+}
+
+/// A file attached to a [`SentReply`]
+#[derive(Debug, Clone)]
+pub struct SentAttachment {
+    pub filename: String,
+    pub data: Vec<u8>,
+}
+
+/// Configuration with short timeouts and no retries, as tests run against mock servers
+#[cfg(test)]
+pub fn config() -> Config {
+    let mut config = Config::default();
+    config.http.timeout_secs = 1;
+    config.http.connect_timeout_secs = 1;
+    config.http.max_retries = 0;
+    config
+}
+
+/// Runs command logic without Discord, capturing what it would have sent
+///
+/// Used by the tests and the REPL.
+pub struct FakeContext {
+    data: Arc<Data>,
+    pub guild_id: Option<serenity::GuildId>,
+    pub locale: Option<String>,
+    deferred: AtomicBool,
+    replies: Mutex<Vec<SentReply>>,
+}
+
+impl FakeContext {
+    /// Creates a context outside of a guild
+    pub fn with_data(data: Arc<Data>) -> Self {
+        Self {
+            data,
+            guild_id: None,
+            locale: None,
+            deferred: AtomicBool::new(false),
+            replies: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Creates a context outside of a guild with an in-memory database
+    #[cfg(test)]
+    pub async fn new(config: Config) -> Self {
+        let storage = crate::storage::Storage::in_memory()
+            .await
+            .expect("Failed to open in-memory database");
+        Self::with_data(Arc::new(Data::load(config, storage).await))
+    }
+
+    /// Whether the command deferred its reply
+    pub fn deferred(&self) -> bool {
+        self.deferred.load(Ordering::Relaxed)
+    }
+
+    /// The replies sent so far, in order
+    pub fn replies(&self) -> Vec<SentReply> {
+        self.replies.lock().expect("Reply lock poisoned").clone()
+    }
+
+    /// The only reply sent, panicking if there were none or several
+    #[cfg(test)]
+    pub fn reply(&self) -> SentReply {
+        let replies = self.replies();
+        assert_eq!(replies.len(), 1, "Expected a single reply: {:?}", replies);
+        replies.into_iter().next().unwrap()
+    }
+}
+
+impl CommandContext for &FakeContext {
+    fn data(self) -> Arc<Data> {
+        self.data.clone()
+    }
+
+    fn guild_id(self) -> Option<serenity::GuildId> {
+        self.guild_id
+    }
+
+    fn discord_locale(self) -> Option<String> {
+        self.locale.clone()
+    }
+
+    async fn defer(self) -> Result<(), Error> {
+        self.deferred.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+
+    async fn send(self, reply: poise::CreateReply<'_>) -> Result<(), Error> {
+        let sent = SentReply {
+            content: reply.content.map(|content| content.to_string()),
+            embeds: reply
+                .embeds
+                .iter()
+                .map(serde_json::to_value)
+                .collect::<Result<_, _>>()?,
+            components: reply
+                .components
+                .iter()
+                .flat_map(|components| components.iter())
+                .map(serde_json::to_value)
+                .collect::<Result<_, _>>()?,
+            attachments: reply
+                .attachments
+                .iter()
+                .map(|attachment| SentAttachment {
+                    filename: attachment.filename.to_string(),
+                    data: attachment.data.to_vec(),
+                })
+                .collect(),
+            ephemeral: reply.ephemeral.unwrap_or(false),
+        };
+        self.replies.lock().expect("Reply lock poisoned").push(sent);
+        Ok(())
+    }
+}
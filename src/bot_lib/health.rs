@@ -0,0 +1,107 @@
+use crate::Data;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Liveness signals which are updated from events and reported by `/readyz`
+///
+/// The gateway connection is tracked per shard in [`Shards`](crate::bot_lib::shards::Shards).
+#[derive(Debug, Default)]
+pub struct Health {
+    commands_registered: AtomicBool,
+    registration_started: AtomicBool,
+}
+
+impl Health {
+    /// Returns `true` for the first caller only, as every shard becoming ready would otherwise
+    /// sync the commands again
+    pub fn start_registration(&self) -> bool {
+        !self.registration_started.swap(true, Ordering::Relaxed)
+    }
+
+    pub fn set_commands_registered(&self, registered: bool) {
+        self.commands_registered
+            .store(registered, Ordering::Relaxed);
+    }
+
+    /// Records the outcome of the registration claimed with [`Health::start_registration`],
+    /// letting the next shard becoming ready try again if it failed
+    pub fn finish_registration(&self, registered: bool) {
+        self.set_commands_registered(registered);
+        if !registered {
+            self.registration_started.store(false, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Individual readiness checks
+#[derive(Debug, Serialize)]
+pub struct Checks {
+    pub gateway: bool,
+    pub commands: bool,
+    pub templates: bool,
+}
+
+/// Readiness as reported by `/readyz`
+#[derive(Debug, Serialize)]
+pub struct Readiness {
+    pub ready: bool,
+    pub checks: Checks,
+}
+
+impl Readiness {
+    pub fn of(data: &Data) -> Self {
+        let checks = Checks {
+            gateway: data.shards.all_connected(),
+            commands: data.health.commands_registered.load(Ordering::Relaxed),
+            templates: !data
+                .meme_templates
+                .read()
+                .expect("Meme template lock poisoned")
+                .is_empty(),
+        };
+        Self {
+            ready: checks.gateway && checks.commands && checks.templates,
+            checks,
+        }
+    }
+}
+
+/// Build information reported by `/version`
+#[derive(Debug, Serialize)]
+pub struct Version {
+    pub name: &'static str,
+    pub version: &'static str,
+    pub git_hash: &'static str,
+    pub git_branch: &'static str,
+    pub build_timestamp: &'static str,
+}
+
+impl Version {
+    pub const CURRENT: Self = Self {
+        name: env!("CARGO_PKG_NAME"),
+        version: env!("CARGO_PKG_VERSION"),
+        git_hash: env!("GIT_HASH"),
+        git_branch: env!("GIT_BRANCH"),
+        build_timestamp: env!("BUILD_TIMESTAMP"),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_registration_is_retried() {
+        let health = Health::default();
+        assert!(health.start_registration());
+        assert!(!health.start_registration());
+
+        health.finish_registration(false);
+        assert!(!health.commands_registered.load(Ordering::Relaxed));
+        assert!(health.start_registration());
+
+        health.finish_registration(true);
+        assert!(health.commands_registered.load(Ordering::Relaxed));
+        assert!(!health.start_registration());
+    }
+}
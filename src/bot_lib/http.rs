@@ -0,0 +1,451 @@
+use crate::bot_lib::cache::ResponseCache;
+use crate::bot_lib::metrics::metrics;
+use crate::config::HttpConfig;
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{Instrument, debug, info_span, warn};
+
+/// The reason an upstream API request failed
+#[derive(Debug)]
+pub enum UpstreamErrorKind {
+    /// The request did not complete within the configured timeout
+    Timeout,
+    /// The connection to the upstream could not be established
+    Connect,
+    /// The upstream answered with a non-success status code
+    Status(StatusCode),
+    /// The response body could not be read or decoded
+    Decode(String),
+    /// Any other error while building or sending the request
+    Request(String),
+}
+
+/// Error returned by [`HttpClient`] for a failed upstream request
+#[derive(Debug)]
+pub struct UpstreamError {
+    /// Name of the upstream provider, e.g. `"xkcd"`
+    pub provider: &'static str,
+    pub kind: UpstreamErrorKind,
+}
+
+impl UpstreamErrorKind {
+    /// Short name used as metric label
+    fn label(&self) -> &'static str {
+        match self {
+            UpstreamErrorKind::Timeout => "timeout",
+            UpstreamErrorKind::Connect => "connect",
+            UpstreamErrorKind::Status(status) if status.is_server_error() => "server_error",
+            UpstreamErrorKind::Status(_) => "client_error",
+            UpstreamErrorKind::Decode(_) => "decode",
+            UpstreamErrorKind::Request(_) => "request",
+        }
+    }
+}
+
+impl UpstreamError {
+    fn new(provider: &'static str, kind: UpstreamErrorKind) -> Self {
+        Self { provider, kind }
+    }
+
+    pub fn from_reqwest(provider: &'static str, e: reqwest::Error) -> Self {
+        let kind = if e.is_timeout() {
+            UpstreamErrorKind::Timeout
+        } else if e.is_connect() {
+            UpstreamErrorKind::Connect
+        } else if let Some(status) = e.status() {
+            UpstreamErrorKind::Status(status)
+        } else if e.is_decode() || e.is_body() {
+            UpstreamErrorKind::Decode(e.to_string())
+        } else {
+            UpstreamErrorKind::Request(e.to_string())
+        };
+        Self::new(provider, kind)
+    }
+
+    /// Whether retrying the same request might succeed
+    fn is_transient(&self) -> bool {
+        match &self.kind {
+            UpstreamErrorKind::Timeout | UpstreamErrorKind::Connect => true,
+            UpstreamErrorKind::Status(status) => {
+                status.is_server_error() || *status == StatusCode::TOO_MANY_REQUESTS
+            }
+            UpstreamErrorKind::Decode(_) | UpstreamErrorKind::Request(_) => false,
+        }
+    }
+}
+
+impl fmt::Display for UpstreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            UpstreamErrorKind::Timeout => write!(f, "{} API request timed out", self.provider),
+            UpstreamErrorKind::Connect => write!(f, "Could not connect to {} API", self.provider),
+            UpstreamErrorKind::Status(status) => {
+                write!(f, "{} API responded with {}", self.provider, status)
+            }
+            UpstreamErrorKind::Decode(e) => {
+                write!(
+                    f,
+                    "{} API returned an invalid response: {}",
+                    self.provider, e
+                )
+            }
+            UpstreamErrorKind::Request(e) => {
+                write!(f, "{} API request failed: {}", self.provider, e)
+            }
+        }
+    }
+}
+
+impl std::error::Error for UpstreamError {}
+
+/// Shared HTTP client used by all commands talking to external APIs
+///
+/// Wraps a single [`reqwest::Client`] configured with a common User-Agent and timeouts,
+/// retries transient failures with exponential backoff and records a tracing span per upstream.
+/// Clones share the same [`ResponseCache`].
+#[derive(Debug, Clone)]
+pub struct HttpClient {
+    client: reqwest::Client,
+    max_retries: u32,
+    backoff: Duration,
+    cache: Arc<ResponseCache>,
+}
+
+/// Starts building a [`reqwest::Client`] with the User-Agent and timeouts of the `[http]`
+/// configuration section
+pub fn client_builder(config: &HttpConfig) -> reqwest::ClientBuilder {
+    reqwest::Client::builder()
+        .user_agent(config.user_agent.clone())
+        .timeout(Duration::from_secs(config.timeout_secs))
+        .connect_timeout(Duration::from_secs(config.connect_timeout_secs))
+}
+
+impl HttpClient {
+    /// Creates a new client from the `[http]` configuration section
+    pub fn new(config: &HttpConfig) -> Result<Self, reqwest::Error> {
+        let client = client_builder(config).build()?;
+        Ok(Self::with_client(client, config, config.max_retries))
+    }
+
+    /// Wraps a client built with [`client_builder`], retrying transient failures up to
+    /// `max_retries` times
+    pub fn with_client(client: reqwest::Client, config: &HttpConfig, max_retries: u32) -> Self {
+        Self {
+            client,
+            max_retries,
+            backoff: Duration::from_millis(config.backoff_ms),
+            cache: Arc::new(ResponseCache::new(&config.cache)),
+        }
+    }
+
+    /// Starts building a GET request
+    pub fn get(&self, url: &str) -> RequestBuilder {
+        self.client.get(url)
+    }
+
+    /// Sends a request, retrying transient failures, and returns the successful response
+    pub async fn send(
+        &self,
+        provider: &'static str,
+        request: RequestBuilder,
+    ) -> Result<Response, UpstreamError> {
+        let span = info_span!("upstream", provider);
+        async move {
+            let mut attempt = 0;
+            loop {
+                let result = match request.try_clone() {
+                    Some(request) => self.send_once(provider, request).await,
+                    // Requests with streaming bodies cannot be retried
+                    None => return self.send_once(provider, request).await,
+                };
+
+                match result {
+                    Err(e) if e.is_transient() && attempt < self.max_retries => {
+                        let delay = self.backoff * 2u32.saturating_pow(attempt);
+                        attempt += 1;
+                        warn!(error = %e, attempt, ?delay, "Upstream request failed, retrying");
+                        tokio::time::sleep(delay).await;
+                    }
+                    result => return result,
+                }
+            }
+        }
+        .instrument(span)
+        .await
+    }
+
+    async fn send_once(
+        &self,
+        provider: &'static str,
+        request: RequestBuilder,
+    ) -> Result<Response, UpstreamError> {
+        let started = Instant::now();
+        let result = request
+            .send()
+            .await
+            .map_err(|e| UpstreamError::from_reqwest(provider, e))
+            .and_then(|response| {
+                debug!(
+                    status = %response.status(),
+                    url = %response.url(),
+                    elapsed_ms = started.elapsed().as_millis() as u64,
+                    "Upstream responded"
+                );
+                if response.status().is_success() {
+                    Ok(response)
+                } else {
+                    Err(UpstreamError::new(
+                        provider,
+                        UpstreamErrorKind::Status(response.status()),
+                    ))
+                }
+            });
+
+        let outcome = match &result {
+            Ok(_) => "success",
+            Err(e) => e.kind.label(),
+        };
+        metrics()
+            .upstream_requests
+            .with_label_values(&[provider, outcome])
+            .inc();
+        metrics()
+            .upstream_duration
+            .with_label_values(&[provider])
+            .observe(started.elapsed().as_secs_f64());
+
+        result
+    }
+
+    /// Sends a request and deserializes the JSON response body
+    pub async fn json<T: DeserializeOwned>(
+        &self,
+        provider: &'static str,
+        request: RequestBuilder,
+    ) -> Result<T, UpstreamError> {
+        self.send(provider, request)
+            .await?
+            .json::<T>()
+            .await
+            .map_err(|e| UpstreamError::from_reqwest(provider, e))
+    }
+
+    /// Sends a request and returns the response body as text
+    pub async fn text(
+        &self,
+        provider: &'static str,
+        request: RequestBuilder,
+    ) -> Result<String, UpstreamError> {
+        self.send(provider, request)
+            .await?
+            .text()
+            .await
+            .map_err(|e| UpstreamError::from_reqwest(provider, e))
+    }
+
+    /// Like [`json`](Self::json), but answered from the response cache for `ttl`
+    ///
+    /// Only meant for responses which don't change between requests, never for random content.
+    pub async fn cached_json<T>(
+        &self,
+        provider: &'static str,
+        ttl: Duration,
+        request: RequestBuilder,
+    ) -> Result<T, UpstreamError>
+    where
+        T: DeserializeOwned + Clone + Send + Sync + 'static,
+    {
+        let Some(key) = cache_key(provider, &request) else {
+            return self.json(provider, request).await;
+        };
+        let http = self.clone();
+        self.cache
+            .get_or_fetch(
+                provider,
+                key,
+                self.cache.ttl(provider, ttl),
+                move || async move { http.json(provider, request).await },
+            )
+            .await
+    }
+
+    /// Like [`text`](Self::text), but answered from the response cache for `ttl`
+    ///
+    /// Only meant for responses which don't change between requests, never for random content.
+    pub async fn cached_text(
+        &self,
+        provider: &'static str,
+        ttl: Duration,
+        request: RequestBuilder,
+    ) -> Result<String, UpstreamError> {
+        let Some(key) = cache_key(provider, &request) else {
+            return self.text(provider, request).await;
+        };
+        let http = self.clone();
+        self.cache
+            .get_or_fetch(
+                provider,
+                key,
+                self.cache.ttl(provider, ttl),
+                move || async move { http.text(provider, request).await },
+            )
+            .await
+    }
+}
+
+/// Identifies a request in the response cache by provider, method and URL including the query
+fn cache_key(provider: &str, request: &RequestBuilder) -> Option<String> {
+    let request = request.try_clone()?.build().ok()?;
+    Some(format!(
+        "{} {} {}",
+        provider,
+        request.method(),
+        request.url()
+    ))
+}
+
+#[cfg(test)]
+impl HttpClient {
+    /// Creates a client with short timeouts and no backoff delay for tests against a mock server
+    pub fn for_tests(max_retries: u32) -> Self {
+        Self::new(&HttpConfig {
+            timeout_secs: 1,
+            connect_timeout_secs: 1,
+            max_retries,
+            backoff_ms: 1,
+            ..HttpConfig::default()
+        })
+        .expect("Failed to create test HTTP client")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{header_exists, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn retries_server_errors_until_success() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/flaky"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .expect(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/flaky"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("ok"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let http = HttpClient::for_tests(2);
+        let body = http
+            .text("test", http.get(&format!("{}/flaky", server.uri())))
+            .await
+            .unwrap();
+
+        assert_eq!(body, "ok");
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_retries() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(3)
+            .mount(&server)
+            .await;
+
+        let http = HttpClient::for_tests(2);
+        let err = http
+            .text("test", http.get(&server.uri()))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            err.kind,
+            UpstreamErrorKind::Status(StatusCode::INTERNAL_SERVER_ERROR)
+        ));
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_client_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let http = HttpClient::for_tests(2);
+        let err = http
+            .text("test", http.get(&server.uri()))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            err.kind,
+            UpstreamErrorKind::Status(StatusCode::NOT_FOUND)
+        ));
+    }
+
+    #[tokio::test]
+    async fn times_out_hung_upstream() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(3)))
+            .mount(&server)
+            .await;
+
+        let http = HttpClient::for_tests(0);
+        let err = http
+            .text("test", http.get(&server.uri()))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err.kind, UpstreamErrorKind::Timeout));
+    }
+
+    #[tokio::test]
+    async fn caches_responses_by_url() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/cached"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("ok"))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let http = HttpClient::for_tests(0);
+        let ttl = Duration::from_secs(60);
+        let url = format!("{}/cached", server.uri());
+        for _ in 0..3 {
+            let body = http.cached_text("test", ttl, http.get(&url)).await.unwrap();
+            assert_eq!(body, "ok");
+        }
+        http.cached_text("test", ttl, http.get(&url).query(&[("mode", "today")]))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn sends_user_agent() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(header_exists("user-agent"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let http = HttpClient::for_tests(0);
+        http.text("test", http.get(&server.uri())).await.unwrap();
+    }
+}
@@ -0,0 +1,460 @@
+use crate::bot_lib::command_context::CommandContext;
+use crate::storage::Storage;
+use crate::{Data, Error};
+use fluent::{FluentArgs, FluentResource};
+use poise::serenity_prelude as serenity;
+use std::collections::HashMap;
+use std::sync::{LazyLock, RwLock};
+use tracing::warn;
+
+type FluentBundle =
+    fluent::bundle::FluentBundle<FluentResource, intl_memoizer::concurrent::IntlLangMemoizer>;
+
+/// Locale of the complete catalogue, used whenever a message is missing in another locale
+pub const DEFAULT_LOCALE: &str = "en-US";
+
+/// Message catalogues by Discord locale, compiled into the binary
+const CATALOGUES: &[(&str, &str)] = &[
+    ("en-US", include_str!("../locales/en-US.ftl")),
+    ("de", include_str!("../locales/de.ftl")),
+];
+
+/// Key under which a guild's language is stored in the guild settings
+const SETTING_KEY: &str = "locale";
+
+/// The message catalogues of every supported locale
+pub struct Translations {
+    bundles: HashMap<&'static str, FluentBundle>,
+}
+
+impl Translations {
+    /// Parses the bundled catalogues
+    fn load() -> Result<Self, Error> {
+        let mut bundles = HashMap::new();
+        for &(locale, source) in CATALOGUES {
+            let resource = FluentResource::try_new(source.to_string()).map_err(|(_, errors)| {
+                format!("Failed to parse the {} catalogue: {:?}", locale, errors)
+            })?;
+            let mut bundle = FluentBundle::new_concurrent(vec![locale.parse()?]);
+            // Isolation marks would end up inside mentions, URLs and code spans
+            bundle.set_use_isolating(false);
+            bundle.add_resource(resource).map_err(|errors| {
+                format!("Invalid messages in the {} catalogue: {:?}", locale, errors)
+            })?;
+            bundles.insert(locale, bundle);
+        }
+        Ok(Self { bundles })
+    }
+
+    /// Whether there is a catalogue for the locale
+    pub fn is_supported(&self, locale: &str) -> bool {
+        self.bundles.contains_key(locale)
+    }
+
+    /// The supported locales along with the name of their language, sorted by locale
+    pub fn languages(&self) -> Vec<(&'static str, String)> {
+        let mut languages: Vec<_> = self
+            .bundles
+            .keys()
+            .map(|&locale| (locale, self.get(locale, "language-name", None)))
+            .collect();
+        languages.sort();
+        languages
+    }
+
+    /// Formats a message or one of its attributes, if the locale's catalogue has it
+    fn lookup(
+        &self,
+        locale: &str,
+        id: &str,
+        attribute: Option<&str>,
+        args: Option<&FluentArgs<'_>>,
+    ) -> Option<String> {
+        let bundle = self.bundles.get(locale)?;
+        let message = bundle.get_message(id)?;
+        let pattern = match attribute {
+            Some(attribute) => message.get_attribute(attribute)?.value(),
+            None => message.value()?,
+        };
+
+        let mut errors = Vec::new();
+        let formatted = bundle.format_pattern(pattern, args, &mut errors);
+        if !errors.is_empty() {
+            warn!(locale, id, ?errors, "Failed to format message");
+        }
+        Some(formatted.into_owned())
+    }
+
+    /// Formats a message in the given locale, falling back to [`DEFAULT_LOCALE`] and
+    /// finally the message ID itself
+    pub fn get(&self, locale: &str, id: &str, args: Option<&FluentArgs<'_>>) -> String {
+        self.lookup(locale, id, None, args)
+            .or_else(|| self.lookup(DEFAULT_LOCALE, id, None, args))
+            .unwrap_or_else(|| {
+                warn!(locale, id, "Missing message");
+                id.to_string()
+            })
+    }
+
+    /// Fills in the descriptions of commands, parameters and choices from the catalogues,
+    /// along with their localized names and descriptions for every other locale
+    pub fn apply(&self, commands: &mut [poise::Command<Data, Error>]) {
+        for command in commands {
+            let id = command.name.to_string();
+            self.apply_to_command(command, id);
+        }
+    }
+
+    fn apply_to_command(&self, command: &mut poise::Command<Data, Error>, id: String) {
+        if let Some(description) = self.lookup(DEFAULT_LOCALE, &id, Some("description"), None) {
+            command.description = Some(description.into());
+        }
+        for parameter in &mut command.parameters {
+            let attribute = format!("{}-description", parameter.name);
+            if let Some(description) =
+                self.lookup(DEFAULT_LOCALE, &id, Some(attribute.as_str()), None)
+            {
+                parameter.description = Some(description.into());
+            }
+        }
+
+        for &locale in self
+            .bundles
+            .keys()
+            .filter(|&&locale| locale != DEFAULT_LOCALE)
+        {
+            if let Some(name) = self.lookup(locale, &id, None, None) {
+                command
+                    .name_localizations
+                    .insert(locale.into(), name.into());
+            }
+            if let Some(description) = self.lookup(locale, &id, Some("description"), None) {
+                command
+                    .description_localizations
+                    .insert(locale.into(), description.into());
+            }
+
+            for parameter in &mut command.parameters {
+                if let Some(name) = self.lookup(locale, &id, Some(&*parameter.name), None) {
+                    parameter
+                        .name_localizations
+                        .insert(locale.into(), name.into());
+                }
+                let attribute = format!("{}-description", parameter.name);
+                if let Some(description) = self.lookup(locale, &id, Some(attribute.as_str()), None)
+                {
+                    parameter
+                        .description_localizations
+                        .insert(locale.into(), description.into());
+                }
+                for choice in &mut parameter.choices {
+                    let choice_id =
+                        format!("choice-{}", choice.name.to_lowercase().replace(' ', "-"));
+                    if let Some(name) = self.lookup(locale, &choice_id, None, None) {
+                        choice.localizations.insert(locale.into(), name.into());
+                    }
+                }
+            }
+        }
+
+        for subcommand in &mut command.subcommands {
+            let subcommand_id = format!("{}-{}", id, subcommand.name);
+            self.apply_to_command(subcommand, subcommand_id);
+        }
+    }
+}
+
+static TRANSLATIONS: LazyLock<Translations> =
+    LazyLock::new(|| Translations::load().expect("Bundled catalogues are valid"));
+
+/// The process-wide message catalogues
+pub fn translations() -> &'static Translations {
+    &TRANSLATIONS
+}
+
+/// Per-guild language overrides, cached in memory as they're looked up for every reply
+#[derive(Debug, Default)]
+pub struct GuildLocales {
+    cache: RwLock<HashMap<serenity::GuildId, Option<String>>>,
+}
+
+impl GuildLocales {
+    /// Returns the language override of a guild, if any
+    pub async fn get(
+        &self,
+        storage: &Storage,
+        guild_id: serenity::GuildId,
+    ) -> Result<Option<String>, sqlx::Error> {
+        if let Some(locale) = self
+            .cache
+            .read()
+            .expect("Locale cache lock poisoned")
+            .get(&guild_id)
+        {
+            return Ok(locale.clone());
+        }
+
+        let locale = storage.guild_setting(guild_id, SETTING_KEY).await?;
+        self.cache
+            .write()
+            .expect("Locale cache lock poisoned")
+            .insert(guild_id, locale.clone());
+        Ok(locale)
+    }
+
+    /// Sets or, with `None`, removes the language override of a guild
+    pub async fn set(
+        &self,
+        storage: &Storage,
+        guild_id: serenity::GuildId,
+        locale: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        match locale {
+            Some(locale) => {
+                storage
+                    .set_guild_setting(guild_id, SETTING_KEY, locale)
+                    .await?
+            }
+            None => {
+                storage.delete_guild_setting(guild_id, SETTING_KEY).await?;
+            }
+        }
+        self.cache
+            .write()
+            .expect("Locale cache lock poisoned")
+            .insert(guild_id, locale.map(str::to_string));
+        Ok(())
+    }
+}
+
+/// Resolves the locale to reply in: the invoker's Discord language if there is a catalogue
+/// for it, otherwise the guild's language if one was set and finally [`DEFAULT_LOCALE`]
+///
+/// Prefix commands have no Discord language, so they always use the guild's language.
+pub async fn locale(ctx: impl CommandContext) -> String {
+    if let Some(locale) = ctx
+        .discord_locale()
+        .filter(|locale| translations().is_supported(locale))
+    {
+        return locale;
+    }
+    if let Some(guild_id) = ctx.guild_id() {
+        let data = ctx.data();
+        match data.locales.get(&data.storage, guild_id).await {
+            Ok(Some(locale)) if translations().is_supported(&locale) => return locale,
+            Ok(_) => {}
+            Err(e) => warn!(%guild_id, error = %e, "Failed to load guild language"),
+        }
+    }
+    DEFAULT_LOCALE.to_string()
+}
+
+/// Formats a message in the locale of the invocation, e.g. `tr!(ctx, "ping-latency", latency = 42)`
+///
+/// The arguments are only built once the locale was resolved, as they can't be held across an
+/// `.await` in a command, which has to be `Send`.
+macro_rules! tr {
+    ($ctx:expr, $id:expr $(, $name:ident = $value:expr)* $(,)?) => {{
+        let locale = $crate::bot_lib::i18n::locale($ctx).await;
+        #[allow(unused_mut)]
+        let mut args = fluent::FluentArgs::new();
+        $(args.set(stringify!($name), $value);)*
+        $crate::bot_lib::i18n::translations().get(&locale, $id, Some(&args))
+    }};
+}
+pub(crate) use tr;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot_lib::fake_context::{self, FakeContext};
+
+    /// Message IDs defined in a catalogue
+    fn message_ids(source: &str) -> Vec<&str> {
+        source
+            .lines()
+            .filter(|line| line.starts_with(|c: char| c.is_ascii_alphabetic()))
+            .filter_map(|line| line.split_once(" =").map(|(id, _)| id))
+            .collect()
+    }
+
+    #[test]
+    fn catalogues_are_valid() {
+        let translations = Translations::load().unwrap();
+        assert!(translations.is_supported(DEFAULT_LOCALE));
+
+        for &(locale, source) in CATALOGUES {
+            let ids = message_ids(source);
+            assert!(
+                ids.contains(&"language-name"),
+                "{} has no language name",
+                locale
+            );
+            for id in ids {
+                assert!(
+                    id.starts_with("choice-")
+                        || translations.bundles[DEFAULT_LOCALE].has_message(id),
+                    "{} defines {}, which {} lacks",
+                    locale,
+                    id,
+                    DEFAULT_LOCALE
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn every_command_is_described() {
+        let commands = crate::commands::all_commands();
+
+        fn check(command: &poise::Command<Data, Error>, id: String) {
+            assert!(command.description.is_some(), "{} has no description", id);
+            for parameter in &command.parameters {
+                assert!(
+                    parameter.description.is_some(),
+                    "{} has no description for {}",
+                    id,
+                    parameter.name
+                );
+            }
+            for subcommand in &command.subcommands {
+                check(subcommand, format!("{}-{}", id, subcommand.name));
+            }
+        }
+        for command in &commands {
+            check(command, command.name.to_string());
+        }
+    }
+
+    #[test]
+    fn names_and_descriptions_fit_discord_limits() {
+        let translations = Translations::load().unwrap();
+
+        fn check_name(name: &str, what: &str) {
+            let length = name.chars().count();
+            assert!(
+                (1..=32).contains(&length)
+                    && name
+                        .chars()
+                        .all(|c| c == '-' || c == '_' || c.is_alphanumeric())
+                    && name == name.to_lowercase(),
+                "{} has the invalid name {:?}",
+                what,
+                name
+            );
+        }
+        fn check_description(description: &str, what: &str) {
+            let length = description.chars().count();
+            assert!(
+                (1..=100).contains(&length),
+                "{} has a description of {} characters",
+                what,
+                length
+            );
+        }
+        fn check(translations: &Translations, command: &poise::Command<Data, Error>, id: String) {
+            if let Some(description) = &command.description {
+                check_description(description, &id);
+            }
+            for &locale in translations.bundles.keys() {
+                let what = format!("{} in {}", id, locale);
+                match translations.lookup(locale, &id, None, None) {
+                    Some(name) => check_name(&name, &what),
+                    None => check_name(&command.name, &what),
+                }
+                if let Some(description) =
+                    translations.lookup(locale, &id, Some("description"), None)
+                {
+                    check_description(&description, &what);
+                }
+                for parameter in &command.parameters {
+                    let what = format!("{} {} in {}", id, parameter.name, locale);
+                    match translations.lookup(locale, &id, Some(&*parameter.name), None) {
+                        Some(name) => check_name(&name, &what),
+                        None => check_name(&parameter.name, &what),
+                    }
+                    let attribute = format!("{}-description", parameter.name);
+                    if let Some(description) =
+                        translations.lookup(locale, &id, Some(attribute.as_str()), None)
+                    {
+                        check_description(&description, &what);
+                    }
+                }
+            }
+            for subcommand in &command.subcommands {
+                check(
+                    translations,
+                    subcommand,
+                    format!("{}-{}", id, subcommand.name),
+                );
+            }
+        }
+        for command in &crate::commands::all_commands() {
+            check(&translations, command, command.name.to_string());
+        }
+    }
+
+    #[test]
+    fn falls_back_to_default_locale() {
+        let translations = Translations::load().unwrap();
+        let mut args = FluentArgs::new();
+        args.set("latency", 42);
+
+        assert_eq!(
+            translations.get("de", "ping-latency", Some(&args)),
+            "Pong! Latenz: 42 ms"
+        );
+        assert_eq!(
+            translations.get("fr", "ping-latency", Some(&args)),
+            "Pong! Latency: 42ms"
+        );
+        assert_eq!(
+            translations.get("de", "about-source-code", None),
+            "📂 Quellcode von Coco Bot"
+        );
+        assert_eq!(
+            translations.get("de", "no-such-message", None),
+            "no-such-message"
+        );
+    }
+
+    #[tokio::test]
+    async fn discord_language_beats_guild_language() {
+        let mut ctx = FakeContext::new(fake_context::config()).await;
+        assert_eq!(locale(&ctx).await, DEFAULT_LOCALE);
+
+        let guild = serenity::GuildId::new(7);
+        ctx.guild_id = Some(guild);
+        let data = ctx.data();
+        data.locales
+            .set(&data.storage, guild, Some("de"))
+            .await
+            .unwrap();
+        assert_eq!(locale(&ctx).await, "de");
+
+        ctx.locale = Some("en-US".to_string());
+        assert_eq!(locale(&ctx).await, "en-US");
+
+        // Languages without a catalogue fall back to the guild's language
+        ctx.locale = Some("fr".to_string());
+        assert_eq!(locale(&ctx).await, "de");
+    }
+
+    #[tokio::test]
+    async fn guild_locales_are_persisted() {
+        let storage = Storage::in_memory().await.unwrap();
+        let guild = serenity::GuildId::new(7);
+        let locales = GuildLocales::default();
+
+        assert_eq!(locales.get(&storage, guild).await.unwrap(), None);
+
+        locales.set(&storage, guild, Some("de")).await.unwrap();
+        assert_eq!(
+            GuildLocales::default().get(&storage, guild).await.unwrap(),
+            Some("de".to_string())
+        );
+
+        locales.set(&storage, guild, None).await.unwrap();
+        assert_eq!(locales.get(&storage, guild).await.unwrap(), None);
+    }
+}
@@ -0,0 +1,31 @@
+use crate::Context;
+use crate::bot_lib::shutdown::InFlightGuard;
+use crate::bot_lib::{metrics, usage};
+use std::time::Instant;
+
+/// State kept for the duration of a command invocation, stored as poise invocation data
+pub struct Invocation {
+    pub started: Instant,
+    /// Keeps the command counted as running until poise drops the invocation
+    _in_flight: InFlightGuard,
+}
+
+/// `pre_command` hook, run right before a command's body
+pub async fn pre_command(ctx: Context<'_>) {
+    ctx.set_invocation_data(Invocation {
+        started: Instant::now(),
+        _in_flight: ctx.data().in_flight.enter(),
+    })
+    .await;
+}
+
+/// `post_command` hook, run once a command's body returned successfully
+pub async fn post_command(ctx: Context<'_>) {
+    finish(ctx, "success").await;
+}
+
+/// Records the outcome of an invocation in the metrics and the usage statistics
+pub async fn finish(ctx: Context<'_>, outcome: &str) {
+    metrics::record_command(ctx, outcome).await;
+    usage::record(ctx, outcome).await;
+}
@@ -0,0 +1,108 @@
+use crate::config::{LogFormat, LoggingConfig};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{InitError, RollingFileAppender};
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer, Registry};
+
+/// Whether [`Sensitive`] values are written to the logs as-is
+static LOG_SENSITIVE: AtomicBool = AtomicBool::new(false);
+
+/// A value such as message content or a user ID which is only logged when
+/// `logging.log_sensitive` is enabled, and shown as `<redacted>` otherwise
+pub struct Sensitive<T>(T);
+
+/// Wraps a value which must not end up in the logs unless explicitly allowed
+pub fn sensitive<T>(value: T) -> Sensitive<T> {
+    Sensitive(value)
+}
+
+impl<T: fmt::Display> fmt::Display for Sensitive<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if LOG_SENSITIVE.load(Ordering::Relaxed) {
+            self.0.fmt(f)
+        } else {
+            f.write_str("<redacted>")
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Sensitive<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if LOG_SENSITIVE.load(Ordering::Relaxed) {
+            self.0.fmt(f)
+        } else {
+            f.write_str("<redacted>")
+        }
+    }
+}
+
+type BoxedLayer = Box<dyn Layer<Registry> + Send + Sync>;
+
+fn format_layer<W>(format: LogFormat, writer: W, ansi: bool) -> BoxedLayer
+where
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let layer = tracing_subscriber::fmt::layer()
+        .with_writer(writer)
+        .with_ansi(ansi);
+    match format {
+        LogFormat::Text => layer.boxed(),
+        LogFormat::Json => layer
+            .json()
+            .flatten_event(true)
+            .with_current_span(true)
+            .boxed(),
+    }
+}
+
+/// Installs the global tracing subscriber according to the `[logging]` configuration
+///
+/// `RUST_LOG` takes precedence over `logging.level` when set. The returned guard flushes the
+/// log file when dropped, so it has to be kept alive for as long as the bot runs.
+pub fn init(config: &LoggingConfig) -> Result<Option<WorkerGuard>, InitError> {
+    LOG_SENSITIVE.store(config.log_sensitive, Ordering::Relaxed);
+
+    let filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(&config.level));
+
+    let mut layers: Vec<BoxedLayer> = vec![format_layer(config.format, std::io::stdout, true)];
+    let mut guard = None;
+
+    if config.file.enabled {
+        let appender = RollingFileAppender::builder()
+            .rotation(config.file.rotation.into())
+            .filename_prefix("coco-bot")
+            .filename_suffix("log")
+            .max_log_files(config.file.max_files)
+            .build(&config.file.directory)?;
+        let (writer, worker_guard) = tracing_appender::non_blocking(appender);
+        layers.push(format_layer(config.format, writer, false));
+        guard = Some(worker_guard);
+    }
+
+    tracing_subscriber::registry()
+        .with(layers.with_filter(filter))
+        .init();
+    Ok(guard)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sensitive_values_are_redacted_unless_enabled() {
+        let content = sensitive("hello there");
+        assert_eq!(content.to_string(), "<redacted>");
+        assert_eq!(format!("{:?}", content), "<redacted>");
+
+        LOG_SENSITIVE.store(true, Ordering::Relaxed);
+        assert_eq!(content.to_string(), "hello there");
+        assert_eq!(format!("{:?}", content), "\"hello there\"");
+        LOG_SENSITIVE.store(false, Ordering::Relaxed);
+    }
+}
@@ -1,10 +1,30 @@
+use crate::bot_lib::meme_templates::{Align, Anchor, FontSettings, MemeTemplate, TextBox};
 use ab_glyph::{FontRef, PxScale};
-use image::{DynamicImage, Rgba};
+use image::codecs::gif::{GifDecoder, GifEncoder, Repeat};
+use image::codecs::webp::WebPDecoder;
+use image::error::{LimitError, LimitErrorKind};
+use image::{
+    AnimationDecoder, DynamicImage, Frame, Frames, ImageDecoder, ImageError, ImageFormat,
+    ImageReader, ImageResult, Limits, Rgba, RgbaImage, imageops,
+};
 use imageproc::drawing::draw_text_mut;
+use imageproc::geometric_transformations::{Interpolation, rotate};
 use std::fs;
-use std::io;
+use std::io::{self, Cursor};
 use tracing::debug;
 
+/// Most pixels kept across all frames of an animation, about 160 MB of RGBA frames
+const MAX_ANIMATION_PIXELS: u64 = 40_000_000;
+
+/// How much a meme is scaled down each time it's too large to upload
+const SHRINK_FACTOR: f32 = 0.75;
+
+/// How often a meme is scaled down before giving up on uploading it
+const MAX_SHRINKS: usize = 4;
+
+/// Speed of the GIF encoder's color quantization, from 1 (best) to 30 (fastest)
+const GIF_SPEED: i32 = 10;
+
 // Array of default meme template directory paths to check
 const DEFAULT_MEME_TEMPLATE_PATHS: &[&str] = &[
     "./src/assets/meme_templates/",
@@ -58,7 +78,7 @@ fn get_all_template_paths() -> Vec<String> {
 /// # Returns
 ///
 /// A `Vec<String>` containing all valid template directory paths
-fn find_all_template_dirs() -> Vec<String> {
+pub fn find_all_template_dirs() -> Vec<String> {
     let all_paths = get_all_template_paths();
     let mut valid_dirs = Vec::new();
 
@@ -221,101 +241,334 @@ pub fn get_meme_template_path(
     .into())
 }
 
+/// An image to caption, keeping every frame if it's animated
+pub enum Picture {
+    Still(DynamicImage),
+    /// Frames covering the whole canvas, each with its delay
+    Animated {
+        frames: Vec<Frame>,
+        repeat: Repeat,
+    },
+}
+
+impl Picture {
+    /// An animation, or a still image if it has only one frame
+    fn animated(mut frames: Vec<Frame>, repeat: Repeat) -> ImageResult<Self> {
+        match frames.len() {
+            0 => Err(ImageError::Limits(LimitError::from_kind(
+                LimitErrorKind::DimensionError,
+            ))),
+            1 => Ok(Picture::Still(DynamicImage::ImageRgba8(
+                frames.remove(0).into_buffer(),
+            ))),
+            _ => Ok(Picture::Animated { frames, repeat }),
+        }
+    }
+
+    /// Width and height of the image or animation
+    pub fn dimensions(&self) -> (u32, u32) {
+        match self {
+            Picture::Still(image) => (image.width(), image.height()),
+            Picture::Animated { frames, .. } => frames[0].buffer().dimensions(),
+        }
+    }
+
+    /// Scales the image or every frame by `factor`, keeping frame delays
+    fn scaled(self, factor: f32) -> Self {
+        let (width, height) = self.dimensions();
+        let width = ((width as f32 * factor) as u32).max(1);
+        let height = ((height as f32 * factor) as u32).max(1);
+        match self {
+            Picture::Still(image) => {
+                Picture::Still(image.resize_exact(width, height, imageops::FilterType::Triangle))
+            }
+            Picture::Animated { frames, repeat } => Picture::Animated {
+                frames: frames
+                    .into_iter()
+                    .map(|frame| {
+                        let buffer = imageops::resize(
+                            frame.buffer(),
+                            width,
+                            height,
+                            imageops::FilterType::Triangle,
+                        );
+                        Frame::from_parts(buffer, 0, 0, frame.delay())
+                    })
+                    .collect(),
+                repeat,
+            },
+        }
+    }
+}
+
+/// Decodes an image, keeping every frame of animated GIFs and WebPs
+///
+/// # Arguments
+///
+/// * `bytes` - The encoded image
+/// * `limits` - Limits on the size of the image, also checked for each frame
+///
+/// # Returns
+///
+/// A `Result` containing the decoded picture on success, or an error if the image is
+/// malformed, in an unknown format or outside the limits. Animations with more than
+/// `MAX_ANIMATION_PIXELS` pixels across their frames fail with `ImageError::Limits`.
+pub fn decode_picture(bytes: &[u8], limits: Limits) -> ImageResult<Picture> {
+    match image::guess_format(bytes)? {
+        ImageFormat::Gif => {
+            let mut decoder = GifDecoder::new(Cursor::new(bytes))?;
+            decoder.set_limits(limits)?;
+            Picture::animated(collect_frames(decoder.into_frames())?, gif_repeat(bytes))
+        }
+        ImageFormat::WebP => {
+            let mut decoder = WebPDecoder::new(Cursor::new(bytes))?;
+            decoder.set_limits(limits)?;
+            if decoder.has_animation() {
+                Picture::animated(collect_frames(decoder.into_frames())?, webp_repeat(bytes))
+            } else {
+                Ok(Picture::Still(DynamicImage::from_decoder(decoder)?))
+            }
+        }
+        format => {
+            let mut reader = ImageReader::with_format(Cursor::new(bytes), format);
+            reader.limits(limits);
+            Ok(Picture::Still(reader.decode()?))
+        }
+    }
+}
+
+/// Collects the frames of an animation, refusing ones too large to keep in memory
+fn collect_frames(frames: Frames) -> ImageResult<Vec<Frame>> {
+    let mut collected = Vec::new();
+    let mut pixels = 0;
+    for frame in frames {
+        let frame = frame?;
+        let (width, height) = frame.buffer().dimensions();
+        pixels += u64::from(width) * u64::from(height);
+        if pixels > MAX_ANIMATION_PIXELS {
+            return Err(ImageError::Limits(LimitError::from_kind(
+                LimitErrorKind::InsufficientMemory,
+            )));
+        }
+        collected.push(frame);
+    }
+    Ok(collected)
+}
+
+/// How often a GIF plays, which `image` doesn't expose
+///
+/// The loop count is read along with the first frame, and GIFs without one play once.
+fn gif_repeat(bytes: &[u8]) -> Repeat {
+    let repeat = gif::DecodeOptions::new()
+        .read_info(bytes)
+        .and_then(|mut decoder| {
+            decoder.next_frame_info()?;
+            Ok(decoder.repeat())
+        });
+    match repeat {
+        Ok(gif::Repeat::Finite(repeats)) => Repeat::Finite(repeats),
+        Ok(gif::Repeat::Infinite) | Err(_) => Repeat::Infinite,
+    }
+}
+
+/// How often an animated WebP plays, which `image` doesn't expose
+fn webp_repeat(bytes: &[u8]) -> Repeat {
+    match image_webp::WebPDecoder::new(Cursor::new(bytes)).map(|decoder| decoder.loop_count()) {
+        // WebP counts every play, GIF only the ones after the first
+        Ok(image_webp::LoopCount::Times(plays)) => Repeat::Finite(plays.get() - 1),
+        Ok(image_webp::LoopCount::Forever) | Err(_) => Repeat::Infinite,
+    }
+}
+
 /// Generates a meme by adding text to a template image with intelligent text wrapping and sizing
 ///
+/// Animated templates are captioned on every frame.
+///
 /// # Arguments
 ///
-/// * `template_filename` - The name of the template file to use
-/// * `top_text` - Text to display at the top of the meme
-/// * `bottom_text` - Text to display at the bottom of the meme
+/// * `template` - The template to use
+/// * `captions` - Text for the template's text boxes in order; boxes without text stay blank
 ///
 /// # Returns
 ///
-/// A `Result` containing the generated meme as a `DynamicImage` on success,
+/// A `Result` containing the generated meme as a `Picture` on success,
 /// or an error if the meme generation fails.
 ///
 /// # Examples
 ///
 /// ```
-/// let meme = generate_meme("template.jpg", "TOP TEXT", "BOTTOM TEXT").unwrap();
+/// let meme = generate_meme(&template, &["TOP TEXT", "BOTTOM TEXT"]).unwrap();
 /// ```
 pub fn generate_meme(
-    template_filename: &str,
-    top_text: &str,
-    bottom_text: &str,
-) -> Result<DynamicImage, Box<dyn std::error::Error + Send + Sync>> {
+    template: &MemeTemplate,
+    captions: &[impl AsRef<str>],
+) -> Result<Picture, Box<dyn std::error::Error + Send + Sync>> {
     // Load the template image
-    let template_path = get_meme_template_path(template_filename)?;
-    let img = image::open(&template_path)?;
+    let template_path = get_meme_template_path(&template.file)?;
+    let picture = decode_picture(&fs::read(&template_path)?, Limits::default())?;
 
-    // Convert to RGBA for text rendering
-    let mut rgba_img = img.to_rgba8();
-    let (width, height) = rgba_img.dimensions();
+    caption_image(picture, &template.font, &template.boxes, captions)
+}
 
+/// Adds captions to any image, e.g. one someone uploaded, and to every frame of animations
+///
+/// # Arguments
+///
+/// * `picture` - The image or animation to caption
+/// * `settings` - Font settings used for every caption
+/// * `boxes` - Where the captions go
+/// * `captions` - Text for the boxes in order; boxes without text stay blank
+///
+/// # Returns
+///
+/// A `Result` containing the captioned picture on success,
+/// or an error if there are more captions than boxes or the font can't be loaded.
+pub fn caption_image(
+    picture: Picture,
+    settings: &FontSettings,
+    boxes: &[TextBox],
+    captions: &[impl AsRef<str>],
+) -> Result<Picture, Box<dyn std::error::Error + Send + Sync>> {
+    if captions.len() > boxes.len() {
+        return Err(format!(
+            "There is room for {} captions, but {} were given",
+            boxes.len(),
+            captions.len()
+        )
+        .into());
+    }
+
+    let captions: Vec<_> = boxes
+        .iter()
+        .copied()
+        .zip(captions.iter().map(AsRef::as_ref))
+        .collect();
+    match picture {
+        Picture::Still(image) => {
+            // Convert to RGBA for text rendering
+            let mut rgba_img = image.to_rgba8();
+            draw_captions(&mut rgba_img, settings, &captions)?;
+            Ok(Picture::Still(DynamicImage::ImageRgba8(rgba_img)))
+        }
+        Picture::Animated { mut frames, repeat } => {
+            // Frames cover the whole canvas, so the captions are drawn once and laid over each
+            let (width, height) = frames[0].buffer().dimensions();
+            let mut layer = RgbaImage::new(width, height);
+            draw_captions(&mut layer, settings, &captions)?;
+            for frame in &mut frames {
+                imageops::overlay(frame.buffer_mut(), &layer, 0, 0);
+            }
+            Ok(Picture::Animated { frames, repeat })
+        }
+    }
+}
+
+/// Draws captions into their text boxes, skipping empty ones
+///
+/// # Arguments
+///
+/// * `image` - The image to draw on
+/// * `settings` - Font settings used for every caption
+/// * `captions` - Each text box along with the text to fit into it
+pub fn draw_captions(
+    image: &mut RgbaImage,
+    settings: &FontSettings,
+    captions: &[(TextBox, &str)],
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     // Load Impact font for classic meme style (embedded at compile time)
     let font_data = include_bytes!("../assets/fonts/unicode-impact.ttf");
     let font = FontRef::try_from_slice(font_data)
         .map_err(|e| format!("Failed to load Impact font: {}", e))?;
 
-    // Text color (white with black outline)
-    let white = Rgba([255u8, 255u8, 255u8, 255u8]);
-    let black = Rgba([0u8, 0u8, 0u8, 255u8]);
-
-    // Calculate available areas for text (leaving margins)
-    let text_margin = (width as f32 * 0.05) as u32; // 5% margin on each side
-    let max_text_width = width - (text_margin * 2);
-    let max_text_height = (height as f32 * 0.2) as u32; // 20% of image height for each text area
-
-    // Draw top text
-    if !top_text.is_empty() {
-        let top_text_upper = top_text.to_uppercase();
-        let (wrapped_lines, font_size) = prepare_text_with_wrapping(
-            &font,
-            &top_text_upper,
-            max_text_width,
-            max_text_height,
-            width,
-        );
-
-        let scale = PxScale::from(font_size);
-        let line_height = (font_size * 1.2) as i32; // 120% of font size for line spacing
-        let start_y = (height as f32 * 0.05) as i32;
-
-        for (i, line) in wrapped_lines.iter().enumerate() {
-            let text_width = calculate_text_width(&font, scale, line);
-            let x = ((width as f32 - text_width) / 2.0).max(text_margin as f32) as i32;
-            let y = start_y + (i as i32 * line_height);
+    for (text_box, text) in captions {
+        if text.is_empty() {
+            continue;
+        }
 
-            draw_text_with_outline(&mut rgba_img, &font, scale, line, x, y, white, black);
+        if text_box.rotation == 0.0 {
+            draw_caption(image, &font, settings, text_box, text);
+        } else {
+            // Draw onto a transparent layer first, so only the caption is rotated
+            let (width, height) = image.dimensions();
+            let mut layer = RgbaImage::new(width, height);
+            draw_caption(&mut layer, &font, settings, text_box, text);
+
+            let center = (
+                (text_box.x + text_box.width / 2.0) * width as f32,
+                (text_box.y + text_box.height / 2.0) * height as f32,
+            );
+            let layer = rotate(
+                &layer,
+                center,
+                text_box.rotation.to_radians(),
+                Interpolation::Bilinear,
+                Rgba([0, 0, 0, 0]),
+            );
+            imageops::overlay(image, &layer, 0, 0);
         }
     }
+    Ok(())
+}
 
-    // Draw bottom text
-    if !bottom_text.is_empty() {
-        let bottom_text_upper = bottom_text.to_uppercase();
-        let (wrapped_lines, font_size) = prepare_text_with_wrapping(
-            &font,
-            &bottom_text_upper,
-            max_text_width,
-            max_text_height,
-            width,
-        );
+/// Draws a caption into its box, positioned by the box's anchor and alignment
+fn draw_caption(
+    image: &mut RgbaImage,
+    font: &FontRef,
+    settings: &FontSettings,
+    text_box: &TextBox,
+    text: &str,
+) {
+    let (width, height) = image.dimensions();
+    let box_x = text_box.x * width as f32;
+    let box_y = text_box.y * height as f32;
+    let box_width = text_box.width * width as f32;
+    let box_height = text_box.height * height as f32;
+
+    let text = if settings.uppercase {
+        text.to_uppercase()
+    } else {
+        text.to_string()
+    };
+    let (wrapped_lines, font_size) = prepare_text_with_wrapping(
+        font,
+        &text,
+        box_width as u32,
+        box_height as u32,
+        width,
+        settings.size,
+    );
 
-        let scale = PxScale::from(font_size);
-        let line_height = (font_size * 1.2) as i32; // 120% of font size for line spacing
-        let total_text_height = (wrapped_lines.len() as i32 - 1) * line_height;
-        let start_y = (height as f32 * 0.85) as i32 - total_text_height;
-        /* jscpd:ignore-start */
-        for (i, line) in wrapped_lines.iter().enumerate() {
-            let text_width = calculate_text_width(&font, scale, line);
-            let x = ((width as f32 - text_width) / 2.0).max(text_margin as f32) as i32;
-            let y = start_y + (i as i32 * line_height);
-
-            draw_text_with_outline(&mut rgba_img, &font, scale, line, x, y, white, black);
+    let scale = PxScale::from(font_size);
+    let line_height = font_size * 1.2; // 120% of font size for line spacing
+    let total_text_height = wrapped_lines.len() as f32 * line_height;
+    let start_y = match text_box.anchor {
+        Anchor::Top => box_y,
+        Anchor::Middle => box_y + (box_height - total_text_height) / 2.0,
+        Anchor::Bottom => box_y + box_height - total_text_height,
+    };
+
+    for (i, line) in wrapped_lines.iter().enumerate() {
+        let text_width = calculate_text_width(font, scale, line);
+        let x = match text_box.align {
+            Align::Left => box_x,
+            Align::Center => box_x + (box_width - text_width) / 2.0,
+            Align::Right => box_x + box_width - text_width,
         }
+        .max(box_x) as i32;
+        let y = (start_y + i as f32 * line_height) as i32;
+
+        draw_text_with_outline(
+            image,
+            font,
+            scale,
+            line,
+            x,
+            y,
+            settings.color.rgba(),
+            settings.outline_color.rgba(),
+            settings.outline_width as i32,
+        );
     }
-    /* jscpd:ignore-end */
-    Ok(DynamicImage::ImageRgba8(rgba_img))
 }
 
 /// Calculates the approximate width of text when rendered
@@ -348,9 +601,10 @@ fn prepare_text_with_wrapping(
     max_width: u32,
     max_height: u32,
     image_width: u32,
+    relative_size: f32,
 ) -> (Vec<String>, f32) {
     // Start with initial font size based on image width
-    let mut font_size = (image_width as f32 * 0.08).max(20.0);
+    let mut font_size = (image_width as f32 * relative_size).max(20.0);
     let min_font_size = 12.0;
     let max_font_size = image_width as f32 * 0.15;
 
@@ -420,9 +674,10 @@ fn wrap_text_to_lines(font: &FontRef, scale: PxScale, text: &str, max_width: f32
     lines
 }
 
-/// Draws text with a black outline for better visibility
+/// Draws text with an outline for better visibility
+#[allow(clippy::too_many_arguments)]
 fn draw_text_with_outline(
-    image: &mut image::ImageBuffer<image::Rgba<u8>, Vec<u8>>,
+    image: &mut RgbaImage,
     font: &FontRef,
     scale: PxScale,
     text: &str,
@@ -430,10 +685,11 @@ fn draw_text_with_outline(
     y: i32,
     text_color: Rgba<u8>,
     outline_color: Rgba<u8>,
+    outline_width: i32,
 ) {
-    // Draw black outline
-    for dx in -2..=2 {
-        for dy in -2..=2 {
+    // Draw outline
+    for dx in -outline_width..=outline_width {
+        for dy in -outline_width..=outline_width {
             if dx != 0 || dy != 0 {
                 draw_text_mut(image, outline_color, x + dx, y + dy, scale, font, text);
             }
@@ -443,59 +699,169 @@ fn draw_text_with_outline(
     draw_text_mut(image, text_color, x, y, scale, font, text);
 }
 
-/// Saves a generated meme to a file
-///
-/// # Arguments
-///
-/// * `meme` - The generated meme image
-/// * `output_path` - Path where the meme should be saved
-///
-/// # Returns
-///
-/// A `Result` indicating success or failure
-pub fn save_meme(
-    meme: &DynamicImage,
-    output_path: &str,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    meme.save(output_path)?;
-    Ok(())
+/// A rendered meme encoded for uploading
+pub struct EncodedMeme {
+    pub bytes: Vec<u8>,
+    /// File extension matching the format, `png` or `gif`
+    pub extension: &'static str,
 }
 
-/// Generates a meme and saves it to a temporary file
+impl EncodedMeme {
+    /// Name of the file the meme is uploaded as
+    pub fn file_name(&self) -> String {
+        format!("meme.{}", self.extension)
+    }
+}
+
+/// Encodes a meme as PNG, or as GIF if it's animated, scaling it down until it fits
 ///
 /// # Arguments
 ///
-/// * `template_filename` - The name of the template file to use
-/// * `top_text` - Text to display at the top of the meme
-/// * `bottom_text` - Text to display at the bottom of the meme
+/// * `picture` - The rendered meme
+/// * `max_bytes` - Largest file that may be uploaded
 ///
 /// # Returns
 ///
-/// A `Result` containing the file path to the generated meme on success,
-/// or an error if the meme generation fails.
-///
-/// # Examples
-///
-/// ```
-/// let meme_path = generate_meme_to_file("template.jpg", "TOP TEXT", "BOTTOM TEXT").unwrap();
-/// ```
-pub fn generate_meme_as_file_path(
-    template_filename: &str,
-    top_text: &str,
-    bottom_text: &str,
-) -> Result<std::path::PathBuf, Box<dyn std::error::Error + Send + Sync>> {
-    // Generate the meme image
-    let meme_image = generate_meme(template_filename, top_text, bottom_text)?;
-
-    // Create a unique temporary file path
-    let temp_dir = std::env::temp_dir();
-    let timestamp = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)?
-        .as_secs();
-    let temp_file_path = temp_dir.join(format!("meme_{}_{}.png", timestamp, std::process::id()));
-
-    // Save the meme to the temporary file
-    save_meme(&meme_image, temp_file_path.to_str().unwrap())?;
-
-    Ok(temp_file_path)
+/// A `Result` containing the encoded meme on success,
+/// or an error if it can't be encoded or is still too large after `MAX_SHRINKS` attempts.
+pub fn encode(
+    picture: Picture,
+    max_bytes: u64,
+) -> Result<EncodedMeme, Box<dyn std::error::Error + Send + Sync>> {
+    let mut picture = picture;
+    for shrinks in 0..=MAX_SHRINKS {
+        let encoded = encode_once(&picture)?;
+        if encoded.bytes.len() as u64 <= max_bytes {
+            debug!(
+                shrinks,
+                bytes = encoded.bytes.len(),
+                format = encoded.extension,
+                "Encoded meme"
+            );
+            return Ok(encoded);
+        }
+        picture = picture.scaled(SHRINK_FACTOR);
+    }
+
+    Err(format!(
+        "The meme is larger than the upload limit of {} bytes, even when scaled down",
+        max_bytes
+    )
+    .into())
+}
+
+fn encode_once(picture: &Picture) -> ImageResult<EncodedMeme> {
+    let mut bytes = Vec::new();
+    let extension = match picture {
+        Picture::Still(image) => {
+            image.write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)?;
+            "png"
+        }
+        Picture::Animated { frames, repeat } => {
+            let mut encoder = GifEncoder::new_with_speed(&mut bytes, GIF_SPEED);
+            encoder.set_repeat(*repeat)?;
+            encoder.encode_frames(frames.iter().cloned())?;
+            drop(encoder);
+            "gif"
+        }
+    };
+    Ok(EncodedMeme { bytes, extension })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot_lib::meme_templates::DEFAULT_BOXES;
+    use image::Delay;
+
+    /// A GIF alternating between red and blue every 100 ms
+    fn animation(repeat: Repeat) -> Vec<u8> {
+        let frames = [Rgba([255, 0, 0, 255]), Rgba([0, 0, 255, 255])]
+            .map(|color| {
+                let buffer = RgbaImage::from_pixel(160, 120, color);
+                Frame::from_parts(buffer, 0, 0, Delay::from_numer_denom_ms(100, 1))
+            })
+            .to_vec();
+        encode_once(&Picture::Animated { frames, repeat })
+            .unwrap()
+            .bytes
+    }
+
+    #[test]
+    fn captions_every_frame_of_animations() {
+        let picture = decode_picture(&animation(Repeat::Finite(2)), Limits::default()).unwrap();
+        let captioned = caption_image(
+            picture,
+            &FontSettings::default(),
+            &DEFAULT_BOXES,
+            &["top", "bottom"],
+        )
+        .unwrap();
+        let encoded = encode(captioned, u64::MAX).unwrap();
+        assert_eq!(encoded.extension, "gif");
+
+        let Picture::Animated { frames, repeat } =
+            decode_picture(&encoded.bytes, Limits::default()).unwrap()
+        else {
+            panic!("the animation became a still image");
+        };
+        assert!(matches!(repeat, Repeat::Finite(2)));
+        assert_eq!(frames.len(), 2);
+        for frame in &frames {
+            assert_eq!(frame.delay(), Delay::from_numer_denom_ms(100, 1));
+            // The white caption is drawn over the red or blue background
+            let top_box = imageops::crop_imm(frame.buffer(), 0, 0, 160, 30).to_image();
+            assert!(
+                top_box
+                    .pixels()
+                    .any(|pixel| pixel.0[..3].iter().all(|&c| c > 200))
+            );
+        }
+    }
+
+    #[test]
+    fn keeps_how_often_animations_play() {
+        for repeat in [Repeat::Finite(0), Repeat::Finite(3), Repeat::Infinite] {
+            let Ok(Picture::Animated {
+                repeat: decoded, ..
+            }) = decode_picture(&animation(repeat), Limits::default())
+            else {
+                panic!("the animation became a still image");
+            };
+            assert_eq!(format!("{:?}", decoded), format!("{:?}", repeat));
+        }
+    }
+
+    #[test]
+    fn scales_memes_down_to_fit() {
+        // Noise compresses badly, so this is far larger than the limit as a PNG
+        let noise = RgbaImage::from_fn(400, 400, |x, y| {
+            let value = (x * 7919 + y * 104729).wrapping_mul(2654435761) >> 24;
+            Rgba([value as u8, (value >> 2) as u8, (value << 3) as u8, 255])
+        });
+        let picture = Picture::Still(DynamicImage::ImageRgba8(noise));
+        let original = encode_once(&picture).unwrap().bytes.len() as u64;
+
+        let encoded = encode(picture, original / 2).unwrap();
+        assert_eq!(encoded.extension, "png");
+        assert!(encoded.bytes.len() as u64 <= original / 2);
+        let decoded = image::load_from_memory(&encoded.bytes).unwrap();
+        assert!(decoded.width() < 400);
+
+        let tiny = Picture::Still(DynamicImage::new_rgba8(400, 400));
+        assert!(encode(tiny, 10).is_err());
+    }
+
+    #[test]
+    fn saves_memes_to_disk() {
+        let picture = Picture::Still(DynamicImage::new_rgba8(16, 16));
+        let meme = encode(picture, u64::MAX).unwrap();
+        assert_eq!(meme.file_name(), "meme.png");
+
+        let path = std::env::temp_dir().join(format!("meme_test_{}.png", std::process::id()));
+        fs::write(&path, &meme.bytes).unwrap();
+        let saved = image::open(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(saved.unwrap().width(), 16);
+    }
 }
@@ -0,0 +1,403 @@
+use crate::bot_lib::http::{self, HttpClient, UpstreamError};
+use crate::bot_lib::meme_generator::{self, Picture};
+use crate::config::{HttpConfig, MemeConfig};
+use image::{ImageError, ImageFormat, Limits};
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::redirect;
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use tracing::debug;
+
+/// Redirects followed before giving up on a link
+const MAX_REDIRECTS: usize = 5;
+
+/// Provider name of image downloads in logs and metrics
+const PROVIDER: &str = "image";
+
+/// Formats accepted for images people supply to caption
+const SUPPORTED_FORMATS: &[ImageFormat] = &[
+    ImageFormat::Png,
+    ImageFormat::Jpeg,
+    ImageFormat::Gif,
+    ImageFormat::WebP,
+    ImageFormat::Bmp,
+];
+
+/// Reason an image someone supplied can't be captioned
+#[derive(Debug)]
+pub enum InvalidImage {
+    /// The link isn't an http(s) URL of a host on the internet
+    Url,
+    /// The file is larger than `memes.max_image_bytes`
+    Size,
+    /// The file isn't an image in one of the supported formats
+    Format,
+    /// The image is wider or taller than `memes.max_image_dimension`, or an animation with
+    /// too many frames of that size
+    Dimensions,
+    Download(UpstreamError),
+}
+
+impl InvalidImage {
+    /// Variant of the `meme-image-invalid` message describing this reason
+    pub fn label(&self) -> &'static str {
+        match self {
+            InvalidImage::Url => "url",
+            InvalidImage::Size => "size",
+            InvalidImage::Format => "format",
+            InvalidImage::Dimensions => "dimensions",
+            InvalidImage::Download(_) => "download",
+        }
+    }
+}
+
+impl fmt::Display for InvalidImage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidImage::Url => write!(f, "not an http(s) link to a public host"),
+            InvalidImage::Size => write!(f, "image file is too large"),
+            InvalidImage::Format => write!(f, "not an image in a supported format"),
+            InvalidImage::Dimensions => write!(f, "image is too wide, tall or long"),
+            InvalidImage::Download(e) => write!(f, "download failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for InvalidImage {}
+
+/// Whether an address can be reached from the internet
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                // "This network", 0.0.0.0/8
+                || a == 0
+                // Shared address space of carrier-grade NATs and some cloud metadata services,
+                // 100.64.0.0/10
+                || (a == 100 && b & 0xc0 == 64)
+                // Benchmarking, 198.18.0.0/15
+                || (a == 198 && b & 0xfe == 18))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(IpAddr::V4(ip)),
+            None => {
+                let segments = ip.segments();
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_unique_local()
+                    || ip.is_unicast_link_local()
+                    // Deprecated IPv4-compatible addresses, ::a.b.c.d
+                    || segments[..6] == [0; 6]
+                    // NAT64 translating to IPv4 addresses, 64:ff9b::/96 and 64:ff9b:1::/48
+                    || segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0]
+                    || segments[..3] == [0x64, 0xff9b, 1])
+            }
+        },
+    }
+}
+
+/// Checks that a link is an http(s) URL which doesn't point into the bot's own network,
+/// e.g. at its metrics server
+///
+/// Only addresses and `localhost` in the link itself can be checked here; host names resolving
+/// to private addresses are refused by the [`client`] when connecting.
+pub fn parse_url(url: &str) -> Result<reqwest::Url, InvalidImage> {
+    let url = reqwest::Url::parse(url.trim()).map_err(|_| InvalidImage::Url)?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(InvalidImage::Url);
+    }
+
+    let host = url.host_str().ok_or(InvalidImage::Url)?;
+    let public = match host.trim_matches(['[', ']']).parse::<IpAddr>() {
+        Ok(ip) => is_public(ip),
+        Err(_) => {
+            let host = host.to_ascii_lowercase();
+            host != "localhost" && !host.ends_with(".localhost")
+        }
+    };
+    if public {
+        Ok(url)
+    } else {
+        Err(InvalidImage::Url)
+    }
+}
+
+/// Resolves host names to their public addresses only, so a name can't lead into the bot's
+/// own network either
+#[derive(Debug)]
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|addr| is_public(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("{} has no public address", name.as_str()).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Creates the client images are downloaded with
+///
+/// Unlike the shared client it checks every redirect with [`parse_url`], only connects to
+/// public addresses, ignores proxies, which would resolve names themselves, and doesn't retry,
+/// as a failing server someone linked is no reason to ask it again.
+pub fn client(config: &HttpConfig) -> Result<HttpClient, reqwest::Error> {
+    let redirects = redirect::Policy::custom(|attempt| {
+        if attempt.previous().len() >= MAX_REDIRECTS {
+            attempt.error("too many redirects")
+        } else if parse_url(attempt.url().as_str()).is_err() {
+            attempt.error(InvalidImage::Url)
+        } else {
+            attempt.follow()
+        }
+    });
+    let client = http::client_builder(config)
+        .redirect(redirects)
+        .dns_resolver(Arc::new(PublicResolver))
+        .no_proxy()
+        .build()?;
+    Ok(HttpClient::with_client(client, config, 0))
+}
+
+/// Downloads and decodes the image or animation behind a link checked with [`parse_url`],
+/// within the configured limits
+///
+/// `http` must be the [`client`] made for this.
+pub async fn download(
+    http: &HttpClient,
+    url: reqwest::Url,
+    config: &MemeConfig,
+) -> Result<Picture, InvalidImage> {
+    let mut response = http
+        .send(PROVIDER, http.get(url.as_str()))
+        .await
+        .map_err(InvalidImage::Download)?;
+    if response
+        .content_length()
+        .is_some_and(|length| length > config.max_image_bytes)
+    {
+        return Err(InvalidImage::Size);
+    }
+
+    // The length may be missing or wrong, so also count while downloading
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| InvalidImage::Download(UpstreamError::from_reqwest(PROVIDER, e)))?
+    {
+        if (bytes.len() + chunk.len()) as u64 > config.max_image_bytes {
+            return Err(InvalidImage::Size);
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+
+    debug!(%url, bytes = bytes.len(), "Downloaded image to caption");
+    decode(&bytes, config)
+}
+
+/// Decodes an image in one of the supported formats, refusing large ones before decoding them
+pub fn decode(bytes: &[u8], config: &MemeConfig) -> Result<Picture, InvalidImage> {
+    let format = image::guess_format(bytes).map_err(|_| InvalidImage::Format)?;
+    if !SUPPORTED_FORMATS.contains(&format) {
+        return Err(InvalidImage::Format);
+    }
+
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(config.max_image_dimension);
+    limits.max_image_height = Some(config.max_image_dimension);
+    meme_generator::decode_picture(bytes, limits).map_err(|e| match e {
+        ImageError::Limits(_) => InvalidImage::Dimensions,
+        _ => InvalidImage::Format,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::DynamicImage;
+    use std::io::Cursor;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        DynamicImage::new_rgba8(width, height)
+            .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+            .unwrap();
+        bytes
+    }
+
+    fn http() -> HttpClient {
+        client(&HttpConfig {
+            timeout_secs: 1,
+            connect_timeout_secs: 1,
+            ..HttpConfig::default()
+        })
+        .unwrap()
+    }
+
+    fn config() -> MemeConfig {
+        MemeConfig {
+            max_image_bytes: 4096,
+            max_image_dimension: 64,
+            ..MemeConfig::default()
+        }
+    }
+
+    #[test]
+    fn refuses_links_into_private_networks() {
+        for url in [
+            "https://example.com/cat.png",
+            "http://93.184.215.14/cat.png",
+            "https://[2606:2800:21f:cb07:6820:80da:af6b:8b2c]/cat.png",
+            "http://100.63.255.255/",
+            "http://100.128.0.1/",
+            "http://198.20.0.1/",
+        ] {
+            assert!(parse_url(url).is_ok(), "refused {}", url);
+        }
+        for url in [
+            "ftp://example.com/cat.png",
+            "not a link",
+            "http://localhost:8080/metrics",
+            "http://app.localhost/",
+            "http://127.0.0.1/",
+            "http://10.0.0.2/",
+            "http://192.168.1.1/",
+            "http://169.254.169.254/latest/meta-data/",
+            "http://[::1]/",
+            "http://[::ffff:127.0.0.1]/",
+            "http://[fd00::1]/",
+            "http://0.1.2.3/",
+            "http://100.64.0.1/",
+            "http://100.100.100.200/latest/meta-data/",
+            "http://100.127.255.255/",
+            "http://198.18.0.1/",
+            "http://198.19.255.255/",
+            "http://[64:ff9b::a9fe:a9fe]/",
+            "http://[64:ff9b:1::a00:1]/",
+            "http://[::10.0.0.1]/",
+            "http://[::127.0.0.1]/",
+        ] {
+            assert!(parse_url(url).is_err(), "accepted {}", url);
+        }
+    }
+
+    #[test]
+    fn decodes_images_within_limits() {
+        assert_eq!(
+            decode(&png(64, 32), &config()).unwrap().dimensions(),
+            (64, 32)
+        );
+        assert!(matches!(
+            decode(&png(65, 32), &config()),
+            Err(InvalidImage::Dimensions)
+        ));
+        assert!(matches!(
+            decode(b"<html></html>", &config()),
+            Err(InvalidImage::Format)
+        ));
+    }
+
+    #[tokio::test]
+    async fn refuses_large_downloads() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/small.png"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(png(8, 8)))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/large.png"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(vec![0; 4097]))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/missing.png"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+
+        let http = http();
+        let url = |name: &str| reqwest::Url::parse(&format!("{}/{}", server.uri(), name)).unwrap();
+
+        assert!(download(&http, url("small.png"), &config()).await.is_ok());
+        assert!(matches!(
+            download(&http, url("large.png"), &config()).await,
+            Err(InvalidImage::Size)
+        ));
+        assert!(matches!(
+            download(&http, url("missing.png"), &config()).await,
+            Err(InvalidImage::Download(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn refuses_redirects_into_private_networks() {
+        let server = MockServer::start().await;
+        // The mock server itself listens on 127.0.0.1, which only the link is checked for
+        let secret = format!("http://127.0.0.1:{}/secret.png", server.address().port());
+        let localhost = format!("http://localhost:{}/secret.png", server.address().port());
+        for (route, location) in [("/redirect.png", &secret), ("/named.png", &localhost)] {
+            Mock::given(method("GET"))
+                .and(path(route))
+                .respond_with(
+                    ResponseTemplate::new(302).insert_header("location", location.as_str()),
+                )
+                .mount(&server)
+                .await;
+        }
+        Mock::given(method("GET"))
+            .and(path("/secret.png"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(png(8, 8)))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let http = http();
+        for route in ["redirect.png", "named.png"] {
+            let url = reqwest::Url::parse(&format!("{}/{}", server.uri(), route)).unwrap();
+            assert!(
+                matches!(
+                    download(&http, url, &config()).await,
+                    Err(InvalidImage::Download(_))
+                ),
+                "followed {}",
+                route
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn resolves_only_public_addresses() {
+        let resolved = PublicResolver.resolve("localhost".parse().unwrap()).await;
+        assert!(resolved.is_err());
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_server_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/flaky.png"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let url = reqwest::Url::parse(&format!("{}/flaky.png", server.uri())).unwrap();
+        assert!(download(&http(), url, &config()).await.is_err());
+    }
+}
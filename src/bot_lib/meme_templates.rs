@@ -0,0 +1,396 @@
+use crate::bot_lib::meme_generator;
+use image::Rgba;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use tracing::{debug, warn};
+
+/// Manifest describing the templates of a template directory, placed beside the images
+pub const MANIFEST_FILE: &str = "templates.toml";
+
+/// Most text boxes a template may have, as Discord forms hold at most five fields
+pub const MAX_BOXES: usize = 5;
+
+/// Where captions go on templates the manifest doesn't give boxes for: top and bottom
+pub const DEFAULT_BOXES: [TextBox; 2] = [
+    TextBox {
+        x: 0.05,
+        y: 0.05,
+        width: 0.9,
+        height: 0.2,
+        anchor: Anchor::Top,
+        align: Align::Center,
+        rotation: 0.0,
+    },
+    TextBox {
+        x: 0.05,
+        y: 0.75,
+        width: 0.9,
+        height: 0.2,
+        anchor: Anchor::Bottom,
+        align: Align::Center,
+        rotation: 0.0,
+    },
+];
+
+/// A template image along with how to caption it
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemeTemplate {
+    /// File name in one of the template directories
+    pub file: String,
+    pub name: String,
+    pub tags: Vec<String>,
+    /// Shown below the memes made from the template
+    pub description: Option<String>,
+    pub font: FontSettings,
+    /// Where the captions go, in order
+    pub boxes: Vec<TextBox>,
+}
+
+impl MemeTemplate {
+    /// A template without a manifest entry, named after its file
+    fn plain(file: String) -> Self {
+        let name = Path::new(&file)
+            .file_stem()
+            .map_or_else(|| file.clone(), |stem| stem.to_string_lossy().into_owned());
+        Self {
+            file,
+            name,
+            tags: Vec::new(),
+            description: None,
+            font: FontSettings::default(),
+            boxes: DEFAULT_BOXES.to_vec(),
+        }
+    }
+
+    /// Whether the file name or name starts with, or a tag is, the lowercase search term
+    pub fn matches(&self, search: &str) -> bool {
+        self.file.to_lowercase().starts_with(search)
+            || self.name.to_lowercase().starts_with(search)
+            || self.tags.iter().any(|tag| tag.to_lowercase() == search)
+    }
+}
+
+/// Finds a template by file name or, ignoring case, by name
+pub fn find<'a>(templates: &'a [MemeTemplate], query: &str) -> Option<&'a MemeTemplate> {
+    templates
+        .iter()
+        .find(|template| template.file == query)
+        .or_else(|| {
+            templates
+                .iter()
+                .find(|template| template.name.eq_ignore_ascii_case(query))
+        })
+}
+
+/// How captions of a template are drawn
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FontSettings {
+    /// Whether captions are written in capitals
+    pub uppercase: bool,
+    pub color: Color,
+    pub outline_color: Color,
+    /// Outline thickness in pixels
+    pub outline_width: u32,
+    /// Size captions start at as a fraction of the image width, shrunk until they fit their box
+    pub size: f32,
+}
+
+impl Default for FontSettings {
+    fn default() -> Self {
+        Self {
+            uppercase: true,
+            color: Color([255, 255, 255, 255]),
+            outline_color: Color([0, 0, 0, 255]),
+            outline_width: 2,
+            size: 0.08,
+        }
+    }
+}
+
+/// A colour written as `#RRGGBB` or `#RRGGBBAA`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Color(pub [u8; 4]);
+
+impl Color {
+    pub fn rgba(self) -> Rgba<u8> {
+        Rgba(self.0)
+    }
+}
+
+impl TryFrom<String> for Color {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let invalid = || format!("`{}` is not a colour like #FFFFFF", value);
+        let hex = value.strip_prefix('#').ok_or_else(invalid)?;
+        if !matches!(hex.len(), 6 | 8) || !hex.is_ascii() {
+            return Err(invalid());
+        }
+
+        let mut channels = [255; 4];
+        for (i, channel) in channels.iter_mut().enumerate().take(hex.len() / 2) {
+            *channel = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).map_err(|_| invalid())?;
+        }
+        Ok(Self(channels))
+    }
+}
+
+/// A rectangle a caption is fitted into, in fractions of the image size
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TextBox {
+    /// Left edge as a fraction of the image width
+    pub x: f32,
+    /// Top edge as a fraction of the image height
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    /// Where the caption sits if it's shorter than the box
+    #[serde(default)]
+    pub anchor: Anchor,
+    /// Where the lines of the caption sit if they're narrower than the box
+    #[serde(default)]
+    pub align: Align,
+    /// Clockwise rotation about the centre of the box in degrees
+    #[serde(default)]
+    pub rotation: f32,
+}
+
+impl TextBox {
+    fn validate(&self) -> Result<(), String> {
+        let within = |start: f32, length: f32| {
+            start.is_finite()
+                && length.is_finite()
+                && start >= 0.0
+                && length > 0.0
+                // Leeway for rounding, e.g. 0.7 + 0.3
+                && start + length <= 1.0 + f32::EPSILON * 4.0
+        };
+        if !self.rotation.is_finite() {
+            Err(format!("box {:?} has an invalid rotation", self))
+        } else if within(self.x, self.width) && within(self.y, self.height) {
+            Ok(())
+        } else {
+            Err(format!("box {:?} doesn't lie within the image", self))
+        }
+    }
+}
+
+/// Vertical position of a caption within its box
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Anchor {
+    Top,
+    #[default]
+    Middle,
+    Bottom,
+}
+
+/// Horizontal position of the lines of a caption within its box
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Align {
+    Left,
+    #[default]
+    Center,
+    Right,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Manifest {
+    template: Vec<ManifestEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ManifestEntry {
+    file: String,
+    name: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    description: Option<String>,
+    #[serde(default)]
+    font: FontSettings,
+    #[serde(default)]
+    boxes: Vec<TextBox>,
+}
+
+/// Parses and validates a manifest
+fn parse_manifest(source: &str) -> Result<Vec<MemeTemplate>, String> {
+    let manifest: Manifest = toml::from_str(source).map_err(|e| e.to_string())?;
+
+    let mut templates = Vec::new();
+    for entry in manifest.template {
+        let problem = |problem: String| format!("{}: {}", entry.file, problem);
+        for text_box in &entry.boxes {
+            text_box.validate().map_err(problem)?;
+        }
+        if entry.boxes.len() > MAX_BOXES {
+            return Err(problem(format!(
+                "at most {} boxes are supported",
+                MAX_BOXES
+            )));
+        }
+        if !(entry.font.size > 0.0 && entry.font.size <= 1.0) {
+            return Err(problem("font.size must be between 0 and 1".to_string()));
+        }
+
+        let mut template = MemeTemplate::plain(entry.file);
+        if let Some(name) = entry.name {
+            template.name = name;
+        }
+        template.tags = entry.tags;
+        template.description = entry.description;
+        template.font = entry.font;
+        if !entry.boxes.is_empty() {
+            template.boxes = entry.boxes;
+        }
+        templates.push(template);
+    }
+    Ok(templates)
+}
+
+/// Loads the templates from all template directories, described by their manifests if present
+///
+/// An invalid manifest is skipped with a warning, leaving its templates with the defaults.
+pub fn load() -> io::Result<Vec<MemeTemplate>> {
+    let files = meme_generator::get_meme_template_files()?;
+
+    // Earlier directories take precedence, as they do for the images
+    let mut described = HashMap::new();
+    for dir in meme_generator::find_all_template_dirs().iter().rev() {
+        let path = Path::new(dir).join(MANIFEST_FILE);
+        let source = match std::fs::read_to_string(&path) {
+            Ok(source) => source,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => {
+                warn!(path = %path.display(), error = %e, "Failed to read meme template manifest");
+                continue;
+            }
+        };
+        match parse_manifest(&source) {
+            Ok(templates) => {
+                debug!(path = %path.display(), count = templates.len(), "Loaded meme template manifest");
+                for template in templates {
+                    described.insert(template.file.clone(), template);
+                }
+            }
+            Err(e) => {
+                warn!(path = %path.display(), error = %e, "Ignoring invalid meme template manifest")
+            }
+        }
+    }
+
+    let templates: Vec<_> = files
+        .into_iter()
+        .map(|file| {
+            described
+                .remove(&file)
+                .unwrap_or_else(|| MemeTemplate::plain(file))
+        })
+        .collect();
+    for file in described.keys() {
+        warn!(file, "Meme template manifest describes a missing image");
+    }
+    Ok(templates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn applies_manifest_entries() {
+        let templates = parse_manifest(
+            r##"
+            [[template]]
+            file = "drake.png"
+            name = "Drake"
+            tags = ["comparison"]
+            font = { uppercase = false, color = "#000000", outline_color = "#FFFFFF80" }
+            boxes = [
+                { x = 0.5, y = 0.0, width = 0.5, height = 0.5 },
+                { x = 0.5, y = 0.5, width = 0.5, height = 0.5, anchor = "top", align = "left", rotation = -10 },
+            ]
+
+            [[template]]
+            file = "lion.jpg"
+            "##,
+        )
+        .unwrap();
+
+        let drake = &templates[0];
+        assert_eq!(drake.name, "Drake");
+        assert_eq!(drake.tags, vec!["comparison"]);
+        assert!(!drake.font.uppercase);
+        assert_eq!(drake.font.color, Color([0, 0, 0, 255]));
+        assert_eq!(drake.font.outline_color, Color([255, 255, 255, 128]));
+        assert_eq!(drake.font.size, FontSettings::default().size);
+        assert_eq!(drake.boxes.len(), 2);
+        assert_eq!(drake.boxes[0].anchor, Anchor::Middle);
+        assert_eq!(drake.boxes[0].align, Align::Center);
+        assert_eq!(drake.boxes[0].rotation, 0.0);
+        assert_eq!(drake.boxes[1].anchor, Anchor::Top);
+        assert_eq!(drake.boxes[1].align, Align::Left);
+        assert_eq!(drake.boxes[1].rotation, -10.0);
+
+        assert_eq!(templates[1], MemeTemplate::plain("lion.jpg".to_string()));
+        assert_eq!(templates[1].name, "lion");
+        assert_eq!(templates[1].boxes, DEFAULT_BOXES);
+    }
+
+    #[test]
+    fn rejects_invalid_manifests() {
+        let too_many_boxes = format!(
+            "[[template]]\nfile = \"a.png\"\nboxes = [{}]",
+            ["{ x = 0.0, y = 0.0, width = 1.0, height = 1.0 }"; MAX_BOXES + 1].join(", ")
+        );
+        for source in [
+            "[[template]]\nfile = \"a.png\"\nboxes = [{ x = 0.6, y = 0.0, width = 0.5, height = 0.5 }]",
+            "[[template]]\nfile = \"a.png\"\nboxes = [{ x = 0.0, y = 0.0, width = 0.0, height = 0.5 }]",
+            "[[template]]\nfile = \"a.png\"\nboxes = [{ x = 0.0, y = 0.0, width = 1.0, height = 1.0, align = \"justify\" }]",
+            &too_many_boxes,
+            "[[template]]\nfile = \"a.png\"\nfont = { color = \"white\" }",
+            "[[template]]\nfile = \"a.png\"\nfont = { size = 0.0 }",
+            "[[template]]\nfile = \"a.png\"\ncaptions = 3",
+            "[[template]]\nname = \"No file\"",
+        ] {
+            assert!(parse_manifest(source).is_err(), "accepted {}", source);
+        }
+    }
+
+    #[test]
+    fn bundled_manifest_is_valid() {
+        let templates =
+            parse_manifest(include_str!("../assets/meme_templates/templates.toml")).unwrap();
+        for template in templates {
+            assert!(
+                Path::new("src/assets/meme_templates")
+                    .join(&template.file)
+                    .is_file(),
+                "{} doesn't exist",
+                template.file
+            );
+        }
+    }
+
+    #[test]
+    fn finds_templates_by_file_or_name() {
+        let mut lion = MemeTemplate::plain("Lion.jpg".to_string());
+        lion.name = "Proud lion".to_string();
+        lion.tags = vec!["Animal".to_string()];
+        let templates = [lion];
+
+        assert!(find(&templates, "Lion.jpg").is_some());
+        assert!(find(&templates, "proud LION").is_some());
+        assert!(find(&templates, "Lion").is_none());
+        assert!(templates[0].matches("proud"));
+        assert!(templates[0].matches("animal"));
+        assert!(!templates[0].matches("anim"));
+    }
+}
@@ -0,0 +1,169 @@
+use crate::bot_lib::invocation::Invocation;
+use crate::{Context, Data};
+use poise::serenity_prelude as serenity;
+use prometheus::{
+    Encoder, GaugeVec, Histogram, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, Opts,
+    Registry, TextEncoder,
+};
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+
+/// How often gateway latency and guild count are sampled
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Prometheus metrics collected by the bot
+///
+/// Metrics are always recorded, serving them at `/metrics` is optional.
+pub struct Metrics {
+    registry: Registry,
+    /// Command invocations by command and outcome
+    pub command_invocations: IntCounterVec,
+    /// Time from the start of a command to its completion, by command
+    pub command_duration: HistogramVec,
+    /// Upstream API requests by provider and outcome, counting every retry attempt
+    pub upstream_requests: IntCounterVec,
+    /// Upstream API request latency by provider
+    pub upstream_duration: HistogramVec,
+    /// Response cache lookups by provider and result (`hit`, `stale` or `miss`)
+    pub cache_requests: IntCounterVec,
+    /// Time spent rendering a meme
+    pub meme_render_duration: Histogram,
+    /// Gateway heartbeat latency by shard
+    pub gateway_latency: GaugeVec,
+    /// Gateway reconnects after a lost connection, by shard
+    pub gateway_reconnects: IntCounterVec,
+    /// Number of guilds the bot is in
+    pub guilds: IntGauge,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry =
+            Registry::new_custom(Some("coco".to_string()), None).expect("Metric prefix is valid");
+
+        let command_invocations = IntCounterVec::new(
+            Opts::new("command_invocations_total", "Command invocations"),
+            &["command", "outcome"],
+        )
+        .expect("Metric is valid");
+        let command_duration = HistogramVec::new(
+            HistogramOpts::new("command_duration_seconds", "Command run time"),
+            &["command"],
+        )
+        .expect("Metric is valid");
+        let upstream_requests = IntCounterVec::new(
+            Opts::new("upstream_requests_total", "Upstream API requests"),
+            &["provider", "outcome"],
+        )
+        .expect("Metric is valid");
+        let upstream_duration = HistogramVec::new(
+            HistogramOpts::new("upstream_request_duration_seconds", "Upstream API latency"),
+            &["provider"],
+        )
+        .expect("Metric is valid");
+        let cache_requests = IntCounterVec::new(
+            Opts::new("cache_requests_total", "Response cache lookups"),
+            &["provider", "result"],
+        )
+        .expect("Metric is valid");
+        let meme_render_duration = Histogram::with_opts(HistogramOpts::new(
+            "meme_render_duration_seconds",
+            "Meme render time",
+        ))
+        .expect("Metric is valid");
+        let gateway_latency = GaugeVec::new(
+            Opts::new("gateway_latency_seconds", "Gateway heartbeat latency"),
+            &["shard"],
+        )
+        .expect("Metric is valid");
+        let gateway_reconnects = IntCounterVec::new(
+            Opts::new("gateway_reconnects_total", "Gateway reconnects"),
+            &["shard"],
+        )
+        .expect("Metric is valid");
+        let guilds = IntGauge::new("guilds", "Guilds the bot is in").expect("Metric is valid");
+
+        registry
+            .register(Box::new(command_invocations.clone()))
+            .and_then(|_| registry.register(Box::new(command_duration.clone())))
+            .and_then(|_| registry.register(Box::new(upstream_requests.clone())))
+            .and_then(|_| registry.register(Box::new(upstream_duration.clone())))
+            .and_then(|_| registry.register(Box::new(cache_requests.clone())))
+            .and_then(|_| registry.register(Box::new(meme_render_duration.clone())))
+            .and_then(|_| registry.register(Box::new(gateway_latency.clone())))
+            .and_then(|_| registry.register(Box::new(gateway_reconnects.clone())))
+            .and_then(|_| registry.register(Box::new(guilds.clone())))
+            .expect("Metrics are only registered once");
+
+        Self {
+            registry,
+            command_invocations,
+            command_duration,
+            upstream_requests,
+            upstream_duration,
+            cache_requests,
+            meme_render_duration,
+            gateway_latency,
+            gateway_reconnects,
+            guilds,
+        }
+    }
+
+    /// Renders all metrics in the Prometheus text format
+    pub fn encode(&self) -> String {
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .expect("Encoding to a Vec never fails");
+        String::from_utf8(buffer).expect("Prometheus text format is UTF-8")
+    }
+}
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+/// The process-wide metrics
+pub fn metrics() -> &'static Metrics {
+    &METRICS
+}
+
+/// Records the outcome of a command, along with its run time if it got to start
+pub async fn record_command(ctx: Context<'_>, outcome: &str) {
+    let command: &str = &ctx.command().qualified_name;
+    metrics()
+        .command_invocations
+        .with_label_values(&[command, outcome])
+        .inc();
+
+    if let Some(invocation) = ctx.invocation_data::<Invocation>().await {
+        metrics()
+            .command_duration
+            .with_label_values(&[command])
+            .observe(invocation.started.elapsed().as_secs_f64());
+    }
+}
+
+/// Periodically samples values which aren't tied to an event, also keeping the shard latencies
+/// shown by `/ping` up to date
+pub fn spawn_sampler(
+    data: Arc<Data>,
+    shard_manager: Arc<serenity::ShardManager>,
+    cache: Arc<serenity::Cache>,
+) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(SAMPLE_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            for (shard_id, runner) in shard_manager.runners.lock().await.iter() {
+                if let Some(latency) = runner.latency {
+                    data.shards.set_latency(*shard_id, latency);
+                    metrics()
+                        .gateway_latency
+                        .with_label_values(&[shard_id.to_string().as_str()])
+                        .set(latency.as_secs_f64());
+                }
+            }
+            metrics().guilds.set(cache.guilds().len() as i64);
+        }
+    });
+}
@@ -0,0 +1,68 @@
+use crate::Error;
+use crate::bot_lib::fake_context::{self, FakeContext};
+use crate::bot_lib::http::HttpClient;
+use crate::config::ApiConfig;
+use std::fmt::Debug;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// A broken upstream response which API clients must turn into an error
+#[derive(Debug, Clone, Copy)]
+pub enum Failure {
+    ServerError,
+    EmptyBody,
+    MalformedJson,
+}
+
+impl Failure {
+    /// Every failure, for clients of JSON APIs
+    pub const ALL: [Failure; 3] = [
+        Failure::ServerError,
+        Failure::EmptyBody,
+        Failure::MalformedJson,
+    ];
+
+    fn response(self) -> ResponseTemplate {
+        match self {
+            Failure::ServerError => ResponseTemplate::new(500),
+            Failure::EmptyBody => ResponseTemplate::new(200),
+            Failure::MalformedJson => ResponseTemplate::new(200).set_body_string("{\"text\": "),
+        }
+    }
+}
+
+/// Starts a server answering every GET request to `route` with `response`
+pub async fn server_responding(route: &str, response: ResponseTemplate) -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path(route))
+        .respond_with(response)
+        .mount(&server)
+        .await;
+    server
+}
+
+/// Creates a fake context whose API base URL chosen by `api` points at `server`
+pub async fn context_for(
+    server: &MockServer,
+    api: fn(&mut ApiConfig) -> &mut String,
+) -> FakeContext {
+    let mut config = fake_context::config();
+    *api(&mut config.apis) = server.uri();
+    FakeContext::new(config).await
+}
+
+/// Checks that `fetch` fails for each of the `failures` served at `route`
+///
+/// `fetch` gets a client retrying once and the base URL of the mock server.
+pub async fn assert_rejects<T: Debug, F: Future<Output = Result<T, Error>>>(
+    route: &str,
+    failures: &[Failure],
+    fetch: impl Fn(HttpClient, String) -> F,
+) {
+    for &failure in failures {
+        let server = server_responding(route, failure.response()).await;
+        let result = fetch(HttpClient::for_tests(1), server.uri()).await;
+        assert!(result.is_err(), "accepted {:?}: {:?}", failure, result);
+    }
+}
@@ -1 +1,23 @@
+pub mod cache;
+pub mod checks;
+pub mod command_context;
+pub mod command_rules;
+pub mod content_packs;
+pub mod fake_context;
+pub mod health;
+pub mod http;
+pub mod i18n;
+pub mod invocation;
+pub mod logging;
 pub mod meme_generator;
+pub mod meme_images;
+pub mod meme_templates;
+pub mod metrics;
+#[cfg(test)]
+pub mod mock_api;
+pub mod prefix;
+pub mod registration;
+pub mod server;
+pub mod shards;
+pub mod shutdown;
+pub mod usage;
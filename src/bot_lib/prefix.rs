@@ -0,0 +1,167 @@
+use crate::storage::Storage;
+use crate::{Data, Error};
+use poise::serenity_prelude as serenity;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::RwLock;
+
+/// Key under which a guild's prefix override is stored in the guild settings
+const SETTING_KEY: &str = "prefix";
+
+/// Longest prefix accepted, so prefixes stay something people can actually type
+pub const MAX_PREFIX_LEN: usize = 10;
+
+/// Reason a prefix can't be used
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidPrefix {
+    Empty,
+    Whitespace,
+    TooLong,
+}
+
+impl InvalidPrefix {
+    /// Variant of the `config-prefix-invalid` message describing this reason
+    pub fn label(self) -> &'static str {
+        match self {
+            InvalidPrefix::Empty => "empty",
+            InvalidPrefix::Whitespace => "whitespace",
+            InvalidPrefix::TooLong => "too-long",
+        }
+    }
+}
+
+impl fmt::Display for InvalidPrefix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidPrefix::Empty => write!(f, "must not be empty"),
+            InvalidPrefix::Whitespace => write!(f, "must not contain whitespace"),
+            InvalidPrefix::TooLong => {
+                write!(f, "must be at most {} characters long", MAX_PREFIX_LEN)
+            }
+        }
+    }
+}
+
+/// Checks whether a prefix can be used, returning the reason if not
+pub fn validate_prefix(prefix: &str) -> Result<(), InvalidPrefix> {
+    if prefix.is_empty() {
+        Err(InvalidPrefix::Empty)
+    } else if prefix.chars().any(char::is_whitespace) {
+        Err(InvalidPrefix::Whitespace)
+    } else if prefix.chars().count() > MAX_PREFIX_LEN {
+        Err(InvalidPrefix::TooLong)
+    } else {
+        Ok(())
+    }
+}
+
+/// Per-guild prefix overrides, cached in memory as they're looked up for every message
+#[derive(Debug, Default)]
+pub struct GuildPrefixes {
+    cache: RwLock<HashMap<serenity::GuildId, Option<String>>>,
+}
+
+impl GuildPrefixes {
+    /// Returns the prefix override of a guild, if any
+    pub async fn get(
+        &self,
+        storage: &Storage,
+        guild_id: serenity::GuildId,
+    ) -> Result<Option<String>, sqlx::Error> {
+        if let Some(prefix) = self
+            .cache
+            .read()
+            .expect("Prefix cache lock poisoned")
+            .get(&guild_id)
+        {
+            return Ok(prefix.clone());
+        }
+
+        let prefix = storage.guild_setting(guild_id, SETTING_KEY).await?;
+        self.cache
+            .write()
+            .expect("Prefix cache lock poisoned")
+            .insert(guild_id, prefix.clone());
+        Ok(prefix)
+    }
+
+    /// Sets or, with `None`, removes the prefix override of a guild
+    pub async fn set(
+        &self,
+        storage: &Storage,
+        guild_id: serenity::GuildId,
+        prefix: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        match prefix {
+            Some(prefix) => {
+                storage
+                    .set_guild_setting(guild_id, SETTING_KEY, prefix)
+                    .await?
+            }
+            None => {
+                storage.delete_guild_setting(guild_id, SETTING_KEY).await?;
+            }
+        }
+        self.cache
+            .write()
+            .expect("Prefix cache lock poisoned")
+            .insert(guild_id, prefix.map(str::to_string));
+        Ok(())
+    }
+}
+
+/// Resolves the prefix for a message: the guild's override, or the configured default
+pub async fn dynamic_prefix(
+    ctx: poise::PartialContext<'_, Data, Error>,
+) -> Result<Option<String>, Error> {
+    let data = ctx.serenity_context.data::<Data>();
+    let default = &data.config.prefix.prefix;
+
+    let Some(guild_id) = ctx.guild_id else {
+        return Ok(Some(default.clone()));
+    };
+    let prefix = data.prefixes.get(&data.storage, guild_id).await?;
+    Ok(Some(prefix.unwrap_or_else(|| default.clone())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_unusable_prefixes() {
+        assert!(validate_prefix("coco!").is_ok());
+        assert_eq!(validate_prefix(""), Err(InvalidPrefix::Empty));
+        assert_eq!(validate_prefix("co co"), Err(InvalidPrefix::Whitespace));
+        assert_eq!(
+            validate_prefix("waytoolongprefix"),
+            Err(InvalidPrefix::TooLong)
+        );
+    }
+
+    #[tokio::test]
+    async fn overrides_are_persisted_and_cached() {
+        let storage = Storage::in_memory().await.unwrap();
+        let guild = serenity::GuildId::new(7);
+        let prefixes = GuildPrefixes::default();
+
+        assert_eq!(prefixes.get(&storage, guild).await.unwrap(), None);
+
+        prefixes.set(&storage, guild, Some("?")).await.unwrap();
+        assert_eq!(
+            prefixes.get(&storage, guild).await.unwrap(),
+            Some("?".to_string())
+        );
+        assert_eq!(
+            GuildPrefixes::default().get(&storage, guild).await.unwrap(),
+            Some("?".to_string())
+        );
+
+        prefixes.set(&storage, guild, None).await.unwrap();
+        assert_eq!(prefixes.get(&storage, guild).await.unwrap(), None);
+        assert_eq!(
+            storage.guild_setting(guild, SETTING_KEY).await.unwrap(),
+            None
+        );
+    }
+}
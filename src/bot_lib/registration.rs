@@ -0,0 +1,347 @@
+use crate::config::RegistrationMode;
+use crate::{Data, Error};
+use poise::serenity_prelude as serenity;
+use std::fmt;
+use tracing::{debug, error, info, warn};
+
+/// Where a set of application commands is registered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    Global,
+    Guild(serenity::GuildId),
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Scope::Global => write!(f, "global"),
+            Scope::Guild(guild_id) => write!(f, "guild {}", guild_id),
+        }
+    }
+}
+
+/// Prefix of the keys under which the last registered command sets are stored
+const STATE_KEY_PREFIX: &str = "registered_commands:";
+
+impl Scope {
+    /// Key under which the last registered command set for this scope is stored
+    fn state_key(&self) -> String {
+        match self {
+            Scope::Global => format!("{}global", STATE_KEY_PREFIX),
+            Scope::Guild(guild_id) => format!("{}guild:{}", STATE_KEY_PREFIX, guild_id),
+        }
+    }
+
+    /// The scope a key written by [`Scope::state_key`] belongs to
+    fn from_state_key(key: &str) -> Option<Self> {
+        match key.strip_prefix(STATE_KEY_PREFIX)? {
+            "global" => Some(Scope::Global),
+            guild => guild
+                .strip_prefix("guild:")?
+                .parse()
+                .ok()
+                .filter(|&id| id != 0)
+                .map(|id| Scope::Guild(serenity::GuildId::new(id))),
+        }
+    }
+}
+
+/// What a registration run does in a scope
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    /// Push the commands, as they changed since they were last registered
+    Register,
+    /// Push the commands only if Discord's set differs, as it may have been changed by
+    /// someone else
+    Verify,
+    /// Remove the commands, as the scope is no longer configured
+    Remove,
+}
+
+/// Decides what to do in each configured scope and each scope registered earlier
+///
+/// # Arguments
+///
+/// * `configured` - The scopes the configuration asks for
+/// * `recorded` - Scopes registered earlier, with the command set pushed to them
+/// * `desired` - The command set to register
+/// * `force` - Register in all configured scopes, even if they look up to date
+fn plan(
+    configured: &[Scope],
+    recorded: &[(Scope, String)],
+    desired: &str,
+    force: bool,
+) -> Vec<(Scope, Step)> {
+    let stored = |scope: &Scope| {
+        recorded
+            .iter()
+            .find(|(recorded, _)| recorded == scope)
+            .map(|(_, commands)| commands.as_str())
+    };
+    let mut steps: Vec<_> = configured
+        .iter()
+        .map(|&scope| match stored(&scope) {
+            Some(commands) if commands == desired && !force => (scope, Step::Verify),
+            _ => (scope, Step::Register),
+        })
+        .collect();
+    steps.extend(
+        recorded
+            .iter()
+            .filter(|(scope, _)| !configured.contains(scope))
+            .map(|&(scope, _)| (scope, Step::Remove)),
+    );
+    steps
+}
+
+/// Summary of a registration run
+#[derive(Debug, Default)]
+pub struct Summary {
+    /// Scopes the commands were pushed to
+    pub registered: Vec<Scope>,
+    /// Scopes which were already up to date
+    pub unchanged: Vec<Scope>,
+    /// Scopes no longer configured, whose commands were removed
+    pub removed: Vec<Scope>,
+    /// Scopes which failed, along with the reason
+    pub failed: Vec<(Scope, String)>,
+}
+
+/// The scopes to register to according to the configuration
+fn configured_scopes(data: &Data) -> Vec<Scope> {
+    let registration = &data.config.registration;
+    match registration.mode {
+        RegistrationMode::Global => vec![Scope::Global],
+        RegistrationMode::Guilds => registration
+            .dev_guilds
+            .iter()
+            .map(|&id| Scope::Guild(serenity::GuildId::new(id)))
+            .collect(),
+    }
+}
+
+/// Scopes commands were registered in earlier, with the command set pushed to them
+async fn recorded_scopes(data: &Data) -> Result<Vec<(Scope, String)>, Error> {
+    let states = data.storage.states(STATE_KEY_PREFIX).await?;
+    Ok(states
+        .into_iter()
+        .filter_map(|(key, commands)| Some((Scope::from_state_key(&key)?, commands)))
+        .collect())
+}
+
+/// Replaces the commands in a scope, recording what was pushed
+async fn push(
+    http: &serenity::Http,
+    data: &Data,
+    commands: &[poise::Command<Data, Error>],
+    scope: Scope,
+) -> Result<(), Error> {
+    match scope {
+        Scope::Global => poise::builtins::register_globally(http, commands).await?,
+        Scope::Guild(guild_id) => {
+            poise::builtins::register_in_guild(http, commands, guild_id).await?
+        }
+    }
+    if commands.is_empty() {
+        data.storage.delete_state(&scope.state_key()).await?;
+    } else {
+        let pushed =
+            serde_json::to_string(&poise::builtins::create_application_commands(commands))?;
+        data.storage.set_state(&scope.state_key(), &pushed).await?;
+    }
+    Ok(())
+}
+
+/// Names of the commands currently registered with Discord in the given scope
+async fn remote_command_names(http: &serenity::Http, scope: Scope) -> Result<Vec<String>, Error> {
+    let commands = match scope {
+        Scope::Global => http.get_global_commands().await?,
+        Scope::Guild(guild_id) => http.get_guild_commands(guild_id).await?,
+    };
+    let mut names: Vec<String> = commands.iter().map(|c| c.name.to_string()).collect();
+    names.sort();
+    Ok(names)
+}
+
+/// Carries out a step in a scope, returning whether anything was pushed to Discord
+async fn run_step(
+    http: &serenity::Http,
+    data: &Data,
+    commands: &[poise::Command<Data, Error>],
+    scope: Scope,
+    step: Step,
+) -> Result<bool, Error> {
+    match step {
+        Step::Register => push(http, data, commands, scope).await?,
+        Step::Verify => {
+            let mut local_names: Vec<String> =
+                commands.iter().map(|c| c.name.to_string()).collect();
+            local_names.sort();
+            if remote_command_names(http, scope).await? == local_names {
+                return Ok(false);
+            }
+            push(http, data, commands, scope).await?;
+        }
+        Step::Remove => push(http, data, &[], scope).await?,
+    }
+    Ok(true)
+}
+
+/// Registers the commands in all configured scopes, skipping scopes which are up to date
+///
+/// Commands are removed from scopes they were registered in earlier which are no longer
+/// configured, e.g. globally after switching to `registration.mode = "guilds"`.
+/// Failures are logged and reported in the summary instead of aborting, so a transient
+/// Discord error doesn't take the bot down.
+pub async fn sync_commands(
+    http: &serenity::Http,
+    data: &Data,
+    commands: &[poise::Command<Data, Error>],
+    force: bool,
+) -> Summary {
+    let mut summary = Summary::default();
+    let steps = match plan_sync(data, commands, force).await {
+        Ok(steps) => steps,
+        Err(e) => {
+            error!(error = %e, "Failed to plan command registration");
+            for scope in configured_scopes(data) {
+                summary.failed.push((scope, e.to_string()));
+            }
+            return summary;
+        }
+    };
+
+    for (scope, step) in steps {
+        match run_step(http, data, commands, scope, step).await {
+            Ok(true) if step == Step::Remove => {
+                info!(%scope, "Commands removed from scope which is no longer configured");
+                summary.removed.push(scope);
+            }
+            Ok(true) => {
+                info!(%scope, command_count = commands.len(), "Commands registered");
+                summary.registered.push(scope);
+            }
+            Ok(false) => {
+                debug!(%scope, "Commands unchanged, skipping registration");
+                summary.unchanged.push(scope);
+            }
+            Err(e) => {
+                error!(%scope, error = %e, "Failed to register commands");
+                summary.failed.push((scope, e.to_string()));
+            }
+        }
+    }
+
+    summary
+}
+
+async fn plan_sync(
+    data: &Data,
+    commands: &[poise::Command<Data, Error>],
+    force: bool,
+) -> Result<Vec<(Scope, Step)>, Error> {
+    let desired = serde_json::to_string(&poise::builtins::create_application_commands(commands))?;
+    let recorded = recorded_scopes(data).await?;
+    Ok(plan(&configured_scopes(data), &recorded, &desired, force))
+}
+
+/// Removes all commands from the configured scopes and those registered in earlier
+pub async fn unregister_commands(http: &serenity::Http, data: &Data) -> Summary {
+    let mut scopes = configured_scopes(data);
+    match recorded_scopes(data).await {
+        Ok(recorded) => {
+            for (scope, _) in recorded {
+                if !scopes.contains(&scope) {
+                    scopes.push(scope);
+                }
+            }
+        }
+        Err(e) => warn!(error = %e, "Failed to look up scopes registered earlier"),
+    }
+
+    let mut summary = Summary::default();
+    for scope in scopes {
+        match push(http, data, &[], scope).await {
+            Ok(()) => {
+                info!(%scope, "Commands unregistered");
+                summary.registered.push(scope);
+            }
+            Err(e) => {
+                error!(%scope, error = %e, "Failed to unregister commands");
+                summary.failed.push((scope, e.to_string()));
+            }
+        }
+    }
+
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn guild(id: u64) -> Scope {
+        Scope::Guild(serenity::GuildId::new(id))
+    }
+
+    #[test]
+    fn state_keys_round_trip() {
+        for scope in [Scope::Global, guild(42)] {
+            assert_eq!(Scope::from_state_key(&scope.state_key()), Some(scope));
+        }
+        for key in [
+            "registered_commands:guild:0",
+            "registered_commands:guild:abc",
+            "registered_commands:elsewhere",
+            "prefix:global",
+        ] {
+            assert_eq!(Scope::from_state_key(key), None, "parsed {}", key);
+        }
+    }
+
+    #[test]
+    fn verifies_unchanged_and_registers_changed_scopes() {
+        let recorded = [(guild(1), "v2".to_string()), (guild(2), "v1".to_string())];
+        let configured = [guild(1), guild(2), guild(3)];
+
+        assert_eq!(
+            plan(&configured, &recorded, "v2", false),
+            [
+                (guild(1), Step::Verify),
+                (guild(2), Step::Register),
+                (guild(3), Step::Register),
+            ]
+        );
+        assert_eq!(
+            plan(&configured, &recorded, "v2", true),
+            [
+                (guild(1), Step::Register),
+                (guild(2), Step::Register),
+                (guild(3), Step::Register),
+            ]
+        );
+    }
+
+    #[test]
+    fn removes_scopes_no_longer_configured() {
+        // Switched from global registration to a dev guild, which dropped another one
+        let recorded = [
+            (Scope::Global, "v1".to_string()),
+            (guild(1), "v1".to_string()),
+            (guild(2), "v1".to_string()),
+        ];
+
+        assert_eq!(
+            plan(&[guild(1)], &recorded, "v1", false),
+            [
+                (guild(1), Step::Verify),
+                (Scope::Global, Step::Remove),
+                (guild(2), Step::Remove),
+            ]
+        );
+        assert_eq!(
+            plan(&[Scope::Global], &[], "v1", false),
+            [(Scope::Global, Step::Register)]
+        );
+    }
+}
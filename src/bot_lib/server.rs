@@ -0,0 +1,72 @@
+use crate::Data;
+use crate::bot_lib::health::{Readiness, Version};
+use crate::bot_lib::metrics::metrics;
+use axum::extract::State;
+use axum::http::{StatusCode, header};
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Json, Router};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tracing::{error, info};
+
+/// `/healthz`: the process is alive and serving requests
+async fn healthz() -> &'static str {
+    "ok"
+}
+
+/// `/readyz`: the bot is connected and able to handle commands
+async fn readyz(State(data): State<Arc<Data>>) -> impl IntoResponse {
+    let readiness = Readiness::of(&data);
+    let status = if readiness.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(readiness))
+}
+
+/// `/version`: build information
+async fn version() -> Json<Version> {
+    Json(Version::CURRENT)
+}
+
+/// `/metrics`: Prometheus metrics
+async fn prometheus_metrics() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, prometheus::TEXT_FORMAT)],
+        metrics().encode(),
+    )
+}
+
+fn router(data: Arc<Data>) -> Router {
+    let mut router = Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .route("/version", get(version));
+    if data.config.metrics.enabled {
+        router = router.route("/metrics", get(prometheus_metrics));
+    }
+    router.with_state(data)
+}
+
+/// Serves the health, readiness, version and (if enabled) metrics endpoints
+///
+/// Failing to bind is logged rather than fatal, the bot itself works fine without them.
+pub async fn serve(data: Arc<Data>) {
+    let listen: SocketAddr = data.config.server.listen;
+    let app = router(data);
+
+    let listener = match tokio::net::TcpListener::bind(listen).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!(%listen, error = %e, "Failed to bind HTTP server");
+            return;
+        }
+    };
+    info!(%listen, "Serving health endpoints");
+
+    if let Err(e) = axum::serve(listener, app).await {
+        error!(error = %e, "HTTP server failed");
+    }
+}
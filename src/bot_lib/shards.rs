@@ -0,0 +1,187 @@
+use crate::bot_lib::metrics::metrics;
+use crate::config::{ShardingConfig, ShardingMode};
+use poise::serenity_prelude as serenity;
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Connection state of a single shard as last reported by the gateway
+#[derive(Debug, Clone)]
+pub struct ShardState {
+    pub stage: serenity::ConnectionStage,
+    /// Last heartbeat latency, sampled periodically
+    pub latency: Option<Duration>,
+    /// How often the shard connected again after losing its connection
+    pub reconnects: u32,
+    /// Whether the shard was connected at some point, to tell reconnects from the first connect
+    was_connected: bool,
+}
+
+/// Coarse connection state, identifying it in the message catalogues
+pub fn stage_label(stage: serenity::ConnectionStage) -> &'static str {
+    match stage {
+        serenity::ConnectionStage::Connected => "connected",
+        serenity::ConnectionStage::Disconnected => "disconnected",
+        _ => "connecting",
+    }
+}
+
+/// The states of the shards run by this process
+#[derive(Debug, Default)]
+pub struct Shards {
+    states: RwLock<BTreeMap<u16, ShardState>>,
+}
+
+impl Shards {
+    /// Records a stage change of a shard, tracing disconnects and reconnects
+    pub fn set_stage(&self, shard_id: serenity::ShardId, stage: serenity::ConnectionStage) {
+        let mut states = self.states.write().expect("Shard state lock poisoned");
+        let state = states.entry(shard_id.0).or_insert_with(|| ShardState {
+            stage: serenity::ConnectionStage::Disconnected,
+            latency: None,
+            reconnects: 0,
+            was_connected: false,
+        });
+        // Both the ready event and the stage update report a shard as connected
+        let previous = state.stage;
+        if previous == stage {
+            return;
+        }
+        state.stage = stage;
+
+        match stage {
+            serenity::ConnectionStage::Connected if state.was_connected => {
+                state.reconnects += 1;
+                metrics()
+                    .gateway_reconnects
+                    .with_label_values(&[shard_id.to_string().as_str()])
+                    .inc();
+                info!(
+                    %shard_id,
+                    reconnects = state.reconnects,
+                    "Shard reconnected"
+                );
+            }
+            serenity::ConnectionStage::Connected => {
+                state.was_connected = true;
+                info!(%shard_id, "Shard connected");
+            }
+            _ if previous == serenity::ConnectionStage::Connected => {
+                warn!(%shard_id, stage = ?stage, "Shard lost its connection");
+            }
+            _ => info!(%shard_id, from = ?previous, to = ?stage, "Shard stage changed"),
+        }
+    }
+
+    /// Records the latest heartbeat latency of a shard
+    pub fn set_latency(&self, shard_id: serenity::ShardId, latency: Duration) {
+        if let Some(state) = self
+            .states
+            .write()
+            .expect("Shard state lock poisoned")
+            .get_mut(&shard_id.0)
+        {
+            state.latency = Some(latency);
+        }
+    }
+
+    /// Whether at least one shard is known and all of them are connected
+    pub fn all_connected(&self) -> bool {
+        let states = self.states.read().expect("Shard state lock poisoned");
+        !states.is_empty()
+            && states
+                .values()
+                .all(|state| state.stage == serenity::ConnectionStage::Connected)
+    }
+
+    /// The state of every shard, by shard ID
+    pub fn snapshot(&self) -> Vec<(u16, ShardState)> {
+        self.states
+            .read()
+            .expect("Shard state lock poisoned")
+            .iter()
+            .map(|(&id, state)| (id, state.clone()))
+            .collect()
+    }
+}
+
+/// Connects the client's shards as configured and runs until they are shut down
+pub async fn start(
+    client: &mut serenity::Client,
+    config: &ShardingConfig,
+) -> Result<(), serenity::Error> {
+    match (config.mode, config.total()) {
+        (ShardingMode::Single, _) => {
+            info!("Starting a single shard");
+            client.start().await
+        }
+        (ShardingMode::Manual, Some(total)) => match config.range {
+            Some([first, last]) => {
+                info!(first, last, %total, "Starting shard range");
+                client.start_shard_range(first..last + 1, total).await
+            }
+            None => {
+                info!(%total, "Starting all shards");
+                client.start_shards(total).await
+            }
+        },
+        // Validation rejects manual mode without a total
+        (ShardingMode::Auto | ShardingMode::Manual, _) => {
+            info!("Starting the recommended number of shards");
+            client.start_autosharded().await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serenity::ConnectionStage;
+
+    fn reconnects(shards: &Shards, shard_id: u16) -> u32 {
+        shards
+            .snapshot()
+            .into_iter()
+            .find(|&(id, _)| id == shard_id)
+            .map(|(_, state)| state.reconnects)
+            .unwrap()
+    }
+
+    #[test]
+    fn counts_reconnects_but_not_the_first_connect() {
+        let shards = Shards::default();
+        let shard = serenity::ShardId(0);
+        assert!(!shards.all_connected());
+
+        shards.set_stage(shard, ConnectionStage::Connecting);
+        assert!(!shards.all_connected());
+        shards.set_stage(shard, ConnectionStage::Connected);
+        // The ready event reports the connection again
+        shards.set_stage(shard, ConnectionStage::Connected);
+        assert!(shards.all_connected());
+        assert_eq!(reconnects(&shards, 0), 0);
+
+        shards.set_stage(shard, ConnectionStage::Resuming);
+        assert!(!shards.all_connected());
+        shards.set_stage(shard, ConnectionStage::Connected);
+        assert!(shards.all_connected());
+        assert_eq!(reconnects(&shards, 0), 1);
+
+        shards.set_stage(shard, ConnectionStage::Disconnected);
+        shards.set_stage(shard, ConnectionStage::Connected);
+        assert_eq!(reconnects(&shards, 0), 2);
+    }
+
+    #[test]
+    fn waits_for_every_shard() {
+        let shards = Shards::default();
+        shards.set_stage(serenity::ShardId(0), ConnectionStage::Connected);
+        shards.set_stage(serenity::ShardId(1), ConnectionStage::Handshake);
+        assert!(!shards.all_connected());
+
+        shards.set_stage(serenity::ShardId(1), ConnectionStage::Connected);
+        assert!(shards.all_connected());
+        assert_eq!(reconnects(&shards, 1), 0);
+    }
+}
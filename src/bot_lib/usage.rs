@@ -0,0 +1,70 @@
+use crate::Context;
+use crate::bot_lib::invocation::Invocation;
+use crate::storage::{CommandUsage, Storage};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+/// Outcomes counted as failures, as opposed to refusals like cooldowns or failed checks
+const FAILED_OUTCOMES: &[&str] = &["error", "upstream_error", "panic"];
+
+/// How often old invocations are pruned
+const PRUNE_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Current Unix timestamp in seconds
+pub fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() as i64)
+}
+
+/// Records a finished invocation in the database, without holding up the reply
+///
+/// The insert counts as in flight, so shutdown waits for it before closing the database.
+pub async fn record(ctx: Context<'_>, outcome: &str) {
+    let data = ctx.data();
+    if !data.config.analytics.enabled {
+        return;
+    }
+
+    let latency = ctx
+        .invocation_data::<Invocation>()
+        .await
+        .map(|invocation| invocation.started.elapsed());
+    let usage = CommandUsage {
+        command: ctx.command().qualified_name.to_string(),
+        guild_id: ctx.guild_id(),
+        channel_id: ctx.channel_id(),
+        user_id: ctx.author().id,
+        outcome: outcome.to_string(),
+        failed: FAILED_OUTCOMES.contains(&outcome),
+        latency,
+    };
+
+    let storage = data.storage.clone();
+    let in_flight = data.in_flight.enter();
+    tokio::spawn(async move {
+        let _in_flight = in_flight;
+        if let Err(e) = storage.record_usage(&usage).await {
+            warn!(command = %usage.command, error = %e, "Failed to record command usage");
+        }
+    });
+}
+
+/// Deletes invocations older than the retention period on startup and once a day
+pub fn spawn_pruner(storage: Storage, retention_days: u64) {
+    let retention = retention_days.saturating_mul(24 * 60 * 60) as i64;
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(PRUNE_INTERVAL);
+        loop {
+            interval.tick().await;
+            match storage
+                .prune_usage(unix_now().saturating_sub(retention))
+                .await
+            {
+                Ok(0) => {}
+                Ok(removed) => info!(removed, "Pruned old command usage"),
+                Err(e) => warn!(error = %e, "Failed to prune command usage"),
+            }
+        }
+    });
+}
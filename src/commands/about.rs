@@ -1,3 +1,5 @@
+use crate::bot_lib::command_context::CommandContext;
+use crate::bot_lib::i18n::tr;
 use crate::{Context, Error};
 use ::serenity::all::Timestamp;
 use poise::serenity_prelude as serenity;
@@ -15,11 +17,12 @@ fn to_title_case(s: &str) -> String {
         .join(" ")
 }
 
-#[poise::command(
-    slash_command,
-    description_localized("en-US", "Get information about the bot.")
-)]
+#[poise::command(slash_command, prefix_command, category = "Utility", track_edits)]
 pub async fn about(ctx: Context<'_>) -> Result<(), Error> {
+    run(ctx).await
+}
+
+pub async fn run(ctx: impl CommandContext) -> Result<(), Error> {
     let version = env!("CARGO_PKG_VERSION");
     let name = env!("CARGO_PKG_NAME");
     let repository = env!("CARGO_PKG_REPOSITORY");
@@ -47,42 +50,69 @@ pub async fn about(ctx: Context<'_>) -> Result<(), Error> {
     };
 
     let embed = serenity::CreateEmbed::default()
-        .title(format!("About {}", to_title_case(&name.replace("-", " "))))
+        .title(tr!(
+            ctx,
+            "about-title",
+            name = to_title_case(&name.replace("-", " "))
+        ))
         .description(format!(
-            "This bot is a Rust port of [KittyBot](https://github.com/olliequ/KittyBot) for the CS@unimelb Discord server.\n\n\
-            **Version:** `{}`\n\
-            **Git Hash:** [`{}`](https://github.com/MRDGH2821/Coco-Bot/tree/{})\n\
-            **Git Branch:** [`{}`](https://github.com/MRDGH2821/Coco-Bot/tree/{})",
-            version, git_hash, git_hash, git_branch,git_branch
+            "{}\n\n\
+            **{}:** `{}`\n\
+            **{}:** [`{}`](https://github.com/MRDGH2821/Coco-Bot/tree/{})\n\
+            **{}:** [`{}`](https://github.com/MRDGH2821/Coco-Bot/tree/{})",
+            tr!(ctx, "about-intro"),
+            tr!(ctx, "about-version"),
+            version,
+            tr!(ctx, "about-git-hash"),
+            git_hash,
+            git_hash,
+            tr!(ctx, "about-git-branch"),
+            git_branch,
+            git_branch
         ))
-        .field("🦀 Dependencies", format!(
-            "**Serenity:** [{}](https://github.com/serenity-rs/serenity/tree/{})\n\
+        .field(
+            tr!(ctx, "about-dependencies"),
+            format!(
+                "**Serenity:** [{}](https://github.com/serenity-rs/serenity/tree/{})\n\
             **Poise:** [{}](https://github.com/serenity-rs/poise/tree/{})\n\
             **Rust:** {}",
-            serenity_info,
-            serenity_hash,
-            poise_info,
-            poise_hash,
-            env!("RUSTC_VERSION")
-        ), true)
-        .field("🔧 Build Info", format!(
-            "**Built:** <t:{}:f> (<t:{}:R>)\n\
-            **Target:** {}",
-            Timestamp::parse(build_time).unwrap().timestamp(),
-            Timestamp::parse(build_time).unwrap().timestamp(),
-            env!("TARGET")
-        ), true)
+                serenity_info,
+                serenity_hash,
+                poise_info,
+                poise_hash,
+                env!("RUSTC_VERSION")
+            ),
+            true,
+        )
+        .field(
+            tr!(ctx, "about-build-info"),
+            format!(
+                "**{}:** <t:{}:f> (<t:{}:R>)\n\
+            **{}:** {}",
+                tr!(ctx, "about-built"),
+                Timestamp::parse(build_time).unwrap().timestamp(),
+                Timestamp::parse(build_time).unwrap().timestamp(),
+                tr!(ctx, "about-target"),
+                env!("TARGET")
+            ),
+            true,
+        )
         .color(serenity::Color::from_rgb(255, 192, 203)) // Pink color
-        .footer(serenity::CreateEmbedFooter::new(format!("Built with Rust 🦀 • {} ({})", version, git_hash)))
+        .footer(serenity::CreateEmbedFooter::new(tr!(
+            ctx,
+            "about-footer",
+            version = version,
+            git_hash = git_hash
+        )))
         .timestamp(serenity::Timestamp::now());
 
     // Create link button for repository
     let github_button =
-        serenity::CreateButton::new_link(repository).label("📂 Coco Bot Source Code");
+        serenity::CreateButton::new_link(repository).label(tr!(ctx, "about-source-code"));
 
     // You could also add more buttons here, for example:
     let kittybot_button = serenity::CreateButton::new_link("https://github.com/olliequ/KittyBot")
-        .label("🐱 KittyBot Source Code");
+        .label(tr!(ctx, "about-kittybot-source-code"));
 
     let action_row =
         serenity::CreateActionRow::Buttons(vec![github_button, kittybot_button].into());
@@ -95,3 +125,31 @@ pub async fn about(ctx: Context<'_>) -> Result<(), Error> {
     .await?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot_lib::fake_context::{self, FakeContext};
+
+    #[test]
+    fn title_cases_words() {
+        assert_eq!(to_title_case("coco bot"), "Coco Bot");
+        assert_eq!(to_title_case("  many   spaces "), "Many Spaces");
+    }
+
+    #[tokio::test]
+    async fn links_source_code() {
+        let ctx = FakeContext::new(fake_context::config()).await;
+
+        run(&ctx).await.unwrap();
+
+        let reply = ctx.reply();
+        assert_eq!(
+            reply.embeds[0]["title"],
+            tr!(&ctx, "about-title", name = "Coco Bot")
+        );
+        let buttons = &reply.components[0]["components"];
+        assert_eq!(buttons[0]["url"], env!("CARGO_PKG_REPOSITORY"));
+        assert_eq!(buttons[1]["url"], "https://github.com/olliequ/KittyBot");
+    }
+}
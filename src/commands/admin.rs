@@ -0,0 +1,315 @@
+use crate::bot_lib::i18n::tr;
+use crate::bot_lib::logging::sensitive;
+use crate::bot_lib::registration::Scope;
+use crate::bot_lib::{meme_templates, registration};
+use crate::config::{ActivityKind, PresenceConfig, StatusKind};
+use crate::{Context, Error, commands};
+use poise::serenity_prelude as serenity;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Formats a duration as e.g. `2d 3h 4m 5s`
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (days, hours, minutes, seconds) = (
+        secs / 86_400,
+        (secs % 86_400) / 3_600,
+        (secs % 3_600) / 60,
+        secs % 60,
+    );
+
+    if days > 0 {
+        format!("{}d {}h {}m {}s", days, hours, minutes, seconds)
+    } else if hours > 0 {
+        format!("{}h {}m {}s", hours, minutes, seconds)
+    } else if minutes > 0 {
+        format!("{}m {}s", minutes, seconds)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+/// Describes where commands were registered
+async fn describe_scope(ctx: Context<'_>, scope: Scope) -> String {
+    match scope {
+        Scope::Global => tr!(ctx, "admin-scope-global"),
+        Scope::Guild(guild_id) => tr!(ctx, "admin-scope-guild", guild = guild_id.to_string()),
+    }
+}
+
+/// Formats a registration summary for the reply, using the message `done` for scopes the
+/// commands were pushed to
+async fn format_summary(ctx: Context<'_>, done: &str, summary: &registration::Summary) -> String {
+    let mut lines = Vec::new();
+    for &scope in &summary.registered {
+        let scope = describe_scope(ctx, scope).await;
+        lines.push(tr!(ctx, done, scope = scope));
+    }
+    for &scope in &summary.unchanged {
+        let scope = describe_scope(ctx, scope).await;
+        lines.push(tr!(ctx, "admin-unchanged", scope = scope));
+    }
+    for &scope in &summary.removed {
+        let scope = describe_scope(ctx, scope).await;
+        lines.push(tr!(ctx, "admin-removed", scope = scope));
+    }
+    for (scope, error) in &summary.failed {
+        let scope = describe_scope(ctx, *scope).await;
+        lines.push(tr!(
+            ctx,
+            "admin-failed",
+            scope = scope,
+            error = error.as_str()
+        ));
+    }
+    lines.join("\n")
+}
+
+/// Sends an ephemeral reply, as admin output is only of interest to the invoker
+async fn reply(ctx: Context<'_>, content: impl Into<String>) -> Result<(), Error> {
+    ctx.send(
+        poise::CreateReply::default()
+            .content(content.into())
+            .ephemeral(true),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Autocomplete function for command names which can be toggled
+async fn autocomplete_command_name<'a>(
+    ctx: Context<'_>,
+    partial: &'a str,
+) -> serenity::CreateAutocompleteResponse<'a> {
+    let partial_lower = partial.to_lowercase();
+    let choices: Vec<serenity::AutocompleteChoice<'a>> = ctx
+        .framework()
+        .options()
+        .commands
+        .iter()
+        .map(|command| command.name.to_string())
+        .filter(|name| name != "admin" && name.starts_with(&partial_lower))
+        .take(25)
+        .map(|name| serenity::AutocompleteChoice::new(name.clone(), name))
+        .collect();
+
+    serenity::CreateAutocompleteResponse::new().set_choices(choices)
+}
+
+#[poise::command(
+    slash_command,
+    prefix_command,
+    category = "Admin",
+    owners_only,
+    default_member_permissions = "ADMINISTRATOR",
+    subcommands(
+        "register",
+        "unregister",
+        "reload_templates",
+        "presence",
+        "stats",
+        "toggle",
+        "shutdown"
+    ),
+    subcommand_required
+)]
+pub async fn admin(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command, owners_only)]
+async fn register(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.defer_ephemeral().await?;
+
+    let data = ctx.data();
+    let all_commands = commands::all_commands();
+    let summary =
+        registration::sync_commands(&ctx.serenity_context().http, &data, &all_commands, true).await;
+    data.health
+        .set_commands_registered(summary.failed.is_empty());
+
+    let content = format_summary(ctx, "admin-registered", &summary).await;
+    reply(ctx, content).await
+}
+
+#[poise::command(slash_command, prefix_command, owners_only)]
+async fn unregister(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.defer_ephemeral().await?;
+
+    let data = ctx.data();
+    let summary = registration::unregister_commands(&ctx.serenity_context().http, &data).await;
+    data.health.set_commands_registered(false);
+
+    let content = format!(
+        "{}\n\n{}",
+        format_summary(ctx, "admin-unregistered", &summary).await,
+        tr!(ctx, "admin-register-again")
+    );
+    reply(ctx, content).await
+}
+
+#[poise::command(slash_command, prefix_command, owners_only)]
+async fn reload_templates(ctx: Context<'_>) -> Result<(), Error> {
+    let data = ctx.data();
+
+    match meme_templates::load() {
+        Ok(templates) => {
+            let count = templates.len();
+            *data
+                .meme_templates
+                .write()
+                .expect("Meme template lock poisoned") = templates;
+            info!(count, "Meme templates reloaded");
+            reply(ctx, tr!(ctx, "admin-templates-reloaded", count = count)).await
+        }
+        Err(e) => {
+            warn!(error = %e, "Failed to reload meme templates");
+            reply(
+                ctx,
+                tr!(ctx, "admin-templates-failed", error = e.to_string()),
+            )
+            .await
+        }
+    }
+}
+
+#[poise::command(slash_command, prefix_command, owners_only)]
+async fn presence(
+    ctx: Context<'_>,
+    activity_type: ActivityKind,
+    activity: Option<String>,
+    status: Option<StatusKind>,
+) -> Result<(), Error> {
+    let data = ctx.data();
+    let presence = PresenceConfig {
+        activity_type,
+        activity: activity.unwrap_or_default(),
+        status: status.unwrap_or_default(),
+    };
+
+    ctx.serenity_context()
+        .set_presence(presence.activity_data(), presence.online_status());
+    *data.presence.write().expect("Presence lock poisoned") = presence;
+
+    reply(ctx, tr!(ctx, "admin-presence-updated")).await
+}
+
+#[poise::command(slash_command, prefix_command, owners_only)]
+async fn stats(ctx: Context<'_>) -> Result<(), Error> {
+    let data = ctx.data();
+    let latency = ctx.ping().await;
+    let guild_count = ctx.cache().guilds().len();
+    let template_count = data
+        .meme_templates
+        .read()
+        .expect("Meme template lock poisoned")
+        .len();
+    let mut disabled: Vec<String> = data
+        .disabled_commands
+        .read()
+        .expect("Disabled commands lock poisoned")
+        .iter()
+        .cloned()
+        .collect();
+    disabled.sort();
+    let disabled = if disabled.is_empty() {
+        tr!(ctx, "admin-stats-none")
+    } else {
+        disabled.join(", ")
+    };
+
+    let embed = serenity::CreateEmbed::default()
+        .title(tr!(ctx, "admin-stats-title"))
+        .field(
+            tr!(ctx, "admin-stats-uptime"),
+            format_duration(data.started_at.elapsed()),
+            true,
+        )
+        .field(
+            tr!(ctx, "admin-stats-latency"),
+            format!("{}ms", latency.as_millis()),
+            true,
+        )
+        .field(
+            tr!(ctx, "admin-stats-guilds"),
+            guild_count.to_string(),
+            true,
+        )
+        .field(
+            tr!(ctx, "admin-stats-commands"),
+            ctx.framework().options().commands.len().to_string(),
+            true,
+        )
+        .field(
+            tr!(ctx, "admin-stats-templates"),
+            template_count.to_string(),
+            true,
+        )
+        .field(tr!(ctx, "admin-stats-disabled"), disabled, false)
+        .footer(serenity::CreateEmbedFooter::new(format!(
+            "v{} ({})",
+            env!("CARGO_PKG_VERSION"),
+            env!("GIT_HASH")
+        )))
+        .color(serenity::Color::from_rgb(88, 101, 242)) // Blurple color
+        .timestamp(serenity::Timestamp::now());
+
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+#[poise::command(slash_command, prefix_command, owners_only)]
+async fn toggle(
+    ctx: Context<'_>,
+    #[autocomplete = "autocomplete_command_name"] command: String,
+) -> Result<(), Error> {
+    let data = ctx.data();
+    let command = command.trim().to_lowercase();
+
+    let exists = ctx
+        .framework()
+        .options()
+        .commands
+        .iter()
+        .any(|c| *c.name == *command);
+    if !exists {
+        return reply(ctx, tr!(ctx, "admin-unknown-command", command = command)).await;
+    }
+    if command == "admin" {
+        return reply(ctx, tr!(ctx, "admin-toggle-protected")).await;
+    }
+
+    let (enabled, snapshot) = {
+        let mut disabled = data
+            .disabled_commands
+            .write()
+            .expect("Disabled commands lock poisoned");
+        // Removing succeeds if the command was disabled, so it is enabled again
+        let enabled = disabled.remove(&command) || !disabled.insert(command.clone());
+        let mut snapshot: Vec<String> = disabled.iter().cloned().collect();
+        snapshot.sort();
+        (enabled, snapshot)
+    };
+
+    data.storage
+        .set_state("disabled_commands", &serde_json::to_string(&snapshot)?)
+        .await?;
+    info!(command = %command, enabled, "Command toggled");
+
+    let id = if enabled {
+        "admin-enabled"
+    } else {
+        "admin-disabled"
+    };
+    reply(ctx, tr!(ctx, id, command = command)).await
+}
+
+#[poise::command(slash_command, prefix_command, owners_only)]
+async fn shutdown(ctx: Context<'_>) -> Result<(), Error> {
+    warn!(user = %sensitive(&ctx.author().name), "Shutdown requested by owner");
+    reply(ctx, tr!(ctx, "admin-shutting-down")).await?;
+
+    ctx.data().shutdown.notify_one();
+    Ok(())
+}
@@ -1,18 +1,116 @@
+use crate::bot_lib::command_context::CommandContext;
+use crate::bot_lib::content_packs::{self, PackKind, Pick};
+use crate::bot_lib::http::HttpClient;
+use crate::bot_lib::i18n::tr;
 use crate::{Context, Error};
 
-#[poise::command(
-    slash_command,
-    description_localized("en-US", "Get a random piece of advice.")
-)]
-pub async fn advice(ctx: Context<'_>) -> Result<(), Error> {
-    let response = reqwest::get("https://api.adviceslip.com/advice")
-        .await?
-        .json::<serde_json::Value>()
+/// Call the advice slip API
+async fn fetch_advice(http: &HttpClient, base_url: &str) -> Result<String, Error> {
+    let request = http.get(&format!("{}/advice", base_url));
+    let response = http
+        .json::<serde_json::Value>("adviceslip", request)
         .await?;
     let advice = response["slip"]["advice"]
         .as_str()
         .unwrap_or("No advice found");
 
+    Ok(advice.to_string())
+}
+
+#[poise::command(slash_command, prefix_command, category = "Fun", track_edits)]
+pub async fn advice(ctx: Context<'_>) -> Result<(), Error> {
+    run(ctx).await
+}
+
+pub async fn run(ctx: impl CommandContext) -> Result<(), Error> {
+    ctx.defer().await?;
+
+    let data = ctx.data();
+    let advice = match fetch_advice(&data.http, &data.config.apis.advice).await {
+        Ok(advice) => advice,
+        Err(e) => {
+            tracing::warn!(error = %e, "Advice API unavailable, using the content pack");
+            let advice = content_packs::pick_entry(
+                &data.storage,
+                ctx.guild_id(),
+                PackKind::Advice,
+                Pick::Random,
+            )
+            .await;
+            format!("{}\n-# {}", advice, tr!(ctx, "content-pack-footer"))
+        }
+    };
+
     ctx.say(advice).await?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot_lib::mock_api::{self, Failure, context_for, server_responding};
+    use wiremock::ResponseTemplate;
+
+    fn advice_response(advice: &str) -> ResponseTemplate {
+        ResponseTemplate::new(200)
+            .set_body_json(serde_json::json!({ "slip": { "id": 1, "advice": advice } }))
+    }
+
+    #[tokio::test]
+    async fn parses_advice() {
+        let server = server_responding("/advice", advice_response("Write tests.")).await;
+
+        let advice = fetch_advice(&HttpClient::for_tests(0), &server.uri())
+            .await
+            .unwrap();
+
+        assert_eq!(advice, "Write tests.");
+    }
+
+    #[tokio::test]
+    async fn falls_back_when_advice_missing() {
+        let server = server_responding(
+            "/advice",
+            ResponseTemplate::new(200).set_body_json(serde_json::json!({ "message": "none" })),
+        )
+        .await;
+
+        let advice = fetch_advice(&HttpClient::for_tests(0), &server.uri())
+            .await
+            .unwrap();
+
+        assert_eq!(advice, "No advice found");
+    }
+
+    #[tokio::test]
+    async fn rejects_broken_responses() {
+        mock_api::assert_rejects("/advice", &Failure::ALL, |http, url| async move {
+            fetch_advice(&http, &url).await
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn replies_with_advice() {
+        let server = server_responding("/advice", advice_response("Write tests.")).await;
+        let ctx = context_for(&server, |apis| &mut apis.advice).await;
+
+        run(&ctx).await.unwrap();
+
+        assert!(ctx.deferred());
+        assert_eq!(ctx.reply().content.as_deref(), Some("Write tests."));
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_content_pack() {
+        let server = server_responding("/advice", ResponseTemplate::new(500)).await;
+        let ctx = context_for(&server, |apis| &mut apis.advice).await;
+
+        run(&ctx).await.unwrap();
+
+        let content = ctx.reply().content.unwrap();
+        let (advice, footer) = content.split_once("\n-# ").unwrap();
+        assert!(PackKind::Advice.bundled().any(|entry| entry == advice));
+        assert_eq!(footer, tr!(&ctx, "content-pack-footer"));
+    }
+}
@@ -0,0 +1,180 @@
+use crate::bot_lib::command_context::CommandContext;
+use crate::bot_lib::i18n::tr;
+use crate::bot_lib::meme_templates::MAX_BOXES;
+use crate::commands::generate_meme::{find_template, send_meme};
+use crate::{Context, Error};
+use poise::serenity_prelude as serenity;
+use std::time::Duration;
+
+/// How long the caption form may stay open before it's discarded
+const FORM_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// Most characters Discord allows in the title and labels of a form
+const FORM_TEXT_LIMIT: usize = 45;
+
+/// Splits captions given as text, e.g. `top | middle | bottom`
+pub fn split_captions(captions: &str) -> Vec<String> {
+    captions
+        .split('|')
+        .map(|caption| caption.trim().to_string())
+        .collect()
+}
+
+/// Shortens the title or a label of a form to what Discord accepts
+fn form_text(text: String) -> String {
+    text.chars().take(FORM_TEXT_LIMIT).collect()
+}
+
+/// A form with a text field for each text box of a template
+#[derive(Default)]
+struct CaptionForm {
+    title: String,
+    labels: Vec<String>,
+    captions: Vec<String>,
+}
+
+/// Custom ID of the field for a text box
+fn field_id(index: usize) -> String {
+    format!("caption-{}", index)
+}
+
+impl poise::Modal for CaptionForm {
+    fn create(
+        defaults: Option<Self>,
+        custom_id: String,
+    ) -> serenity::CreateInteractionResponse<'static> {
+        let form = defaults.unwrap_or_default();
+        let fields: Vec<_> = form
+            .labels
+            .into_iter()
+            .enumerate()
+            .map(|(i, label)| {
+                serenity::CreateActionRow::InputText(
+                    serenity::CreateInputText::new(
+                        serenity::InputTextStyle::Short,
+                        label,
+                        field_id(i),
+                    )
+                    .required(false),
+                )
+            })
+            .collect();
+
+        serenity::CreateInteractionResponse::Modal(
+            serenity::CreateModal::new(custom_id, form.title).components(fields),
+        )
+    }
+
+    fn parse(mut data: serenity::ModalInteractionData) -> Result<Self, &'static str> {
+        // Discord leaves out fields which weren't filled in
+        let captions = (0..MAX_BOXES)
+            .map(|i| poise::find_modal_text(&mut data, &field_id(i)).unwrap_or_default())
+            .collect();
+        Ok(Self {
+            captions,
+            ..Self::default()
+        })
+    }
+}
+
+/// Caption a meme template with as many captions as it has text boxes
+#[poise::command(slash_command, prefix_command, category = "Fun")]
+pub async fn caption_meme(
+    ctx: Context<'_>,
+    #[autocomplete = "super::generate_meme::autocomplete_meme_template"] template: String,
+    captions: Option<String>,
+) -> Result<(), Error> {
+    if let Some(captions) = captions {
+        return run(ctx, &template, &split_captions(&captions)).await;
+    }
+
+    // Without captions, slash commands ask for them in a form while text commands can't
+    let poise::Context::Application(app_ctx) = ctx else {
+        ctx.say(tr!(ctx, "caption-meme-missing")).await?;
+        return Ok(());
+    };
+    let Some(found) = find_template(ctx, &template).await? else {
+        return Ok(());
+    };
+
+    let mut labels = Vec::new();
+    for number in 1..=found.boxes.len() {
+        labels.push(form_text(tr!(ctx, "caption-meme-field", number = number)));
+    }
+    let form = CaptionForm {
+        title: form_text(tr!(
+            ctx,
+            "caption-meme-form-title",
+            template = found.name.as_str()
+        )),
+        labels,
+        captions: Vec::new(),
+    };
+
+    if let Some(mut form) = poise::execute_modal(app_ctx, Some(form), Some(FORM_TIMEOUT)).await? {
+        form.captions.truncate(found.boxes.len());
+        send_meme(ctx, &found, &form.captions).await?;
+    }
+    Ok(())
+}
+
+pub async fn run(
+    ctx: impl CommandContext,
+    template: &str,
+    captions: &[String],
+) -> Result<(), Error> {
+    let Some(template) = find_template(ctx, template).await? else {
+        return Ok(());
+    };
+
+    if captions.len() > template.boxes.len() {
+        let message = tr!(
+            ctx,
+            "caption-meme-too-many",
+            template = template.name.as_str(),
+            boxes = template.boxes.len(),
+            captions = captions.len()
+        );
+        return ctx
+            .send(
+                poise::CreateReply::default()
+                    .content(message)
+                    .ephemeral(true),
+            )
+            .await;
+    }
+
+    send_meme(ctx, &template, captions).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot_lib::fake_context::{self, FakeContext};
+
+    #[test]
+    fn splits_captions() {
+        assert_eq!(
+            split_captions(" tests |more tests|| "),
+            vec!["tests", "more tests", "", ""]
+        );
+        assert_eq!(split_captions("just one"), vec!["just one"]);
+    }
+
+    #[tokio::test]
+    async fn rejects_more_captions_than_boxes() {
+        let ctx = FakeContext::new(fake_context::config()).await;
+
+        run(&ctx, "Sitting lion", &split_captions("one | two | three"))
+            .await
+            .unwrap();
+
+        let reply = ctx.reply();
+        assert!(reply.ephemeral);
+        assert!(!ctx.deferred());
+        assert_eq!(
+            reply.content.as_deref(),
+            Some("❌ Sitting lion has room for 2 captions, but 3 were given.")
+        );
+    }
+}
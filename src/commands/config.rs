@@ -0,0 +1,499 @@
+use crate::bot_lib::command_rules::{Effect, PROTECTED_COMMANDS, Rule, Target};
+use crate::bot_lib::content_packs::{self, MAX_ENTRY_LEN, MAX_GUILD_ENTRIES, PackKind};
+use crate::bot_lib::i18n::{tr, translations};
+use crate::bot_lib::prefix::{MAX_PREFIX_LEN, validate_prefix};
+use crate::{Context, Error};
+use poise::serenity_prelude as serenity;
+use tracing::info;
+
+/// Sends an ephemeral reply, as configuration output is only of interest to the invoker
+async fn reply(ctx: Context<'_>, content: impl Into<String>) -> Result<(), Error> {
+    ctx.send(
+        poise::CreateReply::default()
+            .content(content.into())
+            .ephemeral(true),
+    )
+    .await?;
+    Ok(())
+}
+
+/// All command names and categories which rules can be set for
+fn rule_targets(ctx: Context<'_>) -> Vec<Target> {
+    let commands = &ctx.framework().options().commands;
+    let mut targets: Vec<Target> = commands
+        .iter()
+        .filter(|command| !PROTECTED_COMMANDS.contains(&&*command.name))
+        .map(|command| Target::Command(command.name.to_string()))
+        .collect();
+
+    for category in commands.iter().filter_map(|c| c.category.as_deref()) {
+        let target = Target::Category(category.to_string());
+        if !targets.contains(&target) {
+            targets.push(target);
+        }
+    }
+    targets
+}
+
+/// Finds the command or category matching the user's input
+fn resolve_target(ctx: Context<'_>, input: &str) -> Option<Target> {
+    let input = input.trim().trim_start_matches('/');
+    rule_targets(ctx).into_iter().find(|target| match target {
+        Target::Command(name) => name == input,
+        Target::Category(name) => name.eq_ignore_ascii_case(input),
+    })
+}
+
+/// Autocomplete function for command names and categories
+async fn autocomplete_target<'a>(
+    ctx: Context<'_>,
+    partial: &'a str,
+) -> serenity::CreateAutocompleteResponse<'a> {
+    let partial_lower = partial.to_lowercase();
+    let mut choices: Vec<serenity::AutocompleteChoice<'a>> = Vec::new();
+    for target in rule_targets(ctx) {
+        let (name, label) = match target {
+            Target::Command(name) => (name.clone(), format!("/{}", name)),
+            Target::Category(name) => (
+                name.clone(),
+                tr!(ctx, "config-target-category-choice", name = name),
+            ),
+        };
+        if name.to_lowercase().starts_with(&partial_lower) {
+            choices.push(serenity::AutocompleteChoice::new(label, name));
+        }
+        if choices.len() == 25 {
+            break;
+        }
+    }
+
+    serenity::CreateAutocompleteResponse::new().set_choices(choices)
+}
+
+/// Describes a command or category for a reply
+async fn describe_target(ctx: Context<'_>, target: &Target) -> String {
+    match target {
+        Target::Command(name) => tr!(ctx, "config-target-command", name = name),
+        Target::Category(name) => tr!(ctx, "config-target-category", name = name),
+    }
+}
+
+/// Describes where a rule applies
+async fn describe_scope(ctx: Context<'_>, channel_id: Option<u64>) -> String {
+    match channel_id {
+        // Snowflakes don't fit into the float Fluent formats numbers as
+        Some(channel_id) => tr!(
+            ctx,
+            "config-scope-channel",
+            channel = channel_id.to_string()
+        ),
+        None => tr!(ctx, "config-scope-guild"),
+    }
+}
+
+/// Adds or replaces a rule for the current guild
+async fn set_rule(
+    ctx: Context<'_>,
+    target: String,
+    channel: Option<serenity::GuildChannel>,
+    effect: Effect,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Must be used in a server")?;
+    let Some(target) = resolve_target(ctx, &target) else {
+        return reply(ctx, tr!(ctx, "config-unknown-target", target = target)).await;
+    };
+    let channel_id = channel.map(|channel| channel.id.get());
+
+    let data = ctx.data();
+    let mut rules = data.command_rules.get(&data.storage, guild_id).await?;
+    rules.set(Rule {
+        target: target.clone(),
+        effect,
+        channel_id,
+    });
+    data.command_rules
+        .set(&data.storage, guild_id, rules)
+        .await?;
+    info!(%guild_id, ?target, ?effect, ?channel_id, "Command rule set");
+
+    let effect = match effect {
+        Effect::Allow => "allow",
+        Effect::Deny => "deny",
+    };
+    let target = describe_target(ctx, &target).await;
+    let scope = describe_scope(ctx, channel_id).await;
+    let content = tr!(
+        ctx,
+        "config-rule-set",
+        target = target,
+        effect = effect,
+        scope = scope
+    );
+    reply(ctx, content).await
+}
+
+#[poise::command(
+    slash_command,
+    prefix_command,
+    category = "Admin",
+    guild_only,
+    default_member_permissions = "MANAGE_GUILD",
+    required_permissions = "MANAGE_GUILD",
+    subcommands("commands", "content", "language", "prefix"),
+    subcommand_required
+)]
+pub async fn config(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Autocomplete function for the languages there is a catalogue for
+async fn autocomplete_language<'a>(
+    _ctx: Context<'_>,
+    partial: &'a str,
+) -> serenity::CreateAutocompleteResponse<'a> {
+    let partial_lower = partial.to_lowercase();
+    let choices: Vec<serenity::AutocompleteChoice<'a>> = translations()
+        .languages()
+        .into_iter()
+        .filter(|(locale, name)| {
+            locale.to_lowercase().starts_with(&partial_lower)
+                || name.to_lowercase().starts_with(&partial_lower)
+        })
+        .map(|(locale, name)| {
+            serenity::AutocompleteChoice::new(format!("{} ({})", name, locale), locale)
+        })
+        .collect();
+
+    serenity::CreateAutocompleteResponse::new().set_choices(choices)
+}
+
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+async fn language(
+    ctx: Context<'_>,
+    #[autocomplete = "autocomplete_language"] language: Option<String>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Must be used in a server")?;
+    let data = ctx.data();
+
+    let language = language.map(|language| language.trim().to_string());
+    if let Some(language) = language
+        .as_deref()
+        .filter(|language| !translations().is_supported(language))
+    {
+        return reply(
+            ctx,
+            tr!(ctx, "config-language-unknown", language = language),
+        )
+        .await;
+    }
+
+    data.locales
+        .set(&data.storage, guild_id, language.as_deref())
+        .await?;
+    info!(%guild_id, ?language, "Language changed");
+
+    // Looked up after the change, so text commands are already confirmed in the new language
+    let content = match &language {
+        Some(language) => tr!(
+            ctx,
+            "config-language-set",
+            language = translations().get(language, "language-name", None)
+        ),
+        None => tr!(ctx, "config-language-reset"),
+    };
+    reply(ctx, content).await
+}
+
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+async fn prefix(ctx: Context<'_>, prefix: Option<String>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Must be used in a server")?;
+    let data = ctx.data();
+    let default = &data.config.prefix.prefix;
+
+    let prefix = prefix.map(|prefix| prefix.trim().to_string());
+    if let Some(Err(reason)) = prefix.as_deref().map(validate_prefix) {
+        let content = tr!(
+            ctx,
+            "config-prefix-invalid",
+            reason = reason.label(),
+            max = MAX_PREFIX_LEN
+        );
+        return reply(ctx, content).await;
+    }
+
+    data.prefixes
+        .set(&data.storage, guild_id, prefix.as_deref())
+        .await?;
+    info!(%guild_id, ?prefix, "Prefix changed");
+
+    let content = match &prefix {
+        Some(prefix) => tr!(ctx, "config-prefix-set", prefix = prefix.as_str()),
+        None => tr!(ctx, "config-prefix-reset", prefix = default.as_str()),
+    };
+    let content = if data.config.prefix.enabled {
+        content
+    } else {
+        format!("{}\n{}", content, tr!(ctx, "config-prefix-disabled"))
+    };
+    reply(ctx, content).await
+}
+
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD",
+    subcommands("content_add", "content_remove", "content_list"),
+    subcommand_required
+)]
+async fn content(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Names a content pack for a reply
+async fn describe_pack(ctx: Context<'_>, kind: PackKind) -> String {
+    tr!(ctx, "content-pack-name", pack = kind.key())
+}
+
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD",
+    rename = "add"
+)]
+async fn content_add(ctx: Context<'_>, pack: PackKind, entry: String) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Must be used in a server")?;
+
+    let result = content_packs::add_entry(&ctx.data().storage, guild_id, pack, &entry).await?;
+    let content = match result {
+        Ok(()) => {
+            info!(%guild_id, pack = pack.key(), "Content pack entry added");
+            let pack = describe_pack(ctx, pack).await;
+            tr!(ctx, "config-content-added", pack = pack)
+        }
+        Err(reason) => tr!(
+            ctx,
+            "config-content-invalid",
+            reason = reason.label(),
+            max_len = MAX_ENTRY_LEN,
+            max_entries = MAX_GUILD_ENTRIES
+        ),
+    };
+    reply(ctx, content).await
+}
+
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD",
+    rename = "remove"
+)]
+async fn content_remove(
+    ctx: Context<'_>,
+    pack: PackKind,
+    #[min = 1] number: usize,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Must be used in a server")?;
+
+    // Entries are numbered from one in `/config content list`
+    let removed = content_packs::remove_entry(
+        &ctx.data().storage,
+        guild_id,
+        pack,
+        number.saturating_sub(1),
+    )
+    .await?;
+    if removed.is_some() {
+        info!(%guild_id, pack = pack.key(), number, "Content pack entry removed");
+    }
+
+    let name = describe_pack(ctx, pack).await;
+    let content = match removed {
+        Some(entry) => tr!(ctx, "config-content-removed", pack = name, entry = entry),
+        None => tr!(ctx, "config-content-missing", pack = name, number = number),
+    };
+    reply(ctx, content).await
+}
+
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD",
+    rename = "list"
+)]
+async fn content_list(ctx: Context<'_>, pack: PackKind) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Must be used in a server")?;
+    let entries = content_packs::guild_entries(&ctx.data().storage, guild_id, pack).await?;
+
+    let description = if entries.is_empty() {
+        tr!(ctx, "config-content-empty")
+    } else {
+        // A full pack doesn't fit into an embed, so cut it off at Discord's description limit
+        let mut description = String::new();
+        for (i, entry) in entries.iter().enumerate() {
+            let line = format!("{}. {}\n", i + 1, entry);
+            if description.chars().count() + line.chars().count() > 4000 {
+                description.push('…');
+                break;
+            }
+            description.push_str(&line);
+        }
+        description
+    };
+    let name = describe_pack(ctx, pack).await;
+
+    let embed = serenity::CreateEmbed::default()
+        .title(tr!(ctx, "config-content-title", pack = name))
+        .description(description)
+        .footer(serenity::CreateEmbedFooter::new(tr!(
+            ctx,
+            "config-content-footer",
+            count = pack.bundled().count()
+        )))
+        .color(serenity::Color::from_rgb(88, 101, 242)); // Blurple color
+
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD",
+    subcommands("allow", "deny", "reset", "list"),
+    subcommand_required
+)]
+async fn commands(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+async fn allow(
+    ctx: Context<'_>,
+    #[autocomplete = "autocomplete_target"] target: String,
+    channel: Option<serenity::GuildChannel>,
+) -> Result<(), Error> {
+    set_rule(ctx, target, channel, Effect::Allow).await
+}
+
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+async fn deny(
+    ctx: Context<'_>,
+    #[autocomplete = "autocomplete_target"] target: String,
+    channel: Option<serenity::GuildChannel>,
+) -> Result<(), Error> {
+    set_rule(ctx, target, channel, Effect::Deny).await
+}
+
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+async fn reset(
+    ctx: Context<'_>,
+    #[autocomplete = "autocomplete_target"] target: String,
+    channel: Option<serenity::GuildChannel>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Must be used in a server")?;
+    let Some(target) = resolve_target(ctx, &target) else {
+        return reply(ctx, tr!(ctx, "config-unknown-target", target = target)).await;
+    };
+    let channel_id = channel.map(|channel| channel.id.get());
+
+    let data = ctx.data();
+    let mut rules = data.command_rules.get(&data.storage, guild_id).await?;
+    let removed = rules.remove(&target, channel_id);
+    if removed {
+        data.command_rules
+            .set(&data.storage, guild_id, rules)
+            .await?;
+        info!(%guild_id, ?target, ?channel_id, "Command rule removed");
+    }
+
+    let id = if removed {
+        "config-rule-removed"
+    } else {
+        "config-rule-missing"
+    };
+    let target = describe_target(ctx, &target).await;
+    let scope = describe_scope(ctx, channel_id).await;
+    let content = tr!(ctx, id, target = target, scope = scope);
+    reply(ctx, content).await
+}
+
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+async fn list(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Must be used in a server")?;
+    let data = ctx.data();
+    let rules = data.command_rules.get(&data.storage, guild_id).await?;
+
+    let description = if rules.is_empty() {
+        tr!(ctx, "config-rules-empty")
+    } else {
+        // Many rules don't fit into an embed, so cut them off at Discord's description limit
+        let mut description = String::new();
+        for rule in rules.rules() {
+            let icon = match rule.effect {
+                Effect::Allow => "✅",
+                Effect::Deny => "🚫",
+            };
+            let line = format!(
+                "{} {} {}\n",
+                icon,
+                describe_target(ctx, &rule.target).await,
+                describe_scope(ctx, rule.channel_id).await
+            );
+            if description.chars().count() + line.chars().count() > 4000 {
+                description.push('…');
+                break;
+            }
+            description.push_str(&line);
+        }
+        description
+    };
+
+    let embed = serenity::CreateEmbed::default()
+        .title(tr!(ctx, "config-rules-title"))
+        .description(description)
+        .footer(serenity::CreateEmbedFooter::new(tr!(
+            ctx,
+            "config-rules-footer"
+        )))
+        .color(serenity::Color::from_rgb(88, 101, 242)); // Blurple color
+
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
@@ -1,3 +1,7 @@
+use crate::bot_lib::command_context::CommandContext;
+use crate::bot_lib::content_packs::{self, PackKind, Pick};
+use crate::bot_lib::http::HttpClient;
+use crate::bot_lib::i18n::tr;
 use crate::{Context, Error};
 use poise::serenity_prelude as serenity;
 use serde::{Deserialize, Serialize};
@@ -9,52 +13,154 @@ struct DadJokeResponse {
     status: u32,
 }
 
-#[poise::command(
-    slash_command,
-    description_localized("en-US", "Get a random dad joke to brighten your day!")
-)]
+/// Call the dad joke API
+async fn fetch_dad_joke(http: &HttpClient, base_url: &str) -> Result<DadJokeResponse, Error> {
+    // The API only returns JSON when explicitly asked for it
+    let request = http
+        .get(&format!("{}/", base_url))
+        .header("Accept", "application/json");
+
+    Ok(http.json("icanhazdadjoke", request).await?)
+}
+
+#[poise::command(slash_command, prefix_command, category = "Fun", track_edits)]
 pub async fn dad_joke(ctx: Context<'_>) -> Result<(), Error> {
+    run(ctx).await
+}
+
+pub async fn run(ctx: impl CommandContext) -> Result<(), Error> {
     // Send a "thinking" response first since API calls can take time
     ctx.defer().await?;
 
-    // Create a reqwest client with the required headers
-    let client = reqwest::Client::new();
-    let response = client
-        .get("https://icanhazdadjoke.com/")
-        .header("Accept", "application/json")
-        .header(
-            "User-Agent",
-            "Coco-Bot (https://github.com/MRDGH2821/Coco-Bot)",
-        )
-        .send()
-        .await?
-        .json::<DadJokeResponse>()
-        .await?;
+    let data = ctx.data();
+    let base_url = &data.config.apis.dad_joke;
+    let (joke, id) = match fetch_dad_joke(&data.http, base_url).await {
+        Ok(response) => (response.joke, Some(response.id)),
+        Err(e) => {
+            tracing::warn!(error = %e, "Dad joke API unavailable, using the content pack");
+            let joke = content_packs::pick_entry(
+                &data.storage,
+                ctx.guild_id(),
+                PackKind::DadJoke,
+                Pick::Random,
+            )
+            .await;
+            (joke, None)
+        }
+    };
+
+    let footer = match &id {
+        Some(id) => tr!(ctx, "dad-joke-footer", id = id.as_str()),
+        None => tr!(ctx, "content-pack-footer"),
+    };
 
     // Create an embed for the dad joke
     let embed = serenity::CreateEmbed::default()
-        .title("Here's your Dad Joke")
-        .description(&response.joke)
+        .title(tr!(ctx, "dad-joke-title"))
+        .description(joke)
         .color(serenity::Color::from_rgb(255, 165, 0)) // Orange color for humor
-        .footer(serenity::CreateEmbedFooter::new(format!(
-            "Joke ID: {} | Powered by icanhazdadjoke.com",
-            response.id
-        )))
+        .footer(serenity::CreateEmbedFooter::new(footer))
         .timestamp(serenity::Timestamp::now());
+    let mut reply = poise::CreateReply::default().embed(embed);
 
-    // Create a button to link to the joke on the website
-    let joke_link_button =
-        serenity::CreateButton::new_link(format!("https://icanhazdadjoke.com/j/{}", response.id))
-            .label("🔗 View on Website");
-
-    let action_row = serenity::CreateActionRow::Buttons(vec![joke_link_button].into());
+    // Create a button to link to the joke on the website, which bundled jokes aren't on
+    if let Some(id) = id {
+        let joke_link_button = serenity::CreateButton::new_link(format!("{}/j/{}", base_url, id))
+            .label(tr!(ctx, "dad-joke-view"));
+        let action_row = serenity::CreateActionRow::Buttons(vec![joke_link_button].into());
+        reply = reply.components(vec![action_row]);
+    }
 
-    ctx.send(
-        poise::CreateReply::default()
-            .embed(embed)
-            .components(vec![action_row]),
-    )
-    .await?;
+    ctx.send(reply).await?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot_lib::mock_api::{self, Failure, context_for, server_responding};
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn requests_json_and_parses_joke() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/"))
+            .and(header("accept", "application/json"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "R7UfaahVfFd",
+                "joke": "My dog used to chase people on a bike a lot. It got so bad I had to take his bike away.",
+                "status": 200
+            })))
+            .mount(&server)
+            .await;
+
+        let joke = fetch_dad_joke(&HttpClient::for_tests(0), &server.uri())
+            .await
+            .unwrap();
+
+        assert_eq!(joke.id, "R7UfaahVfFd");
+        assert!(joke.joke.starts_with("My dog"));
+    }
+
+    #[tokio::test]
+    async fn rejects_html_response() {
+        let server = server_responding(
+            "/",
+            ResponseTemplate::new(200).set_body_string("<!DOCTYPE html><p>A joke</p>"),
+        )
+        .await;
+
+        let result = fetch_dad_joke(&HttpClient::for_tests(0), &server.uri()).await;
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn rejects_broken_responses() {
+        mock_api::assert_rejects("/", &Failure::ALL, |http, url| async move {
+            fetch_dad_joke(&http, &url).await
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn links_joke_on_website() {
+        let server = server_responding("/", ResponseTemplate::new(200).set_body_json(
+            serde_json::json!({ "id": "abc", "joke": "I'm reading a book on anti-gravity.", "status": 200 }),
+        ))
+        .await;
+        let ctx = context_for(&server, |apis| &mut apis.dad_joke).await;
+
+        run(&ctx).await.unwrap();
+
+        let reply = ctx.reply();
+        assert_eq!(
+            reply.embeds[0]["description"],
+            "I'm reading a book on anti-gravity."
+        );
+        assert_eq!(
+            reply.components[0]["components"][0]["url"],
+            format!("{}/j/abc", server.uri())
+        );
+    }
+
+    #[tokio::test]
+    async fn bundled_jokes_have_no_link() {
+        let server = server_responding("/", ResponseTemplate::new(503)).await;
+        let ctx = context_for(&server, |apis| &mut apis.dad_joke).await;
+
+        run(&ctx).await.unwrap();
+
+        let reply = ctx.reply();
+        let joke = reply.embeds[0]["description"].as_str().unwrap();
+        assert!(PackKind::DadJoke.bundled().any(|entry| entry == joke));
+        assert_eq!(
+            reply.embeds[0]["footer"]["text"],
+            tr!(&ctx, "content-pack-footer")
+        );
+        assert!(reply.components.is_empty());
+    }
+}
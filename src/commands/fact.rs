@@ -1,8 +1,16 @@
+use crate::bot_lib::command_context::CommandContext;
+use crate::bot_lib::content_packs::{self, PackKind, Pick};
+use crate::bot_lib::http::HttpClient;
+use crate::bot_lib::i18n::tr;
 use crate::{Context, Error};
 use poise::serenity_prelude as serenity;
 use serde::Deserialize;
+use std::time::Duration;
 
-#[derive(Deserialize)]
+/// How long the fact of the day is cached
+const TODAY_TTL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Clone, Deserialize)]
 struct UselessFact {
     text: String,
     source: Option<String>,
@@ -12,30 +20,41 @@ struct UselessFact {
 }
 
 /// Call the useless facts API
-async fn call_facts_api(mode: &str) -> Result<UselessFact, Error> {
-    let client = reqwest::Client::new();
-    let url = "https://api.viewbits.com/v1/uselessfacts";
-
-    let response = client.get(url).query(&[("mode", mode)]).send().await?;
+async fn call_facts_api(
+    http: &HttpClient,
+    base_url: &str,
+    mode: &str,
+) -> Result<UselessFact, Error> {
+    let url = format!("{}/v1/uselessfacts", base_url);
+    let request = http.get(&url).query(&[("mode", mode)]);
 
-    let fact: UselessFact = response.json().await?;
+    // Random ones must differ on every call, only the one of the day can be cached
+    let fact: UselessFact = if mode == "today" {
+        http.cached_json("viewbits", TODAY_TTL, request).await?
+    } else {
+        http.json("viewbits", request).await?
+    };
     Ok(fact)
 }
 
 /// Create fact embed for Discord
-fn create_fact_embed<'a>(fact: &UselessFact, mode: &str) -> serenity::CreateEmbed<'a> {
+async fn create_fact_embed<'a>(
+    ctx: impl CommandContext,
+    fact: &UselessFact,
+    mode: &str,
+) -> serenity::CreateEmbed<'a> {
     let mut embed = serenity::CreateEmbed::new();
 
     // Set title and color based on mode
     match mode {
         "today" => {
-            embed = embed.title("🧠 Useless Fact of the Day").color(0x4682B4); // Steel blue color for daily fact
+            embed = embed.title(tr!(ctx, "fact-title-today")).color(0x4682B4); // Steel blue color for daily fact
         }
         "random" => {
-            embed = embed.title("🤓 Random Useless Fact").color(0x20B2AA); // Light sea green color for random fact
+            embed = embed.title(tr!(ctx, "fact-title-random")).color(0x20B2AA); // Light sea green color for random fact
         }
         _ => {
-            embed = embed.title("🤓 Useless Fact").color(0x20B2AA);
+            embed = embed.title(tr!(ctx, "fact-title")).color(0x20B2AA);
         }
     }
 
@@ -44,9 +63,10 @@ fn create_fact_embed<'a>(fact: &UselessFact, mode: &str) -> serenity::CreateEmbe
 
     // Add source as footer if available
     if let Some(source) = &fact.source {
-        embed = embed.footer(serenity::CreateEmbedFooter::new(format!(
-            "Source: {}",
-            source
+        embed = embed.footer(serenity::CreateEmbedFooter::new(tr!(
+            ctx,
+            "source",
+            source = source.as_str()
         )));
     }
 
@@ -64,13 +84,16 @@ pub enum Mode {
 
 #[poise::command(
     slash_command,
-    description_localized("en-US", "Get a useless fact."),
+    prefix_command,
+    category = "Fun",
+    track_edits,
     user_cooldown = 20
 )]
-pub async fn fact(
-    ctx: Context<'_>,
-    #[description = "Type of fact"] mode: Option<Mode>,
-) -> Result<(), Error> {
+pub async fn fact(ctx: Context<'_>, mode: Option<Mode>) -> Result<(), Error> {
+    run(ctx, mode).await
+}
+
+pub async fn run(ctx: impl CommandContext, mode: Option<Mode>) -> Result<(), Error> {
     // Validate mode
     // let mode = mode.unwrap_or("random");
 
@@ -80,26 +103,155 @@ pub async fn fact(
         Some(Mode::Today) => "today",
         _ => "random", // Default to random if not specified
     };
-    match call_facts_api(&mode).await {
-        Ok(fact) => {
-            let embed = create_fact_embed(&fact, &mode);
-            let mut reply = poise::CreateReply::default().embed(embed);
+    let data = ctx.data();
+    let (fact, from_pack) =
+        match call_facts_api(&data.http, &data.config.apis.viewbits, &mode).await {
+            Ok(fact) => (fact, false),
+            Err(e) => {
+                tracing::warn!(error = %e, "Facts API unavailable, using the content pack");
+                let pick = if mode == "today" {
+                    Pick::Daily
+                } else {
+                    Pick::Random
+                };
+                let text =
+                    content_packs::pick_entry(&data.storage, ctx.guild_id(), PackKind::Fact, pick)
+                        .await;
+                let fact = UselessFact {
+                    text,
+                    source: None,
+                    url: None,
+                    html: None,
+                };
+                (fact, true)
+            }
+        };
 
-            // Add a button for the URL if available
-            if let Some(url) = &fact.url {
-                let button = serenity::CreateButton::new_link(url).label("🔗 View Source");
+    let mut embed = create_fact_embed(ctx, &fact, &mode).await;
+    if from_pack {
+        embed = embed.footer(serenity::CreateEmbedFooter::new(tr!(
+            ctx,
+            "content-pack-footer"
+        )));
+    }
+    let mut reply = poise::CreateReply::default().embed(embed);
 
-                let action_row = serenity::CreateActionRow::Buttons(vec![button].into());
-                reply = reply.components(vec![action_row]);
-            }
+    // Add a button for the URL if available
+    if let Some(url) = &fact.url {
+        let button = serenity::CreateButton::new_link(url).label(tr!(ctx, "view-source"));
 
-            ctx.send(reply).await?;
-        }
-        Err(e) => {
-            tracing::error!("Facts API error: {}", e);
-            ctx.say("❌ Sorry, I couldn't fetch a fact right now. The knowledge servers might be taking a break! 🤓").await?;
-        }
+        let action_row = serenity::CreateActionRow::Buttons(vec![button].into());
+        reply = reply.components(vec![action_row]);
     }
 
+    ctx.send(reply).await?;
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot_lib::mock_api::{self, Failure, context_for, server_responding};
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn parses_fact() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/uselessfacts"))
+            .and(query_param("mode", "today"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "text": "Bananas are berries.",
+                "source": "djtech.net",
+                "url": "https://example.com/fact"
+            })))
+            .mount(&server)
+            .await;
+
+        let fact = call_facts_api(&HttpClient::for_tests(0), &server.uri(), "today")
+            .await
+            .unwrap();
+
+        assert_eq!(fact.text, "Bananas are berries.");
+        assert_eq!(fact.source.as_deref(), Some("djtech.net"));
+        assert_eq!(fact.url.as_deref(), Some("https://example.com/fact"));
+    }
+
+    #[tokio::test]
+    async fn optional_fields_may_be_missing() {
+        let server = server_responding(
+            "/v1/uselessfacts",
+            ResponseTemplate::new(200).set_body_json(serde_json::json!({ "text": "Just text" })),
+        )
+        .await;
+
+        let fact = call_facts_api(&HttpClient::for_tests(0), &server.uri(), "random")
+            .await
+            .unwrap();
+
+        assert_eq!(fact.text, "Just text");
+        assert!(fact.source.is_none());
+        assert!(fact.url.is_none());
+    }
+
+    #[tokio::test]
+    async fn rejects_broken_responses() {
+        mock_api::assert_rejects("/v1/uselessfacts", &Failure::ALL, |http, url| async move {
+            call_facts_api(&http, &url, "random").await
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn replies_with_fact_of_the_day() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/uselessfacts"))
+            .and(query_param("mode", "today"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "text": "Bananas are berries.",
+                "source": "djtech.net",
+                "url": "https://example.com/fact"
+            })))
+            .mount(&server)
+            .await;
+        let ctx = context_for(&server, |apis| &mut apis.viewbits).await;
+
+        run(&ctx, Some(Mode::Today)).await.unwrap();
+
+        let reply = ctx.reply();
+        let embed = &reply.embeds[0];
+        assert_eq!(embed["title"], tr!(&ctx, "fact-title-today"));
+        assert_eq!(embed["description"], "Bananas are berries.");
+        assert_eq!(
+            embed["footer"]["text"],
+            tr!(&ctx, "source", source = "djtech.net")
+        );
+        assert_eq!(
+            reply.components[0]["components"][0]["url"],
+            "https://example.com/fact"
+        );
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_content_pack() {
+        let server = server_responding("/v1/uselessfacts", ResponseTemplate::new(500)).await;
+        let mut ctx = context_for(&server, |apis| &mut apis.viewbits).await;
+        ctx.locale = Some("de".to_string());
+
+        run(&ctx, None).await.unwrap();
+
+        let reply = ctx.reply();
+        let embed = &reply.embeds[0];
+        assert_eq!(embed["title"], tr!(&ctx, "fact-title-random"));
+        assert!(
+            PackKind::Fact
+                .bundled()
+                .any(|entry| embed["description"] == entry)
+        );
+        assert_eq!(embed["footer"]["text"], "📦 Aus dem Offline-Inhaltspaket");
+        assert!(reply.components.is_empty());
+    }
+}
@@ -1,8 +1,16 @@
+use crate::bot_lib::command_context::CommandContext;
+use crate::bot_lib::content_packs::{self, PackKind, Pick};
+use crate::bot_lib::http::HttpClient;
+use crate::bot_lib::i18n::tr;
 use crate::{Context, Error};
 use poise::serenity_prelude as serenity;
 use serde::Deserialize;
+use std::time::Duration;
 
-#[derive(Deserialize)]
+/// How long the fortune of the day is cached
+const TODAY_TTL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Clone, Deserialize)]
 struct FortuneCookie {
     text: String,
     source: Option<String>,
@@ -12,30 +20,43 @@ struct FortuneCookie {
 }
 
 /// Call the fortune cookie API
-async fn call_fortune_api(mode: &str) -> Result<FortuneCookie, Error> {
-    let client = reqwest::Client::new();
-    let url = "https://api.viewbits.com/v1/fortunecookie";
-
-    let response = client.get(url).query(&[("mode", mode)]).send().await?;
+async fn call_fortune_api(
+    http: &HttpClient,
+    base_url: &str,
+    mode: &str,
+) -> Result<FortuneCookie, Error> {
+    let url = format!("{}/v1/fortunecookie", base_url);
+    let request = http.get(&url).query(&[("mode", mode)]);
 
-    let fortune: FortuneCookie = response.json().await?;
+    // Random ones must differ on every call, only the one of the day can be cached
+    let fortune: FortuneCookie = if mode == "today" {
+        http.cached_json("viewbits", TODAY_TTL, request).await?
+    } else {
+        http.json("viewbits", request).await?
+    };
     Ok(fortune)
 }
 
 /// Create fortune embed for Discord
-fn create_fortune_embed<'a>(fortune: &FortuneCookie, mode: &str) -> serenity::CreateEmbed<'a> {
+async fn create_fortune_embed<'a>(
+    ctx: impl CommandContext,
+    fortune: &FortuneCookie,
+    mode: &str,
+) -> serenity::CreateEmbed<'a> {
     let mut embed = serenity::CreateEmbed::new();
 
     // Set title and color based on mode
     match mode {
         "today" => {
-            embed = embed.title("🔮 Fortune Cookie of the Day").color(0x9400D3); // Purple color for daily fortune
+            embed = embed.title(tr!(ctx, "fortune-title-today")).color(0x9400D3); // Purple color for daily fortune
         }
         "random" => {
-            embed = embed.title("🥠 Your Fortune Cookie").color(0xFFD700); // Gold color for random fortune
+            embed = embed
+                .title(tr!(ctx, "fortune-title-random"))
+                .color(0xFFD700); // Gold color for random fortune
         }
         _ => {
-            embed = embed.title("🥠 Fortune Cookie").color(0xFFD700);
+            embed = embed.title(tr!(ctx, "fortune-title")).color(0xFFD700);
         }
     }
 
@@ -44,14 +65,15 @@ fn create_fortune_embed<'a>(fortune: &FortuneCookie, mode: &str) -> serenity::Cr
 
     // Add lucky numbers as a field if available
     if let Some(numbers) = &fortune.numbers {
-        embed = embed.field("🍀 Lucky Numbers", numbers.clone(), false);
+        embed = embed.field(tr!(ctx, "fortune-lucky-numbers"), numbers.clone(), false);
     }
 
     // Add source as footer if available
     if let Some(source) = &fortune.source {
-        embed = embed.footer(serenity::CreateEmbedFooter::new(format!(
-            "Source: {}",
-            source
+        embed = embed.footer(serenity::CreateEmbedFooter::new(tr!(
+            ctx,
+            "source",
+            source = source.as_str()
         )));
     }
 
@@ -63,45 +85,179 @@ fn create_fortune_embed<'a>(fortune: &FortuneCookie, mode: &str) -> serenity::Cr
 
 #[poise::command(
     slash_command,
-    description_localized("en-US", "Get a fortune cookie message."),
+    prefix_command,
+    category = "Fun",
+    track_edits,
     user_cooldown = "20"
 )]
 pub async fn fortune(
     ctx: Context<'_>,
-    #[description = "Type of fortune"]
-    #[choices("random", "today")]
-    mode: &'static str,
+    #[choices("random", "today")] mode: &'static str,
 ) -> Result<(), Error> {
+    run(ctx, mode).await
+}
+
+pub async fn run(ctx: impl CommandContext, mode: &str) -> Result<(), Error> {
     // Validate mode
     if !["random", "today"].contains(&mode) {
-        ctx.say("❌ Invalid mode. Please choose 'random' or 'today'.")
-            .await?;
+        ctx.say(tr!(ctx, "fortune-invalid-mode")).await?;
         return Ok(());
     }
 
     // Show typing indicator since API calls might take time
     ctx.defer().await?;
 
-    match call_fortune_api(&mode).await {
-        Ok(fortune) => {
-            let embed = create_fortune_embed(&fortune, &mode);
-            let mut reply = poise::CreateReply::default().embed(embed);
+    let data = ctx.data();
+    let (fortune, from_pack) = match call_fortune_api(&data.http, &data.config.apis.viewbits, &mode)
+        .await
+    {
+        Ok(fortune) => (fortune, false),
+        Err(e) => {
+            tracing::warn!(error = %e, "Fortune API unavailable, using the content pack");
+            let pick = if mode == "today" {
+                Pick::Daily
+            } else {
+                Pick::Random
+            };
+            let text =
+                content_packs::pick_entry(&data.storage, ctx.guild_id(), PackKind::Fortune, pick)
+                    .await;
+            let fortune = FortuneCookie {
+                text,
+                source: None,
+                url: None,
+                numbers: None,
+                _html: None,
+            };
+            (fortune, true)
+        }
+    };
 
-            // Add a button for the URL if available
-            if let Some(url) = &fortune.url {
-                let button = serenity::CreateButton::new_link(url).label("🔗 View Source");
+    let mut embed = create_fortune_embed(ctx, &fortune, &mode).await;
+    if from_pack {
+        embed = embed.footer(serenity::CreateEmbedFooter::new(tr!(
+            ctx,
+            "content-pack-footer"
+        )));
+    }
+    let mut reply = poise::CreateReply::default().embed(embed);
 
-                let action_row = serenity::CreateActionRow::Buttons(vec![button].into());
-                reply = reply.components(vec![action_row]);
-            }
+    // Add a button for the URL if available
+    if let Some(url) = &fortune.url {
+        let button = serenity::CreateButton::new_link(url).label(tr!(ctx, "view-source"));
 
-            ctx.send(reply).await?;
-        }
-        Err(e) => {
-            tracing::error!("Fortune API error: {}", e);
-            ctx.say("❌ Sorry, I couldn't fetch your fortune right now. The cosmic energies might be disrupted! 🔮").await?;
-        }
+        let action_row = serenity::CreateActionRow::Buttons(vec![button].into());
+        reply = reply.components(vec![action_row]);
     }
 
+    ctx.send(reply).await?;
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot_lib::fake_context::{self, FakeContext};
+    use crate::bot_lib::mock_api::{self, Failure, context_for, server_responding};
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const ROUTE: &str = "/v1/fortunecookie";
+
+    #[tokio::test]
+    async fn parses_fortune() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path(ROUTE))
+            .and(query_param("mode", "random"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "text": "You will write many tests.",
+                "numbers": "4, 8, 15, 16, 23, 42",
+                "source": "viewbits"
+            })))
+            .mount(&server)
+            .await;
+
+        let fortune = call_fortune_api(&HttpClient::for_tests(0), &server.uri(), "random")
+            .await
+            .unwrap();
+
+        assert_eq!(fortune.text, "You will write many tests.");
+        assert_eq!(fortune.numbers.as_deref(), Some("4, 8, 15, 16, 23, 42"));
+        assert_eq!(fortune.source.as_deref(), Some("viewbits"));
+        assert!(fortune.url.is_none());
+    }
+
+    #[tokio::test]
+    async fn rejects_missing_text() {
+        let server = server_responding(
+            ROUTE,
+            ResponseTemplate::new(200).set_body_json(serde_json::json!({ "numbers": "1, 2" })),
+        )
+        .await;
+
+        let result = call_fortune_api(&HttpClient::for_tests(0), &server.uri(), "today").await;
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn rejects_broken_responses() {
+        mock_api::assert_rejects(ROUTE, &Failure::ALL, |http, url| async move {
+            call_fortune_api(&http, &url, "today").await
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn replies_with_lucky_numbers() {
+        let server = server_responding(
+            ROUTE,
+            ResponseTemplate::new(200).set_body_json(
+                serde_json::json!({ "text": "You will write many tests.", "numbers": "4, 8, 15" }),
+            ),
+        )
+        .await;
+        let ctx = context_for(&server, |apis| &mut apis.viewbits).await;
+
+        run(&ctx, "random").await.unwrap();
+
+        let reply = ctx.reply();
+        let embed = &reply.embeds[0];
+        assert_eq!(embed["title"], tr!(&ctx, "fortune-title-random"));
+        assert_eq!(embed["description"], "You will write many tests.");
+        assert_eq!(
+            embed["fields"][0]["name"],
+            tr!(&ctx, "fortune-lucky-numbers")
+        );
+        assert_eq!(embed["fields"][0]["value"], "4, 8, 15");
+        assert!(embed.get("footer").is_none());
+        assert!(reply.components.is_empty());
+    }
+
+    #[tokio::test]
+    async fn picks_daily_fortune_from_content_pack() {
+        let server = server_responding(ROUTE, ResponseTemplate::new(500)).await;
+        let ctx = context_for(&server, |apis| &mut apis.viewbits).await;
+
+        run(&ctx, "today").await.unwrap();
+        run(&ctx, "today").await.unwrap();
+
+        let replies = ctx.replies();
+        let first = &replies[0].embeds[0];
+        assert_eq!(first["title"], tr!(&ctx, "fortune-title-today"));
+        assert_eq!(first["footer"]["text"], tr!(&ctx, "content-pack-footer"));
+        assert_eq!(first["description"], replies[1].embeds[0]["description"]);
+    }
+
+    #[tokio::test]
+    async fn rejects_unknown_mode() {
+        let ctx = FakeContext::new(fake_context::config()).await;
+
+        run(&ctx, "yesterday").await.unwrap();
+
+        assert!(!ctx.deferred());
+        assert_eq!(ctx.reply().content, Some(tr!(&ctx, "fortune-invalid-mode")));
+    }
+}
@@ -1,57 +1,269 @@
-use crate::bot_lib::meme_generator;
+use crate::bot_lib::command_context::CommandContext;
+use crate::bot_lib::i18n::tr;
+use crate::bot_lib::meme_generator::{EncodedMeme, Picture};
+use crate::bot_lib::meme_images::InvalidImage;
+use crate::bot_lib::meme_templates::{self, DEFAULT_BOXES, FontSettings, MemeTemplate};
+use crate::bot_lib::metrics::metrics;
+use crate::bot_lib::{meme_generator, meme_images};
+use crate::config::MemeConfig;
 use crate::{Context, Error};
 use ::serenity::all::colours;
 use poise::serenity_prelude as serenity;
 use tracing::debug;
 
-async fn autocomplete_meme_template<'a>(
-    _ctx: Context<'_>,
+/// Suggests templates whose file name or name starts with, or a tag is, what was typed
+pub async fn autocomplete_meme_template<'a>(
+    ctx: Context<'_>,
     partial: &'a str,
 ) -> serenity::CreateAutocompleteResponse<'a> {
     let partial_lower = partial.to_lowercase();
-    let templates = meme_generator::get_meme_template_files().unwrap_or_default();
+    let templates = ctx
+        .data()
+        .meme_templates
+        .read()
+        .expect("Meme template lock poisoned")
+        .clone();
     let choices: Vec<serenity::AutocompleteChoice<'a>> = templates
         .into_iter()
-        .filter(move |template| {
-            let template_lower = template.to_lowercase();
-            template_lower.starts_with(&partial_lower)
-        })
-        .map(|name| serenity::AutocompleteChoice::new(name.clone(), name))
+        .filter(|template| template.matches(&partial_lower))
+        .map(|template| serenity::AutocompleteChoice::new(template.name, template.file))
         .collect();
 
     serenity::CreateAutocompleteResponse::new().set_choices(choices)
 }
 
-/// Generate a meme with the specified template and text
-#[poise::command(
-    slash_command,
-    description_localized("en-US", "Generate a meme with the specified template and text.")
-)]
+/// What a meme is drawn on
+#[derive(Debug, Clone, PartialEq)]
+pub enum MemeBase {
+    /// A template, by file name or name
+    Template(String),
+    /// An image downloaded from a link, with how to refer to it in the reply
+    Image { url: String, label: String },
+}
+
+impl MemeBase {
+    /// An image link, referred to by itself
+    pub fn link(url: String) -> Self {
+        Self::Image {
+            label: url.clone(),
+            url,
+        }
+    }
+}
+
+/// Generate a meme from a template, an image or a member's avatar
+#[poise::command(slash_command, prefix_command, category = "Fun", track_edits)]
 pub async fn generate_meme(
     ctx: Context<'_>,
-    #[description = "Name of the meme template file"]
-    #[autocomplete = "autocomplete_meme_template"]
-    template: String,
-    #[description = "Text to display at the top of the meme"] top_text: String,
-    #[description = "Text to display at the bottom of the meme"] bottom_text: String,
+    #[autocomplete = "autocomplete_meme_template"] template: Option<String>,
+    top_text: Option<String>,
+    bottom_text: Option<String>,
+    image: Option<serenity::Attachment>,
+    image_url: Option<String>,
+    member: Option<serenity::Member>,
+) -> Result<(), Error> {
+    let bases = [
+        template.map(MemeBase::Template),
+        image.map(|image| MemeBase::Image {
+            url: image.url.to_string(),
+            label: image.filename.to_string(),
+        }),
+        image_url.map(MemeBase::link),
+        member.map(|member| MemeBase::Image {
+            url: member.face(),
+            label: member.display_name().to_string(),
+        }),
+    ];
+    run(
+        ctx,
+        bases.into_iter().flatten().collect(),
+        top_text.as_deref().unwrap_or_default(),
+        bottom_text.as_deref().unwrap_or_default(),
+    )
+    .await
+}
+
+pub async fn run(
+    ctx: impl CommandContext,
+    bases: Vec<MemeBase>,
+    top_text: &str,
+    bottom_text: &str,
+) -> Result<(), Error> {
+    let base = match <[MemeBase; 1]>::try_from(bases) {
+        Ok([base]) => base,
+        Err(bases) => {
+            let message = tr!(ctx, "meme-base-invalid", count = bases.len());
+            return ctx
+                .send(
+                    poise::CreateReply::default()
+                        .content(message)
+                        .ephemeral(true),
+                )
+                .await;
+        }
+    };
+
+    let captions = [top_text, bottom_text];
+    match base {
+        MemeBase::Template(name) => {
+            let Some(template) = find_template(ctx, &name).await? else {
+                return Ok(());
+            };
+            // Templates with a single box only get the top text
+            let count = captions.len().min(template.boxes.len());
+            send_meme(ctx, &template, &captions[..count]).await
+        }
+        MemeBase::Image { url, label } => send_image_meme(ctx, &url, &label, &captions).await,
+    }
+}
+
+/// Looks up a template by file name or name, telling the invoker if there is none
+pub async fn find_template(
+    ctx: impl CommandContext,
+    name: &str,
+) -> Result<Option<MemeTemplate>, Error> {
+    let data = ctx.data();
+    let found = {
+        let templates = data
+            .meme_templates
+            .read()
+            .expect("Meme template lock poisoned");
+        meme_templates::find(&templates, name).cloned()
+    };
+    if found.is_none() {
+        let message = tr!(ctx, "meme-unknown-template", template = name);
+        ctx.send(
+            poise::CreateReply::default()
+                .content(message)
+                .ephemeral(true),
+        )
+        .await?;
+    }
+    Ok(found)
+}
+
+/// Renders a meme from a template and sends it, or why that failed
+pub async fn send_meme(
+    ctx: impl CommandContext,
+    template: &MemeTemplate,
+    captions: &[impl AsRef<str>],
 ) -> Result<(), Error> {
     // Defer the response since meme generation might take a moment
     ctx.defer().await?;
 
-    // Generate the meme
-    match meme_generator::generate_meme_as_file_path(&template, &top_text, &bottom_text) {
-        Ok(meme_path) => {
-            // Create attachment from the file path
-            let attachment = serenity::CreateAttachment::path(&meme_path)?;
-            let filename = attachment.filename.clone();
+    let mut description = tr!(ctx, "meme-template", template = template.name.as_str());
+    if let Some(about) = &template.description {
+        description.push_str("\n-# ");
+        description.push_str(about);
+    }
+    let template = template.clone();
+    let captions = owned(captions);
+    let result = render(ctx, move || {
+        meme_generator::generate_meme(&template, &captions)
+    })
+    .await;
+
+    send_rendered(ctx, result, description).await
+}
+
+/// Downloads an image and captions it like a template without a manifest entry
+async fn send_image_meme(
+    ctx: impl CommandContext,
+    url: &str,
+    label: &str,
+    captions: &[&str],
+) -> Result<(), Error> {
+    let data = ctx.data();
+    let limits = &data.config.memes;
+    // Checked before deferring, as only a reply which wasn't deferred publicly can be private
+    let url = match meme_images::parse_url(url) {
+        Ok(url) => url,
+        Err(e) => return refuse_image(ctx, url, e, limits).await,
+    };
+
+    // Downloading and rendering might take a moment
+    ctx.defer().await?;
+
+    let picture = match meme_images::download(&data.image_http, url.clone(), limits).await {
+        Ok(picture) => picture,
+        Err(e) => return refuse_image(ctx, url.as_str(), e, limits).await,
+    };
+
+    let description = tr!(ctx, "meme-image", image = label);
+    let captions = owned(captions);
+    let result = render(ctx, move || {
+        meme_generator::caption_image(picture, &FontSettings::default(), &DEFAULT_BOXES, &captions)
+    })
+    .await;
+
+    send_rendered(ctx, result, description).await
+}
+
+/// Tells the invoker why an image can't be captioned
+async fn refuse_image(
+    ctx: impl CommandContext,
+    url: &str,
+    reason: InvalidImage,
+    limits: &MemeConfig,
+) -> Result<(), Error> {
+    debug!(error = %reason, url, "Refusing image to caption");
+    let message = tr!(
+        ctx,
+        "meme-image-invalid",
+        reason = reason.label(),
+        max_mb = limits.max_image_bytes as f64 / (1024.0 * 1024.0),
+        max_dimension = limits.max_image_dimension
+    );
+    ctx.send(
+        poise::CreateReply::default()
+            .content(message)
+            .ephemeral(true),
+    )
+    .await
+}
+
+/// Copies captions so they can be moved to the rendering thread
+fn owned(captions: &[impl AsRef<str>]) -> Vec<String> {
+    captions
+        .iter()
+        .map(|caption| caption.as_ref().to_string())
+        .collect()
+}
+
+/// Renders and encodes a meme on a blocking thread, as animations take a while
+async fn render(
+    ctx: impl CommandContext,
+    render: impl FnOnce() -> Result<Picture, Error> + Send + 'static,
+) -> Result<EncodedMeme, Error> {
+    let max_bytes = ctx.data().config.memes.max_upload_bytes;
+    tokio::task::spawn_blocking(move || {
+        let render_timer = metrics().meme_render_duration.start_timer();
+        let result = render().and_then(|picture| meme_generator::encode(picture, max_bytes));
+        render_timer.observe_duration();
+        result
+    })
+    .await?
+}
+
+/// Sends a rendered meme, or why rendering it failed
+async fn send_rendered(
+    ctx: impl CommandContext,
+    result: Result<EncodedMeme, Error>,
+    description: String,
+) -> Result<(), Error> {
+    match result {
+        Ok(meme) => {
+            // Upload the encoded meme straight from memory
+            let filename = meme.file_name();
+            let attachment = serenity::CreateAttachment::bytes(meme.bytes, filename.clone());
             let embed = serenity::CreateEmbed::default()
-                .title("Generated Meme")
-                .description(format!("Template: `{}`", template))
+                .title(tr!(ctx, "meme-title"))
+                .description(description)
                 .colour(colours::branding::BLACK)
-                .attachment(filename);
+                .attachment(filename.clone());
 
             // Send the meme as a response
-            debug!(attachment = ?attachment, "Sending generated meme");
+            debug!(%filename, bytes = attachment.data.len(), "Sending generated meme");
             ctx.send(
                 poise::CreateReply::default()
                     .embed(embed)
@@ -60,7 +272,7 @@ pub async fn generate_meme(
             .await?;
         }
         Err(e) => {
-            let error_msg = format!("Failed to generate meme: {}", e);
+            let error_msg = tr!(ctx, "meme-error", error = e.to_string());
             ctx.send(
                 poise::CreateReply::default()
                     .content(error_msg)
@@ -72,3 +284,96 @@ pub async fn generate_meme(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot_lib::fake_context::{self, FakeContext};
+
+    fn template(name: &str) -> Vec<MemeBase> {
+        vec![MemeBase::Template(name.to_string())]
+    }
+
+    #[tokio::test]
+    async fn reports_unknown_template() {
+        let ctx = FakeContext::new(fake_context::config()).await;
+
+        run(&ctx, template("No such template.png"), "top", "bottom")
+            .await
+            .unwrap();
+
+        let reply = ctx.reply();
+        assert!(reply.ephemeral);
+        assert!(reply.attachments.is_empty());
+        assert!(!ctx.deferred());
+        assert_eq!(
+            reply.content.as_deref(),
+            Some("❌ There is no meme template called `No such template.png`.")
+        );
+    }
+
+    #[tokio::test]
+    async fn renders_template_by_name() {
+        let ctx = FakeContext::new(fake_context::config()).await;
+
+        run(&ctx, template("sitting LION"), "top", "bottom")
+            .await
+            .unwrap();
+
+        let reply = ctx.reply();
+        assert!(ctx.deferred());
+        assert_eq!(reply.attachments.len(), 1);
+        assert_eq!(reply.attachments[0].filename, "meme.png");
+        assert_eq!(
+            reply.embeds[0]["description"],
+            "Template: `Sitting lion`\n-# A lion sitting and facing the camera"
+        );
+    }
+
+    #[tokio::test]
+    async fn needs_exactly_one_base() {
+        let ctx = FakeContext::new(fake_context::config()).await;
+        let image = MemeBase::Image {
+            url: "https://example.com/cat.png".to_string(),
+            label: "cat.png".to_string(),
+        };
+
+        run(&ctx, Vec::new(), "top", "bottom").await.unwrap();
+        run(&ctx, vec![image.clone(), image], "top", "bottom")
+            .await
+            .unwrap();
+
+        let replies = ctx.replies();
+        assert!(!ctx.deferred());
+        assert_eq!(
+            replies[0].content.as_deref(),
+            Some(
+                "❌ Choose a template, an image, an image link or a member to make the meme from."
+            )
+        );
+        assert_eq!(
+            replies[1].content.as_deref(),
+            Some("❌ Choose only one of a template, an image, an image link or a member.")
+        );
+    }
+
+    #[tokio::test]
+    async fn refuses_links_into_private_networks() {
+        let ctx = FakeContext::new(fake_context::config()).await;
+        let image = MemeBase::Image {
+            url: "http://127.0.0.1:9000/metrics".to_string(),
+            label: "metrics".to_string(),
+        };
+
+        run(&ctx, vec![image], "top", "bottom").await.unwrap();
+
+        let reply = ctx.reply();
+        assert!(!ctx.deferred());
+        assert!(reply.ephemeral);
+        assert!(reply.attachments.is_empty());
+        assert_eq!(
+            reply.content.as_deref(),
+            Some("❌ That image can't be used: only http(s) links to public hosts are allowed.")
+        );
+    }
+}
@@ -1,28 +1,78 @@
+use crate::bot_lib::command_context::CommandContext;
+use crate::bot_lib::i18n::tr;
 use crate::{Context, Error};
 use poise::serenity_prelude::{self as serenity, Mentionable};
 
-#[poise::command(
-    slash_command,
-    description_localized("en-US", "Let me Google that for you or someone else")
-)]
+/// Create the LMGTFY URL for a search
+fn lmgtfy_url(search: &str) -> String {
+    format!(
+        "https://letmegooglethat.com/?q={}",
+        urlencoding::encode(search)
+    )
+}
+
+#[poise::command(slash_command, prefix_command, category = "Utility", track_edits)]
 pub async fn let_me_google_that(
     ctx: Context<'_>,
-    #[description = "What should I google?"] search: String,
-    #[description = "Whom should I ping to look at the result?"] target: Option<serenity::Member>,
+    search: String,
+    target: Option<serenity::Member>,
 ) -> Result<(), Error> {
-    // URL encode the search query
-    let encoded_search = urlencoding::encode(&search);
+    run(ctx, &search, target.map(|member| member.user.id)).await
+}
 
-    // Create the LMGTFY URL
-    let lmgtfy_url = format!("https://letmegooglethat.com/?q={}", encoded_search);
+pub async fn run(
+    ctx: impl CommandContext,
+    search: &str,
+    target: Option<serenity::UserId>,
+) -> Result<(), Error> {
+    let lmgtfy_url = lmgtfy_url(search);
 
     // Format the response message based on whether target is provided
-    let response = if let Some(target_member) = target {
-        format!("{}, here you go: {}", target_member.mention(), lmgtfy_url)
+    let response = if let Some(target) = target {
+        tr!(
+            ctx,
+            "lmgtfy-reply-target",
+            target = target.mention().to_string(),
+            url = lmgtfy_url
+        )
     } else {
-        format!("Here you go: {}", lmgtfy_url)
+        tr!(ctx, "lmgtfy-reply", url = lmgtfy_url)
     };
 
     ctx.say(response).await?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot_lib::fake_context::{self, FakeContext};
+
+    #[test]
+    fn encodes_search() {
+        assert_eq!(
+            lmgtfy_url("rust & c++?"),
+            "https://letmegooglethat.com/?q=rust%20%26%20c%2B%2B%3F"
+        );
+    }
+
+    #[tokio::test]
+    async fn mentions_target() {
+        let ctx = FakeContext::new(fake_context::config()).await;
+
+        run(&ctx, "borrow checker", None).await.unwrap();
+        run(&ctx, "borrow checker", Some(serenity::UserId::new(42)))
+            .await
+            .unwrap();
+
+        let replies = ctx.replies();
+        assert_eq!(
+            replies[0].content.as_deref(),
+            Some("Here you go: https://letmegooglethat.com/?q=borrow%20checker")
+        );
+        assert_eq!(
+            replies[1].content.as_deref(),
+            Some("<@42>, here you go: https://letmegooglethat.com/?q=borrow%20checker")
+        );
+    }
+}
@@ -1,5 +1,8 @@
 mod about;
+mod admin;
 mod advice;
+mod caption_meme;
+mod config;
 mod dad_joke;
 mod fact;
 mod fortune;
@@ -8,17 +11,26 @@ mod let_me_google_that;
 mod pickup_line;
 mod ping;
 mod say;
+mod stats;
 mod user_info;
 mod xkcd;
 
 use crate::Data;
 use crate::Error;
+use crate::bot_lib::fake_context::FakeContext;
+use crate::bot_lib::i18n::translations;
+use crate::repl::Arguments;
 use poise::Command;
+use std::pin::Pin;
 
+/// All commands, with their descriptions and localizations taken from the message catalogues
 pub fn all_commands() -> Vec<Command<Data, Error>> {
-    vec![
+    let mut commands = vec![
         about::about(),
+        admin::admin(),
         advice::advice(),
+        caption_meme::caption_meme(),
+        config::config(),
         dad_joke::dad_joke(),
         fact::fact(),
         fortune::fortune(),
@@ -27,7 +39,143 @@ pub fn all_commands() -> Vec<Command<Data, Error>> {
         pickup_line::pickup_line(),
         ping::ping(),
         say::say(),
+        stats::stats(),
         user_info::user_info(),
         xkcd::xkcd(),
-    ]
+    ];
+    translations().apply(&mut commands);
+    commands
+}
+
+/// A command invocation whose options were read, ready to run against a fake context
+type LocalRun<'a> = Pin<Box<dyn Future<Output = Result<(), Error>> + 'a>>;
+
+/// Runs a command against a fake context with options parsed by the REPL, for the commands
+/// whose logic doesn't depend on Discord
+///
+/// Options the command doesn't look at are refused rather than silently dropped.
+pub async fn run_local(ctx: &FakeContext, name: &str, args: &Arguments) -> Result<(), Error> {
+    let run = prepare_local(ctx, name, args)?;
+    if let Some(option) = args.unread().first() {
+        return Err(format!("/{} can't use the option `{}` locally", name, option).into());
+    }
+    run.await
+}
+
+/// Reads the options of a command, returning the invocation without running it yet
+fn prepare_local<'a>(
+    ctx: &'a FakeContext,
+    name: &str,
+    args: &'a Arguments,
+) -> Result<LocalRun<'a>, Error> {
+    Ok(match name {
+        "about" => Box::pin(about::run(ctx)),
+        "advice" => Box::pin(advice::run(ctx)),
+        "caption_meme" => {
+            let template = args.required("template")?;
+            let captions = caption_meme::split_captions(args.required("captions")?);
+            Box::pin(async move { caption_meme::run(ctx, template, &captions).await })
+        }
+        "dad_joke" => Box::pin(dad_joke::run(ctx)),
+        "fact" => Box::pin(fact::run(ctx, args.choice("mode")?)),
+        "fortune" => Box::pin(fortune::run(ctx, args.required("mode")?)),
+        "generate_meme" => {
+            if args.get("image").is_some() || args.get("member").is_some() {
+                return Err("Attachments and avatars need Discord, use image_url instead".into());
+            }
+            let bases = [
+                args.get("template")
+                    .map(|template| generate_meme::MemeBase::Template(template.to_string())),
+                args.get("image_url")
+                    .map(|url| generate_meme::MemeBase::link(url.to_string())),
+            ];
+            Box::pin(generate_meme::run(
+                ctx,
+                bases.into_iter().flatten().collect(),
+                args.get("top_text").unwrap_or_default(),
+                args.get("bottom_text").unwrap_or_default(),
+            ))
+        }
+        "let_me_google_that" => Box::pin(let_me_google_that::run(
+            ctx,
+            args.required("search")?,
+            args.user("target")?,
+        )),
+        "pickup_line" => Box::pin(pickup_line::run(ctx, args.required("line_type")?)),
+        "say" => Box::pin(say::run(
+            ctx,
+            args.required("message")?,
+            args.get("character"),
+        )),
+        "stats" => Box::pin(stats::run(ctx, args.choice("window")?)),
+        "xkcd" => Box::pin(xkcd::run(
+            ctx,
+            args.parse("comic_number")?,
+            args.parse("random")?.unwrap_or(false),
+        )),
+        _ => return Err(format!("/{} needs Discord and can't run locally", name).into()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot_lib::fake_context;
+
+    /// A value every parameter accepts: one of its choices, or a number, which also passes as
+    /// text and user ID
+    fn value(parameter: &poise::CommandParameter<Data, Error>) -> (String, String) {
+        let value = match parameter.choices.first() {
+            Some(choice) => choice.name.to_string(),
+            None => "1".to_string(),
+        };
+        (parameter.name.to_string(), value)
+    }
+
+    #[tokio::test]
+    async fn local_commands_read_every_option() {
+        let ctx = FakeContext::new(fake_context::config()).await;
+        let mut local = Vec::new();
+
+        for command in all_commands() {
+            let name = &*command.name;
+            let required: Vec<_> = command
+                .parameters
+                .iter()
+                .filter(|parameter| parameter.required)
+                .map(value)
+                .collect();
+            let args = Arguments::new(required.clone());
+            // Commands may still refuse to run, e.g. /caption_meme without captions
+            let prepared = prepare_local(&ctx, name, &args);
+            if matches!(&prepared, Err(e) if e.to_string().ends_with("can't run locally")) {
+                continue;
+            }
+            assert!(
+                args.unread().is_empty(),
+                "/{} ignores {:?}",
+                name,
+                args.unread()
+            );
+            local.push(name.to_string());
+
+            for parameter in command
+                .parameters
+                .iter()
+                .filter(|parameter| !parameter.required)
+            {
+                let args = Arguments::new(required.iter().cloned().chain([value(parameter)]));
+                // Reading the option is all that matters, even if it's refused
+                let _ = prepare_local(&ctx, name, &args);
+                assert!(
+                    args.unread().is_empty(),
+                    "/{} ignores `{}`",
+                    name,
+                    parameter.name
+                );
+            }
+        }
+
+        assert!(local.contains(&"generate_meme".to_string()), "{:?}", local);
+    }
 }
@@ -1,13 +1,18 @@
 use crate::Error;
+use crate::bot_lib::command_context::CommandContext;
+use crate::bot_lib::content_packs::{self, PackKind, Pick};
+use crate::bot_lib::http::HttpClient;
+use crate::bot_lib::i18n::tr;
 
 /// Call the pickup/breakup line API
-async fn get_random_line(line_type: &str) -> Result<String, Error> {
-    let client = reqwest::Client::new();
-    let url = format!("https://api.jcwyt.com/{}", line_type);
+async fn get_random_line(
+    http: &HttpClient,
+    base_url: &str,
+    line_type: &str,
+) -> Result<String, Error> {
+    let url = format!("{}/{}", base_url, line_type);
 
-    let response = client.get(&url).send().await?;
-
-    let line_text = response.text().await?;
+    let line_text = http.text("jcwyt", http.get(&url)).await?;
     let trimmed_line = line_text.trim().to_string();
 
     if trimmed_line.is_empty() {
@@ -19,29 +24,110 @@ async fn get_random_line(line_type: &str) -> Result<String, Error> {
 
 #[poise::command(
     slash_command,
-    description_localized("en-US", "Get a pickup or breakup line."),
+    prefix_command,
+    category = "Fun",
+    track_edits,
     user_cooldown = "3"
 )]
 pub async fn pickup_line(
     ctx: crate::Context<'_>,
-    #[description = "Which type of lines to show"]
-    #[choices("pickup", "breakup")]
-    line_type: &'static str,
+    #[choices("pickup", "breakup")] line_type: &'static str,
 ) -> Result<(), Error> {
-    match get_random_line(&line_type).await {
+    run(ctx, line_type).await
+}
+
+pub async fn run(ctx: impl CommandContext, line_type: &str) -> Result<(), Error> {
+    // Show typing indicator since API calls might take time
+    ctx.defer().await?;
+
+    let data = ctx.data();
+    let base_url = &data.config.apis.pickup_line;
+    match get_random_line(&data.http, base_url, &line_type).await {
         Ok(line) => {
             ctx.say(line).await?;
         }
         Err(e) => {
-            tracing::error!("Pickup line API error: {}", e);
+            tracing::warn!(error = %e, "Pickup line API unavailable, using the content pack");
 
-            let error_reply = poise::CreateReply::default()
-                .content("❌ Sorry, I couldn't fetch a line right now. https://api.jcwyt.com/ might be down! 💔\n\nTry again later.")
-                .ephemeral(true);
-
-            ctx.send(error_reply).await?;
+            let kind = match line_type {
+                "breakup" => PackKind::Breakup,
+                _ => PackKind::Pickup,
+            };
+            let line =
+                content_packs::pick_entry(&data.storage, ctx.guild_id(), kind, Pick::Random).await;
+            ctx.say(format!("{}\n-# {}", line, tr!(ctx, "content-pack-footer")))
+                .await?;
         }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot_lib::mock_api::{self, Failure, context_for, server_responding};
+    use wiremock::{MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn trims_line() {
+        let server = server_responding(
+            "/pickup",
+            ResponseTemplate::new(200).set_body_string("  Are you a compiler?\n"),
+        )
+        .await;
+
+        let line = get_random_line(&HttpClient::for_tests(0), &server.uri(), "pickup")
+            .await
+            .unwrap();
+
+        assert_eq!(line, "Are you a compiler?");
+    }
+
+    #[tokio::test]
+    async fn rejects_blank_body() {
+        let server =
+            server_responding("/pickup", ResponseTemplate::new(200).set_body_string(" \n")).await;
+
+        let result = get_random_line(&HttpClient::for_tests(0), &server.uri(), "pickup").await;
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn rejects_broken_responses() {
+        // The API answers with plain text, so there is no JSON to break
+        let failures = [Failure::ServerError, Failure::EmptyBody];
+        mock_api::assert_rejects("/pickup", &failures, |http, url| async move {
+            get_random_line(&http, &url, "pickup").await
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn replies_with_line() {
+        let server = server_responding(
+            "/pickup",
+            ResponseTemplate::new(200).set_body_string("Are you a compiler?\n"),
+        )
+        .await;
+        let ctx = context_for(&server, |apis| &mut apis.pickup_line).await;
+
+        run(&ctx, "pickup").await.unwrap();
+
+        assert!(ctx.deferred());
+        assert_eq!(ctx.reply().content.as_deref(), Some("Are you a compiler?"));
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_matching_content_pack() {
+        let server = MockServer::start().await;
+        let ctx = context_for(&server, |apis| &mut apis.pickup_line).await;
+
+        run(&ctx, "breakup").await.unwrap();
+
+        let content = ctx.reply().content.unwrap();
+        let (line, _) = content.split_once("\n-# ").unwrap();
+        assert!(PackKind::Breakup.bundled().any(|entry| entry == line));
+    }
+}
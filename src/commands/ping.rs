@@ -1,11 +1,12 @@
+use crate::bot_lib::command_context::CommandContext;
+use crate::bot_lib::i18n::tr;
+use crate::bot_lib::shards::stage_label;
 use crate::{Context, Error};
+use poise::serenity_prelude as serenity;
 
-#[poise::command(
-    slash_command,
-    description_localized("en-US", "Ping the bot to check latency.")
-)]
+#[poise::command(slash_command, prefix_command, category = "Utility", track_edits)]
 pub async fn ping(ctx: Context<'_>) -> Result<(), Error> {
-    let response = ctx.say("Pong!").await?;
+    let response = ctx.say(tr!(ctx, "ping-pong")).await?;
 
     let res_timestamp = response
         .message()
@@ -19,6 +20,89 @@ pub async fn ping(ctx: Context<'_>) -> Result<(), Error> {
 
     let latency = res_timestamp - ctx_timestamp;
 
-    ctx.say(format!("Pong! Latency: {}ms", latency)).await?;
+    run(ctx, latency, ctx.serenity_context().shard_id).await
+}
+
+/// Reports the latency measured by the first reply and the state of the shard it was sent on
+pub async fn run(
+    ctx: impl CommandContext,
+    latency: i64,
+    current: serenity::ShardId,
+) -> Result<(), Error> {
+    let mut reply = tr!(ctx, "ping-latency", latency = latency);
+
+    let shards = ctx.data().shards.snapshot();
+    if let Some((id, state)) = shards.iter().find(|(id, _)| *id == current.0) {
+        reply.push_str("\n-# ");
+        reply.push_str(&tr!(
+            ctx,
+            "ping-shard",
+            shard = *id,
+            stage = stage_label(state.stage),
+            reconnects = state.reconnects
+        ));
+        if let Some(heartbeat) = state.latency {
+            reply.push_str(&tr!(
+                ctx,
+                "ping-shard-heartbeat",
+                heartbeat = heartbeat.as_millis() as u64
+            ));
+        }
+    }
+    if shards.len() > 1 {
+        let connected = shards
+            .iter()
+            .filter(|(_, state)| state.stage == serenity::ConnectionStage::Connected)
+            .count();
+        reply.push_str("\n-# ");
+        reply.push_str(&tr!(
+            ctx,
+            "ping-shards-connected",
+            connected = connected,
+            total = shards.len()
+        ));
+    }
+
+    ctx.say(reply).await?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot_lib::fake_context::{self, FakeContext};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn reports_shard_state() {
+        let ctx = FakeContext::new(fake_context::config()).await;
+        let data = ctx.data();
+        let shards = &data.shards;
+        shards.set_stage(serenity::ShardId(0), serenity::ConnectionStage::Connected);
+        shards.set_latency(serenity::ShardId(0), Duration::from_millis(30));
+
+        run(&ctx, 42, serenity::ShardId(0)).await.unwrap();
+        assert_eq!(
+            ctx.reply().content.as_deref(),
+            Some("Pong! Latency: 42ms\n-# Shard 0: connected, 0 reconnects, heartbeat 30ms")
+        );
+    }
+
+    #[tokio::test]
+    async fn counts_connected_shards() {
+        let ctx = FakeContext::new(fake_context::config()).await;
+        let data = ctx.data();
+        let shards = &data.shards;
+        shards.set_stage(serenity::ShardId(0), serenity::ConnectionStage::Connected);
+        shards.set_stage(serenity::ShardId(1), serenity::ConnectionStage::Connecting);
+
+        run(&ctx, 42, serenity::ShardId(1)).await.unwrap();
+        assert_eq!(
+            ctx.reply().content.as_deref(),
+            Some(
+                "Pong! Latency: 42ms\n-# Shard 1: connecting, 0 reconnects\n\
+                 -# 1 of 2 shards in this process connected"
+            )
+        );
+    }
+}
@@ -1,5 +1,12 @@
+use crate::bot_lib::command_context::CommandContext;
+use crate::bot_lib::http::HttpClient;
+use crate::bot_lib::i18n::tr;
 use crate::{Context, Error};
 use poise::serenity_prelude as serenity;
+use std::time::Duration;
+
+/// How long rendered messages are cached, as the same text always renders the same
+const COWSAY_TTL: Duration = Duration::from_secs(24 * 60 * 60);
 
 /// Create a code block for Discord
 fn code_block(s: &str) -> String {
@@ -119,41 +126,50 @@ async fn character_autocomplete<'a>(
 }
 
 /// Call the cowsay API to generate ASCII art
-async fn call_cowsay_api(message: &str, cow_type: &str) -> Result<String, Error> {
-    let client = reqwest::Client::new();
-    let url = "https://easyapis.honeybeeks.net/api/cowsay";
-
-    let response = client
-        .get(url)
-        .query(&[("text", message), ("type", cow_type)])
-        .send()
-        .await?;
+async fn call_cowsay_api(
+    http: &HttpClient,
+    base_url: &str,
+    message: &str,
+    cow_type: &str,
+) -> Result<String, Error> {
+    let url = format!("{}/api/cowsay", base_url);
+    let request = http
+        .get(&url)
+        .query(&[("text", message), ("type", cow_type)]);
 
-    let response_text = response.text().await?;
+    let response_text = http.cached_text("cowsay", COWSAY_TTL, request).await?;
 
     // The API returns plain text, not JSON
     Ok(code_block(&response_text))
 }
 
-#[poise::command(
-    slash_command,
-    description_localized("en-US", "Say a custom message with an ASCII character.")
-)]
+#[poise::command(slash_command, prefix_command, category = "Fun", track_edits)]
 pub async fn say(
     ctx: Context<'_>,
-    #[description = "Message to say"] message: String,
-    #[description = "Which character?"]
-    #[autocomplete = "character_autocomplete"]
-    character: Option<String>,
+    message: String,
+    #[autocomplete = "character_autocomplete"] character: Option<String>,
+) -> Result<(), Error> {
+    run(ctx, &message, character.as_deref()).await
+}
+
+pub async fn run(
+    ctx: impl CommandContext,
+    message: &str,
+    character: Option<&str>,
 ) -> Result<(), Error> {
-    let character_str = character.unwrap_or_else(|| "default".to_string());
+    let character_str = character.unwrap_or("default");
 
     let message_text = message.trim().to_string();
 
-    let response = call_cowsay_api(&message_text, &character_str).await?;
+    ctx.defer().await?;
+
+    let data = ctx.data();
+    let base_url = &data.config.apis.cowsay;
+    let response = call_cowsay_api(&data.http, base_url, &message_text, character_str).await?;
 
     if response.len() > 2000 {
-        let too_long_msg = call_cowsay_api("That message was too long.", "default").await?;
+        let too_long_msg =
+            call_cowsay_api(&data.http, base_url, &tr!(ctx, "say-too-long"), "default").await?;
         ctx.say(too_long_msg).await?;
     } else {
         ctx.say(response).await?;
@@ -161,3 +177,53 @@ pub async fn say(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot_lib::mock_api::context_for;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn wraps_art_in_code_block() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/cowsay"))
+            .and(query_param("text", "moo"))
+            .and(query_param("type", "tux"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("< moo >"))
+            .mount(&server)
+            .await;
+        let ctx = context_for(&server, |apis| &mut apis.cowsay).await;
+
+        run(&ctx, "  moo ", Some("tux")).await.unwrap();
+
+        assert_eq!(ctx.reply().content.as_deref(), Some("```\n< moo >\n```"));
+    }
+
+    #[tokio::test]
+    async fn replaces_overly_long_art() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/cowsay"))
+            .and(query_param("text", "moo"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("o".repeat(2000)))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/cowsay"))
+            .and(query_param("type", "default"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("< too long >"))
+            .mount(&server)
+            .await;
+        let ctx = context_for(&server, |apis| &mut apis.cowsay).await;
+
+        run(&ctx, "moo", None).await.unwrap();
+
+        assert_eq!(
+            ctx.reply().content.as_deref(),
+            Some("```\n< too long >\n```")
+        );
+    }
+}
@@ -0,0 +1,206 @@
+use crate::bot_lib::command_context::CommandContext;
+use crate::bot_lib::i18n::tr;
+use crate::bot_lib::usage::unix_now;
+use crate::{Context, Error};
+use poise::serenity_prelude as serenity;
+
+/// How many commands and users are listed
+const TOP_COMMANDS: usize = 10;
+const TOP_USERS: u32 = 5;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, poise::ChoiceParameter)]
+pub enum Window {
+    Day,
+    #[default]
+    Week,
+    Month,
+    #[name = "All Time"]
+    AllTime,
+}
+
+impl Window {
+    /// Identifies the window in the message catalogues
+    fn label(self) -> &'static str {
+        match self {
+            Self::Day => "day",
+            Self::Week => "week",
+            Self::Month => "month",
+            Self::AllTime => "all",
+        }
+    }
+
+    /// Unix timestamp the window starts at
+    fn since(self, now: i64) -> i64 {
+        let days = match self {
+            Self::Day => 1,
+            Self::Week => 7,
+            Self::Month => 30,
+            Self::AllTime => return 0,
+        };
+        now - days * 24 * 60 * 60
+    }
+}
+
+/// Share of failed invocations in percent, formatted with one decimal
+fn failure_rate(failed: i64, total: i64) -> String {
+    format!("{:.1}", failed as f64 * 100.0 / total.max(1) as f64)
+}
+
+#[poise::command(
+    slash_command,
+    prefix_command,
+    category = "Utility",
+    guild_only,
+    default_member_permissions = "MANAGE_GUILD",
+    required_permissions = "MANAGE_GUILD",
+    track_edits
+)]
+pub async fn stats(ctx: Context<'_>, window: Option<Window>) -> Result<(), Error> {
+    run(ctx, window).await
+}
+
+pub async fn run(ctx: impl CommandContext, window: Option<Window>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Must be used in a server")?;
+    let window = window.unwrap_or_default();
+    let since = window.since(unix_now());
+
+    let data = ctx.data();
+    let storage = &data.storage;
+    let commands = storage.command_counts(guild_id, since).await?;
+    let users = storage.top_users(guild_id, since, TOP_USERS).await?;
+
+    let mut embed = serenity::CreateEmbed::default()
+        .title(tr!(ctx, "stats-title", window = window.label()))
+        .color(serenity::Color::from_rgb(88, 101, 242)) // Blurple color
+        .timestamp(serenity::Timestamp::now());
+
+    if commands.is_empty() {
+        embed = embed.description(tr!(ctx, "stats-empty"));
+    } else {
+        let total: i64 = commands.iter().map(|count| count.total).sum();
+        let failed: i64 = commands.iter().map(|count| count.failed).sum();
+        embed = embed.description(tr!(
+            ctx,
+            "stats-summary",
+            total = total,
+            failed = failed,
+            rate = failure_rate(failed, total)
+        ));
+
+        let mut lines = Vec::new();
+        for (i, count) in commands.iter().take(TOP_COMMANDS).enumerate() {
+            let mut line = tr!(
+                ctx,
+                "stats-top-command",
+                rank = i + 1,
+                command = count.command.as_str(),
+                total = count.total
+            );
+            if let Some(latency) = count.mean_latency_ms {
+                line.push_str(&format!(" (⌀ {:.0} ms)", latency));
+            }
+            lines.push(line);
+        }
+        embed = embed.field(tr!(ctx, "stats-top-commands"), lines.join("\n"), false);
+
+        let mut lines = Vec::new();
+        for (i, (user_id, total)) in users.iter().enumerate() {
+            // Snowflakes don't fit into the float Fluent formats numbers as
+            lines.push(tr!(
+                ctx,
+                "stats-top-user",
+                rank = i + 1,
+                user = user_id.get().to_string(),
+                total = *total
+            ));
+        }
+        embed = embed.field(tr!(ctx, "stats-top-users"), lines.join("\n"), false);
+
+        let mut failing: Vec<_> = commands.iter().filter(|count| count.failed > 0).collect();
+        failing.sort_by(|a, b| {
+            (b.failed * a.total)
+                .cmp(&(a.failed * b.total))
+                .then_with(|| b.total.cmp(&a.total))
+        });
+        let mut lines = Vec::new();
+        for count in failing.into_iter().take(TOP_COMMANDS) {
+            lines.push(tr!(
+                ctx,
+                "stats-failure-rate",
+                command = count.command.as_str(),
+                rate = failure_rate(count.failed, count.total),
+                failed = count.failed,
+                total = count.total
+            ));
+        }
+        if lines.is_empty() {
+            lines.push(tr!(ctx, "stats-no-failures"));
+        }
+        embed = embed.field(tr!(ctx, "stats-failure-rates"), lines.join("\n"), false);
+    }
+
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot_lib::fake_context::{self, FakeContext};
+    use crate::storage::CommandUsage;
+
+    #[test]
+    fn windows_end_now() {
+        let now = 1_000_000_000;
+        assert_eq!(Window::Day.since(now), now - 86_400);
+        assert_eq!(Window::Week.since(now), now - 7 * 86_400);
+        assert_eq!(Window::AllTime.since(now), 0);
+    }
+
+    #[test]
+    fn formats_failure_rates() {
+        assert_eq!(failure_rate(1, 3), "33.3");
+        assert_eq!(failure_rate(0, 0), "0.0");
+    }
+
+    #[tokio::test]
+    async fn reports_usage_of_the_guild() {
+        let mut ctx = FakeContext::new(fake_context::config()).await;
+        ctx.guild_id = Some(serenity::GuildId::new(1));
+        for (command, outcome) in [("xkcd", "success"), ("xkcd", "error"), ("fact", "success")] {
+            let usage = CommandUsage {
+                command: command.to_string(),
+                guild_id: ctx.guild_id,
+                channel_id: serenity::ChannelId::new(2),
+                user_id: serenity::UserId::new(3),
+                outcome: outcome.to_string(),
+                failed: outcome == "error",
+                latency: None,
+            };
+            ctx.data().storage.record_usage(&usage).await.unwrap();
+        }
+
+        run(&ctx, None).await.unwrap();
+
+        let reply = ctx.reply();
+        assert!(reply.ephemeral);
+        let embed = &reply.embeds[0];
+        assert_eq!(embed["title"], tr!(&ctx, "stats-title", window = "week"));
+        assert_eq!(embed["description"], "3 commands used, 1 failed (33.3%).");
+        assert_eq!(
+            embed["fields"][0]["value"],
+            "1. `/xkcd` — 2 uses\n2. `/fact` — 1 use"
+        );
+        assert_eq!(embed["fields"][1]["value"], "1. <@3> — 3 commands");
+        assert_eq!(embed["fields"][2]["value"], "`/xkcd` — 50.0% (1 of 2)");
+    }
+
+    #[tokio::test]
+    async fn needs_a_guild() {
+        let ctx = FakeContext::new(fake_context::config()).await;
+
+        assert!(run(&ctx, None).await.is_err());
+        assert!(ctx.replies().is_empty());
+    }
+}
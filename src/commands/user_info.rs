@@ -1,14 +1,13 @@
+use crate::bot_lib::command_context::CommandContext;
+use crate::bot_lib::i18n::tr;
 use crate::{Context, Error};
 use ::serenity::all::CreateEmbed;
 use poise::{CreateReply, serenity_prelude as serenity};
 
-#[poise::command(
-    slash_command,
-    description_localized("en-US", "Get information about a user in the server or yourself")
-)]
+#[poise::command(slash_command, prefix_command, category = "Utility", track_edits)]
 pub async fn user_info(
     ctx: Context<'_>,
-    #[description = "Selected user"] guild_member: Option<serenity::Member>,
+    guild_member: Option<serenity::Member>,
 ) -> Result<(), Error> {
     let member = if let Some(member) = guild_member {
         member
@@ -16,23 +15,74 @@ pub async fn user_info(
         ctx.author_member().await.unwrap().into_owned()
     };
 
-    let created_at = member.user.id.created_at().timestamp();
-    let joined_at = member.joined_at.unwrap().timestamp();
+    run(
+        ctx,
+        member.user.id,
+        &member.user.name,
+        member.user.avatar_url(),
+        member.joined_at.unwrap(),
+    )
+    .await
+}
+
+/// Shows when a member joined Discord and the server
+pub async fn run(
+    ctx: impl CommandContext,
+    user_id: serenity::UserId,
+    name: &str,
+    avatar_url: Option<String>,
+    joined_at: serenity::Timestamp,
+) -> Result<(), Error> {
+    let created_at = user_id.created_at().timestamp();
+    let joined_at = joined_at.timestamp();
 
-    let response = format!(
-        "Joined Discord on: <t:{}:F> (<t:{}:R>)\nJoined this server on: <t:{}:F> (<t:{}:R>)",
-        created_at, created_at, joined_at, joined_at,
+    let response = tr!(
+        ctx,
+        "user-info-joined",
+        created = created_at,
+        joined = joined_at
     );
 
     let embed = CreateEmbed::default()
-        .title(format!("About {}", member.user.name))
+        .title(tr!(ctx, "user-info-title", name = name))
         .description(response)
         .timestamp(serenity::Timestamp::now())
         .color(serenity::Color::from_rgb(0, 255, 0))
-        .thumbnail(member.user.avatar_url().unwrap_or_default());
+        .thumbnail(avatar_url.unwrap_or_default());
 
     let reply = CreateReply::default().embed(embed);
 
     ctx.send(reply).await?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot_lib::fake_context::{self, FakeContext};
+
+    #[tokio::test]
+    async fn shows_join_dates() {
+        let ctx = FakeContext::new(fake_context::config()).await;
+        let joined_at = serenity::Timestamp::from_unix_timestamp(1_600_000_000).unwrap();
+
+        run(
+            &ctx,
+            serenity::UserId::new(80351110224678912),
+            "ferris",
+            Some("https://cdn.example/ferris.png".to_string()),
+            joined_at,
+        )
+        .await
+        .unwrap();
+
+        let embed = &ctx.reply().embeds[0];
+        assert_eq!(embed["title"], "About ferris");
+        assert_eq!(
+            embed["description"],
+            "Joined Discord on: <t:1439227597:F> (<t:1439227597:R>)\n\
+             Joined this server on: <t:1600000000:F> (<t:1600000000:R>)"
+        );
+        assert_eq!(embed["thumbnail"]["url"], "https://cdn.example/ferris.png");
+    }
+}
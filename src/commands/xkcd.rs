@@ -1,11 +1,41 @@
+use crate::bot_lib::command_context::CommandContext;
+use crate::bot_lib::http::HttpClient;
+use crate::bot_lib::i18n::tr;
 use crate::{Context, Error};
 use poise::serenity_prelude as serenity;
+use rand::Rng;
 use serde_json::Value;
 use serenity::all::{CreateActionRow, CreateButton};
+use std::time::Duration;
+
+/// How long the number of the latest comic is cached, as new comics come out thrice a week
+const LATEST_COMIC_TTL: Duration = Duration::from_secs(15 * 60);
+
+/// Fetch the number of the latest xkcd comic
+async fn fetch_latest_comic_number(http: &HttpClient, base_url: &str) -> Result<u32, Error> {
+    let request = http.get(&format!("{}/info.0.json", base_url));
+    let latest_response = http
+        .cached_json::<Value>("xkcd", LATEST_COMIC_TTL, request)
+        .await?;
+
+    Ok(latest_response["num"].as_u64().unwrap_or(1) as u32)
+}
+
+/// Pick the comic to send: a random one, the requested one if it exists, otherwise the latest
+fn target_comic(latest: u32, requested: Option<u32>, random: bool, rng: &mut impl Rng) -> u32 {
+    if random {
+        rng.random_range(1..=latest)
+    } else {
+        match requested {
+            Some(num) if num > 0 && num <= latest => num,
+            _ => latest,
+        }
+    }
+}
 
 /// Autocomplete function for comic numbers
 async fn comic_autocomplete<'a>(
-    _ctx: Context<'_>,
+    ctx: Context<'_>,
     partial: &'a str,
 ) -> serenity::CreateAutocompleteResponse<'a> {
     let mut choices = Vec::new();
@@ -33,7 +63,7 @@ async fn comic_autocomplete<'a>(
         if num > 0 && num <= 3000 {
             // Reasonable upper bound for xkcd comics
             choices.push(serenity::AutocompleteChoice::new(
-                format!("Comic #{}", num),
+                tr!(ctx, "xkcd-comic", number = num),
                 num.to_string(),
             ));
         }
@@ -42,13 +72,13 @@ async fn comic_autocomplete<'a>(
         for offset in [1, 2, 3, 5, 10] {
             if num + offset <= 3000 {
                 choices.push(serenity::AutocompleteChoice::new(
-                    format!("Comic #{}", num + offset),
+                    tr!(ctx, "xkcd-comic", number = num + offset),
                     (num + offset).to_string(),
                 ));
             }
             if num >= offset && num - offset > 0 {
                 choices.push(serenity::AutocompleteChoice::new(
-                    format!("Comic #{}", num - offset),
+                    tr!(ctx, "xkcd-comic", number = num - offset),
                     (num - offset).to_string(),
                 ));
             }
@@ -72,7 +102,7 @@ async fn comic_autocomplete<'a>(
         for (num, keywords) in popular_comics.iter() {
             if keywords.contains(&search_term) {
                 choices.push(serenity::AutocompleteChoice::new(
-                    format!("Comic #{}", num),
+                    tr!(ctx, "xkcd-comic", number = num),
                     num.to_string(),
                 ));
             }
@@ -85,49 +115,34 @@ async fn comic_autocomplete<'a>(
 }
 
 /// Fetch an xkcd comic
-#[poise::command(
-    slash_command,
-    description_localized(
-        "en-US",
-        "Fetch an xkcd comic. Leave empty for latest comic, provide a number, or get a random one."
-    )
-)]
+#[poise::command(slash_command, prefix_command, category = "Fun", track_edits)]
 pub async fn xkcd(
     ctx: Context<'_>,
-    #[description = "Comic number (leave empty for latest)"]
-    #[autocomplete = "comic_autocomplete"]
+    #[autocomplete = "comic_autocomplete"] comic_number: Option<u32>,
+    random: Option<bool>,
+) -> Result<(), Error> {
+    run(ctx, comic_number, random.unwrap_or(false)).await
+}
+
+pub async fn run(
+    ctx: impl CommandContext,
     comic_number: Option<u32>,
-    #[description = "Get a random xkcd comic"] random: Option<bool>,
+    random: bool,
 ) -> Result<(), Error> {
-    // Get the latest comic info to know the range
-    let latest_response = reqwest::get("https://xkcd.com/info.0.json")
-        .await?
-        .json::<Value>()
-        .await?;
+    ctx.defer().await?;
 
-    let latest_num = latest_response["num"].as_u64().unwrap_or(1) as u32;
+    // Get the latest comic info to know the range
+    let data = ctx.data();
+    let base_url = &data.config.apis.xkcd;
+    let latest_num = fetch_latest_comic_number(&data.http, base_url).await?;
 
-    // Determine which comic to fetch
-    let target_num = if random.unwrap_or(false) {
-        // Generate a random comic number between 1 and latest_num
-        use rand::Rng;
-        let mut rng = rand::rng();
-        rng.random_range(1..=latest_num)
-    } else if let Some(num) = comic_number {
-        if num == 0 || num > latest_num {
-            latest_num // Send latest comic if invalid number
-        } else {
-            num
-        }
-    } else {
-        latest_num
-    };
+    let target_num = target_comic(latest_num, comic_number, random, &mut rand::rng());
 
     let explain_url = format!("https://explainxkcd.com/{}", target_num);
-    let xkcd_url = format!("https://xkcd.com/{}", target_num);
+    let xkcd_url = format!("{}/{}", base_url, target_num);
 
     // Create button component for explanation
-    let button = CreateButton::new_link(explain_url).label("Explain xkcd");
+    let button = CreateButton::new_link(explain_url).label(tr!(ctx, "xkcd-explain"));
 
     let components = vec![CreateActionRow::Buttons(vec![button].into())];
 
@@ -140,3 +155,80 @@ pub async fn xkcd(
     .await?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot_lib::mock_api::{self, Failure, context_for, server_responding};
+    use wiremock::ResponseTemplate;
+
+    #[tokio::test]
+    async fn reads_latest_comic_number() {
+        let server = server_responding(
+            "/info.0.json",
+            ResponseTemplate::new(200).set_body_json(
+                serde_json::json!({ "num": 3000, "title": "Latest", "img": "https://imgs.xkcd.com/x.png" }),
+            ),
+        )
+        .await;
+
+        let latest = fetch_latest_comic_number(&HttpClient::for_tests(0), &server.uri())
+            .await
+            .unwrap();
+
+        assert_eq!(latest, 3000);
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_first_comic_without_number() {
+        let server = server_responding(
+            "/info.0.json",
+            ResponseTemplate::new(200).set_body_json(serde_json::json!({ "num": "many" })),
+        )
+        .await;
+
+        let latest = fetch_latest_comic_number(&HttpClient::for_tests(0), &server.uri())
+            .await
+            .unwrap();
+
+        assert_eq!(latest, 1);
+    }
+
+    #[tokio::test]
+    async fn rejects_broken_responses() {
+        mock_api::assert_rejects("/info.0.json", &Failure::ALL, |http, url| async move {
+            fetch_latest_comic_number(&http, &url).await
+        })
+        .await;
+    }
+
+    #[test]
+    fn targets_requested_comic_if_it_exists() {
+        let mut rng = rand::rng();
+        assert_eq!(target_comic(3000, Some(353), false, &mut rng), 353);
+        assert_eq!(target_comic(3000, Some(0), false, &mut rng), 3000);
+        assert_eq!(target_comic(3000, Some(3001), false, &mut rng), 3000);
+        assert_eq!(target_comic(3000, None, false, &mut rng), 3000);
+        for _ in 0..100 {
+            assert!((1..=3).contains(&target_comic(3, Some(2), true, &mut rng)));
+        }
+    }
+
+    #[tokio::test]
+    async fn links_comic_and_explanation() {
+        let server = server_responding(
+            "/info.0.json",
+            ResponseTemplate::new(200).set_body_json(serde_json::json!({ "num": 3000 })),
+        )
+        .await;
+        let ctx = context_for(&server, |apis| &mut apis.xkcd).await;
+
+        run(&ctx, Some(927), false).await.unwrap();
+
+        let reply = ctx.reply();
+        assert_eq!(reply.content, Some(format!("{}/927", server.uri())));
+        let button = &reply.components[0]["components"][0];
+        assert_eq!(button["url"], "https://explainxkcd.com/927");
+        assert_eq!(button["label"], tr!(&ctx, "xkcd-explain"));
+    }
+}
@@ -0,0 +1,1111 @@
+use poise::serenity_prelude as serenity;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::net::SocketAddr;
+use std::num::NonZeroU16;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// Default location of the configuration file, relative to the working directory
+const DEFAULT_CONFIG_PATH: &str = "config.toml";
+
+/// Environment variable pointing at an explicit configuration file
+const CONFIG_PATH_ENV: &str = "COCO_CONFIG";
+
+/// Prefix for environment variables overriding configuration keys
+///
+/// Sections and keys are separated by a double underscore, e.g.
+/// `COCO_PRESENCE__ACTIVITY="with Rust!"` sets `presence.activity`.
+const ENV_PREFIX: &str = "COCO_";
+
+/// Suffix marking an environment variable whose value is a path to a file holding the real value
+const FILE_SUFFIX: &str = "_FILE";
+
+/// Errors which can occur while loading or validating the configuration
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The configuration file (or a `*_FILE` secret) could not be read
+    Io(PathBuf, std::io::Error),
+    /// The configuration file or an override is not valid TOML / does not match the schema
+    Parse(String),
+    /// The configuration was parsed but contains invalid values
+    Invalid(Vec<String>),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(path, e) => write!(f, "Failed to read '{}': {}", path.display(), e),
+            ConfigError::Parse(msg) => write!(f, "Failed to parse configuration: {}", msg),
+            ConfigError::Invalid(problems) => {
+                write!(f, "Invalid configuration:")?;
+                for problem in problems {
+                    write!(f, "\n  - {}", problem)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Typed bot configuration
+///
+/// Values are layered in the following order, later layers winning:
+///
+/// 1. Built-in defaults
+/// 2. The TOML file at `COCO_CONFIG` (or `./config.toml` if present)
+/// 3. `COCO_<SECTION>__<KEY>` environment variables
+/// 4. `COCO_<SECTION>__<KEY>_FILE` environment variables, read from the referenced file
+///
+/// The legacy `DISCORD_TOKEN` / `DISCORD_TOKEN_FILE` variables are still honoured.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub discord: DiscordConfig,
+    pub presence: PresenceConfig,
+    pub apis: ApiConfig,
+    pub http: HttpConfig,
+    pub storage: StorageConfig,
+    pub registration: RegistrationConfig,
+    pub sharding: ShardingConfig,
+    /// Per-command user cooldowns in seconds, overriding the ones declared on the commands
+    pub cooldowns: HashMap<String, u64>,
+    pub moderation: ModerationConfig,
+    pub server: ServerConfig,
+    pub metrics: MetricsConfig,
+    pub logging: LoggingConfig,
+    pub prefix: PrefixConfig,
+    pub analytics: AnalyticsConfig,
+    pub memes: MemeConfig,
+    /// Configuration file the settings were read from, logged once logging is set up
+    #[serde(skip)]
+    pub source: Option<PathBuf>,
+    /// Keys overridden by environment variables, logged once logging is set up
+    #[serde(skip)]
+    pub env_overrides: Vec<String>,
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DiscordConfig {
+    /// Bot token
+    pub token: Option<String>,
+    /// Path to a file containing the bot token (e.g. a Docker secret)
+    pub token_file: Option<PathBuf>,
+}
+
+impl fmt::Debug for DiscordConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Never print the token itself
+        f.debug_struct("DiscordConfig")
+            .field("token", &self.token.as_ref().map(|_| "<redacted>"))
+            .field("token_file", &self.token_file)
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, poise::ChoiceParameter)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityKind {
+    #[default]
+    Playing,
+    Listening,
+    Watching,
+    Competing,
+    Custom,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, poise::ChoiceParameter)]
+#[serde(rename_all = "snake_case")]
+pub enum StatusKind {
+    #[default]
+    Online,
+    Idle,
+    #[name = "Do Not Disturb"]
+    DoNotDisturb,
+    Invisible,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PresenceConfig {
+    pub activity_type: ActivityKind,
+    pub activity: String,
+    pub status: StatusKind,
+}
+
+impl Default for PresenceConfig {
+    fn default() -> Self {
+        Self {
+            activity_type: ActivityKind::Playing,
+            activity: "with Rust!".to_string(),
+            status: StatusKind::Online,
+        }
+    }
+}
+
+impl PresenceConfig {
+    /// Builds the activity to show on the bot's profile, if any
+    pub fn activity_data(&self) -> Option<serenity::ActivityData> {
+        if self.activity.is_empty() {
+            return None;
+        }
+        let name = self.activity.clone();
+        Some(match self.activity_type {
+            ActivityKind::Playing => serenity::ActivityData::playing(name),
+            ActivityKind::Listening => serenity::ActivityData::listening(name),
+            ActivityKind::Watching => serenity::ActivityData::watching(name),
+            ActivityKind::Competing => serenity::ActivityData::competing(name),
+            ActivityKind::Custom => serenity::ActivityData::custom(name),
+        })
+    }
+
+    pub fn online_status(&self) -> serenity::OnlineStatus {
+        match self.status {
+            StatusKind::Online => serenity::OnlineStatus::Online,
+            StatusKind::Idle => serenity::OnlineStatus::Idle,
+            StatusKind::DoNotDisturb => serenity::OnlineStatus::DoNotDisturb,
+            StatusKind::Invisible => serenity::OnlineStatus::Invisible,
+        }
+    }
+}
+
+/// Base URLs of the upstream APIs used by the commands
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ApiConfig {
+    pub advice: String,
+    pub dad_joke: String,
+    pub viewbits: String,
+    pub pickup_line: String,
+    pub cowsay: String,
+    pub xkcd: String,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            advice: "https://api.adviceslip.com".to_string(),
+            dad_joke: "https://icanhazdadjoke.com".to_string(),
+            viewbits: "https://api.viewbits.com".to_string(),
+            pickup_line: "https://api.jcwyt.com".to_string(),
+            cowsay: "https://easyapis.honeybeeks.net".to_string(),
+            xkcd: "https://xkcd.com".to_string(),
+        }
+    }
+}
+
+impl ApiConfig {
+    fn entries(&self) -> [(&'static str, &str); 6] {
+        [
+            ("advice", &self.advice),
+            ("dad_joke", &self.dad_joke),
+            ("viewbits", &self.viewbits),
+            ("pickup_line", &self.pickup_line),
+            ("cowsay", &self.cowsay),
+            ("xkcd", &self.xkcd),
+        ]
+    }
+}
+
+/// Settings for the shared HTTP client used to call the upstream APIs
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HttpConfig {
+    /// User-Agent header sent with every request
+    pub user_agent: String,
+    /// Total time allowed for a single request, in seconds
+    pub timeout_secs: u64,
+    /// Time allowed to establish a connection, in seconds
+    pub connect_timeout_secs: u64,
+    /// How many times a request failing with a timeout, connection error or 5xx is retried
+    pub max_retries: u32,
+    /// Delay before the first retry in milliseconds, doubled for every further retry
+    pub backoff_ms: u64,
+    pub cache: CacheConfig,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            user_agent: format!(
+                "Coco-Bot/{} ({})",
+                env!("CARGO_PKG_VERSION"),
+                env!("CARGO_PKG_REPOSITORY")
+            ),
+            timeout_secs: 10,
+            connect_timeout_secs: 5,
+            max_retries: 2,
+            backoff_ms: 250,
+            cache: CacheConfig::default(),
+        }
+    }
+}
+
+/// Settings for the cache of upstream API responses
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CacheConfig {
+    pub enabled: bool,
+    /// Most responses kept at once, the oldest are evicted first
+    pub max_entries: usize,
+    /// How long an expired response may still be served while it is refreshed in the background
+    pub stale_secs: u64,
+    /// Time to live in seconds keyed by provider, overriding the ones chosen by the commands
+    pub ttl_secs: HashMap<String, u64>,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_entries: 1000,
+            stale_secs: 3600,
+            ttl_secs: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RegistrationMode {
+    /// Register commands globally, which can take a while to show up everywhere
+    #[default]
+    Global,
+    /// Register commands only in the `dev_guilds`, where they update instantly
+    Guilds,
+}
+
+/// Controls where slash commands are registered on startup
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RegistrationConfig {
+    pub mode: RegistrationMode,
+    /// Guild IDs to register commands in when `mode = "guilds"`
+    pub dev_guilds: Vec<u64>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShardingMode {
+    /// Run as many shards as Discord recommends for the bot's guild count
+    #[default]
+    Auto,
+    /// Run a single shard, which Discord only allows up to 2500 guilds
+    Single,
+    /// Run `total` shards, or only those in `range` when the bot is split across processes
+    Manual,
+}
+
+/// Controls how many gateway shards this process runs
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ShardingConfig {
+    pub mode: ShardingMode,
+    /// Total number of shards across all processes, required when `mode = "manual"`
+    pub total: Option<u16>,
+    /// Shards run by this process as `[first, last]`, both inclusive; all of them if unset
+    pub range: Option<[u16; 2]>,
+}
+
+impl ShardingConfig {
+    /// The total number of shards, which is guaranteed to be valid after validation in manual mode
+    pub fn total(&self) -> Option<NonZeroU16> {
+        self.total.and_then(NonZeroU16::new)
+    }
+}
+
+/// Settings for the embedded SQLite database
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageConfig {
+    /// Path of the database file, created along with its parent directories if missing
+    pub database_path: PathBuf,
+    /// Maximum number of pooled connections
+    pub max_connections: u32,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            database_path: PathBuf::from("data/coco.db"),
+            max_connections: 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ModerationConfig {
+    /// React with 👋 to messages containing "hello"
+    pub greet: bool,
+    /// Messages containing any of these words (case-insensitive) are deleted
+    pub bad_words: Vec<String>,
+}
+
+impl Default for ModerationConfig {
+    fn default() -> Self {
+        Self {
+            greet: true,
+            bad_words: vec!["spam".to_string(), "badword".to_string()],
+        }
+    }
+}
+
+/// Settings for the embedded HTTP server exposing health, readiness and version endpoints
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub enabled: bool,
+    /// Address the server listens on
+    pub listen: SocketAddr,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            listen: SocketAddr::from(([127, 0, 0, 1], 8080)),
+        }
+    }
+}
+
+/// Settings for the Prometheus metrics endpoint
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MetricsConfig {
+    /// Serve metrics at `/metrics` on the embedded HTTP server
+    pub enabled: bool,
+}
+
+/// Settings for invoking commands with a text prefix instead of a slash command
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PrefixConfig {
+    pub enabled: bool,
+    /// Default prefix, which servers can override with `/config prefix`
+    pub prefix: String,
+    /// Also accept a mention of the bot as prefix
+    pub mention: bool,
+    /// For how long editing a command message re-runs the command, 0 disables it
+    pub edit_tracking_secs: u64,
+}
+
+impl Default for PrefixConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            prefix: "coco!".to_string(),
+            mention: true,
+            edit_tracking_secs: 300,
+        }
+    }
+}
+
+/// Longest retention period for recorded command usage, about 100 years
+pub const MAX_RETENTION_DAYS: u64 = 36_500;
+
+/// Settings for recording command usage, shown by `/stats`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AnalyticsConfig {
+    /// Record every command invocation in the database
+    pub enabled: bool,
+    /// Invocations older than this many days are deleted, 0 keeps them forever
+    ///
+    /// At most [`MAX_RETENTION_DAYS`], as the retention period is computed in seconds.
+    pub retention_days: u64,
+}
+
+impl Default for AnalyticsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            retention_days: 90,
+        }
+    }
+}
+
+/// Limits on the images people supply to `/generate_meme`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MemeConfig {
+    /// Largest image file downloaded, in bytes
+    pub max_image_bytes: u64,
+    /// Widest or tallest image accepted, in pixels
+    pub max_image_dimension: u32,
+    /// Largest meme uploaded, in bytes; larger ones are scaled down to fit
+    pub max_upload_bytes: u64,
+}
+
+impl Default for MemeConfig {
+    fn default() -> Self {
+        Self {
+            max_image_bytes: 8 * 1024 * 1024,
+            max_image_dimension: 4096,
+            max_upload_bytes: 10 * 1024 * 1024,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    /// Human readable lines
+    #[default]
+    Text,
+    /// One JSON object per line, for log shipping
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogRotation {
+    Minutely,
+    Hourly,
+    #[default]
+    Daily,
+    Never,
+}
+
+impl From<LogRotation> for tracing_appender::rolling::Rotation {
+    fn from(rotation: LogRotation) -> Self {
+        match rotation {
+            LogRotation::Minutely => Self::MINUTELY,
+            LogRotation::Hourly => Self::HOURLY,
+            LogRotation::Daily => Self::DAILY,
+            LogRotation::Never => Self::NEVER,
+        }
+    }
+}
+
+/// Settings for the optional rotating log file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogFileConfig {
+    pub enabled: bool,
+    /// Directory the log files are written to, created if missing
+    pub directory: PathBuf,
+    pub rotation: LogRotation,
+    /// Number of rotated files to keep
+    pub max_files: usize,
+}
+
+impl Default for LogFileConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            directory: PathBuf::from("logs"),
+            rotation: LogRotation::Daily,
+            max_files: 7,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    /// Filter directives such as `info` or `coco_bot=debug,serenity=warn`, overridden by `RUST_LOG`
+    pub level: String,
+    pub format: LogFormat,
+    /// Log message content, user names and user IDs instead of redacting them
+    pub log_sensitive: bool,
+    pub file: LogFileConfig,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+            format: LogFormat::Text,
+            log_sensitive: false,
+            file: LogFileConfig::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Loads the configuration from the file system and environment, then validates it
+    ///
+    /// The token can only be left out when not connecting to Discord, as in the REPL.
+    pub fn load(require_token: bool) -> Result<Self, ConfigError> {
+        let env: HashMap<String, String> = std::env::vars().collect();
+        Self::load_from(&env, require_token)
+    }
+
+    /// Loads the configuration using the given set of environment variables
+    fn load_from(env: &HashMap<String, String>, require_token: bool) -> Result<Self, ConfigError> {
+        let source = config_file_path(env);
+        let mut root = match &source {
+            Some(path) => {
+                let content =
+                    std::fs::read_to_string(path).map_err(|e| ConfigError::Io(path.clone(), e))?;
+                content
+                    .parse::<toml::Table>()
+                    .map_err(|e| ConfigError::Parse(format!("{}: {}", path.display(), e)))?
+            }
+            None => toml::Table::new(),
+        };
+
+        // Secrets referenced by the file belong to the file layer, so the environment beats them
+        resolve_secrets(&mut root)?;
+        let env_overrides = apply_env_overrides(&mut root, env)?;
+
+        let mut config: Config = toml::Value::Table(root)
+            .try_into()
+            .map_err(|e: toml::de::Error| ConfigError::Parse(e.to_string()))?;
+
+        config.source = source;
+        config.env_overrides = env_overrides;
+        config.validate(require_token)?;
+        Ok(config)
+    }
+
+    /// Checks the configuration for values which would fail at runtime
+    fn validate(&self, require_token: bool) -> Result<(), ConfigError> {
+        let mut problems = Vec::new();
+
+        match self.discord.token.as_deref().map(str::trim) {
+            None | Some("") if !require_token => {}
+            None | Some("") => problems.push(
+                "discord.token is required (set DISCORD_TOKEN, DISCORD_TOKEN_FILE or discord.token)"
+                    .to_string(),
+            ),
+            Some(token) => {
+                if token.parse::<serenity::all::Token>().is_err() {
+                    problems.push("discord.token is not a valid bot token".to_string());
+                }
+            }
+        }
+
+        for (name, url) in self.apis.entries() {
+            match reqwest::Url::parse(url) {
+                Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => {}
+                Ok(_) => problems.push(format!("apis.{} must be an http(s) URL", name)),
+                Err(e) => problems.push(format!("apis.{} is not a valid URL: {}", name, e)),
+            }
+        }
+
+        if self.http.timeout_secs == 0 {
+            problems.push("http.timeout_secs must be greater than 0".to_string());
+        }
+        if self.http.connect_timeout_secs == 0 {
+            problems.push("http.connect_timeout_secs must be greater than 0".to_string());
+        }
+        if self.http.user_agent.trim().is_empty() {
+            problems.push("http.user_agent must not be empty".to_string());
+        }
+        if self.http.cache.enabled && self.http.cache.max_entries == 0 {
+            problems.push("http.cache.max_entries must be greater than 0".to_string());
+        }
+
+        if self.registration.mode == RegistrationMode::Guilds {
+            if self.registration.dev_guilds.is_empty() {
+                problems.push(
+                    "registration.dev_guilds must not be empty when registration.mode is \"guilds\""
+                        .to_string(),
+                );
+            }
+            if self.registration.dev_guilds.contains(&0) {
+                problems.push("registration.dev_guilds must not contain 0".to_string());
+            }
+        }
+
+        if self.sharding.mode == ShardingMode::Manual {
+            match (self.sharding.total(), self.sharding.range) {
+                (None, _) => problems.push(
+                    "sharding.total must be greater than 0 when sharding.mode is \"manual\""
+                        .to_string(),
+                ),
+                (Some(total), Some([first, last])) if first > last || last >= total.get() => {
+                    problems.push(format!(
+                        "sharding.range must be [first, last] with first <= last < sharding.total ({})",
+                        total
+                    ))
+                }
+                _ => {}
+            }
+        } else if self.sharding.total.is_some() || self.sharding.range.is_some() {
+            problems.push(
+                "sharding.total and sharding.range are only used when sharding.mode is \"manual\""
+                    .to_string(),
+            );
+        }
+
+        if self.storage.database_path.as_os_str().is_empty() {
+            problems.push("storage.database_path must not be empty".to_string());
+        }
+        if self.storage.max_connections == 0 {
+            problems.push("storage.max_connections must be greater than 0".to_string());
+        }
+
+        if self
+            .moderation
+            .bad_words
+            .iter()
+            .any(|w| w.trim().is_empty())
+        {
+            problems.push("moderation.bad_words must not contain empty entries".to_string());
+        }
+
+        if let Err(e) = tracing_subscriber::EnvFilter::try_new(&self.logging.level) {
+            problems.push(format!("logging.level is not a valid filter: {}", e));
+        }
+        if self.logging.file.enabled {
+            if self.logging.file.directory.as_os_str().is_empty() {
+                problems.push("logging.file.directory must not be empty".to_string());
+            }
+            if self.logging.file.max_files == 0 {
+                problems.push("logging.file.max_files must be greater than 0".to_string());
+            }
+        }
+
+        if let Err(reason) = crate::bot_lib::prefix::validate_prefix(&self.prefix.prefix) {
+            problems.push(format!("prefix.prefix {}", reason));
+        }
+
+        if self.analytics.retention_days > MAX_RETENTION_DAYS {
+            problems.push(format!(
+                "analytics.retention_days must be at most {}",
+                MAX_RETENTION_DAYS
+            ));
+        }
+
+        if self.memes.max_image_bytes == 0 {
+            problems.push("memes.max_image_bytes must be greater than 0".to_string());
+        }
+        if self.memes.max_image_dimension == 0 {
+            problems.push("memes.max_image_dimension must be greater than 0".to_string());
+        }
+        if self.memes.max_upload_bytes == 0 {
+            problems.push("memes.max_upload_bytes must be greater than 0".to_string());
+        }
+
+        if self.metrics.enabled && !self.server.enabled {
+            problems.push("metrics.enabled requires server.enabled".to_string());
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid(problems))
+        }
+    }
+
+    /// Returns the bot token, which is guaranteed to be present after validation
+    pub fn token(&self) -> serenity::all::Token {
+        self.discord
+            .token
+            .as_deref()
+            .map(str::trim)
+            .and_then(|token| token.parse().ok())
+            .expect("Token was checked during validation")
+    }
+
+    /// Applies the configured cooldown overrides to the given commands
+    pub fn apply_cooldowns<U, E>(&self, commands: &mut [poise::Command<U, E>]) {
+        for command in commands {
+            if let Some(&seconds) = self.cooldowns.get(&*command.name) {
+                debug!(command = %command.name, seconds, "Overriding user cooldown");
+                let mut cooldowns = command
+                    .cooldown_config
+                    .write()
+                    .expect("Cooldown config lock poisoned");
+                cooldowns.user = Some(std::time::Duration::from_secs(seconds));
+            }
+        }
+    }
+}
+
+/// Determines which configuration file to load, if any
+fn config_file_path(env: &HashMap<String, String>) -> Option<PathBuf> {
+    if let Some(path) = env.get(CONFIG_PATH_ENV) {
+        // An explicitly requested file must exist, so let the read fail loudly
+        return Some(PathBuf::from(path));
+    }
+    let default = Path::new(DEFAULT_CONFIG_PATH);
+    default.is_file().then(|| default.to_path_buf())
+}
+
+/// Reads a secret from a file, trimming surrounding whitespace
+fn read_secret(path: &Path) -> Result<String, ConfigError> {
+    std::fs::read_to_string(path)
+        .map(|s| s.trim().to_string())
+        .map_err(|e| ConfigError::Io(path.to_path_buf(), e))
+}
+
+/// Parses an environment variable value for the key at `path`
+///
+/// Keys which accept a string, such as `prefix.prefix`, take the value as is. Others parse it
+/// as a TOML value, so `COCO_COOLDOWNS__FACT=10` becomes an integer and
+/// `COCO_MODERATION__BAD_WORDS='["spam"]'` an array, falling back to a string if that fails.
+fn parse_env_value(path: &[String], raw: &str) -> toml::Value {
+    let string = toml::Value::String(raw.to_string());
+    if accepts(path, string.clone()) {
+        return string;
+    }
+    format!("value = {}", raw)
+        .parse::<toml::Table>()
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or(string)
+}
+
+/// Whether the configuration accepts `value` at `path` when nothing else is set
+fn accepts(path: &[String], value: toml::Value) -> bool {
+    let mut probe = toml::Table::new();
+    set_path(&mut probe, path, value).is_ok()
+        && toml::Value::Table(probe).try_into::<Config>().is_ok()
+}
+
+/// Replaces `discord.token_file` in the configuration file with the token it references
+fn resolve_secrets(root: &mut toml::Table) -> Result<(), ConfigError> {
+    let Some(discord) = root.get_mut("discord").and_then(toml::Value::as_table_mut) else {
+        return Ok(());
+    };
+    if let Some(path) = discord.get("token_file") {
+        let path = path
+            .as_str()
+            .ok_or_else(|| ConfigError::Parse("discord.token_file must be a path".to_string()))?;
+        let token = read_secret(Path::new(path))?;
+        discord.insert("token".to_string(), toml::Value::String(token));
+    }
+    Ok(())
+}
+
+/// Layers `COCO_*` (and legacy `DISCORD_TOKEN*`) environment variables on top of the file values,
+/// returning the keys they override
+fn apply_env_overrides(
+    root: &mut toml::Table,
+    env: &HashMap<String, String>,
+) -> Result<Vec<String>, ConfigError> {
+    let mut overrides: Vec<(Vec<String>, toml::Value)> = Vec::new();
+
+    if let Some(token) = env.get("DISCORD_TOKEN") {
+        overrides.push((
+            vec!["discord".into(), "token".into()],
+            toml::Value::String(token.clone()),
+        ));
+    }
+    if let Some(path) = env.get("DISCORD_TOKEN_FILE") {
+        overrides.push((
+            vec!["discord".into(), "token".into()],
+            toml::Value::String(read_secret(Path::new(path))?),
+        ));
+    }
+
+    // Sort so plain values are applied before their `_FILE` counterparts
+    let mut keys: Vec<&String> = env
+        .keys()
+        .filter(|key| key.starts_with(ENV_PREFIX) && key.as_str() != CONFIG_PATH_ENV)
+        .collect();
+    keys.sort();
+
+    for key in keys {
+        let raw = &env[key];
+        let stripped = &key[ENV_PREFIX.len()..];
+        let (stripped, secret) = match stripped.strip_suffix(FILE_SUFFIX) {
+            Some(rest) => (rest, Some(read_secret(Path::new(raw))?)),
+            None => (stripped, None),
+        };
+        let path: Vec<String> = stripped
+            .split("__")
+            .map(|segment| segment.to_lowercase())
+            .collect();
+        if path.iter().any(String::is_empty) {
+            return Err(ConfigError::Parse(format!(
+                "Malformed configuration override variable '{}'",
+                key
+            )));
+        }
+        let value = match secret {
+            Some(secret) => toml::Value::String(secret),
+            None => parse_env_value(&path, raw),
+        };
+        overrides.push((path, value));
+    }
+
+    let mut keys = Vec::with_capacity(overrides.len());
+    for (path, value) in overrides {
+        set_path(root, &path, value)?;
+        keys.push(path.join("."));
+    }
+    Ok(keys)
+}
+
+/// Sets a (possibly nested) key in a TOML table, creating intermediate tables as needed
+fn set_path(
+    root: &mut toml::Table,
+    path: &[String],
+    value: toml::Value,
+) -> Result<(), ConfigError> {
+    let (last, parents) = path.split_last().expect("Override path is never empty");
+    let mut table = root;
+    for segment in parents {
+        let entry = table
+            .entry(segment.clone())
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+        table = entry.as_table_mut().ok_or_else(|| {
+            ConfigError::Parse(format!(
+                "Cannot override '{}': '{}' is not a table",
+                path.join("."),
+                segment
+            ))
+        })?;
+    }
+    table.insert(last.clone(), value);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes a file to the temporary directory, named after the test using it
+    fn temp_file(name: &str, content: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("coco_{}_{}", std::process::id(), name));
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    /// Loads a configuration file along with environment variables, without requiring a token
+    fn load(name: &str, file: &str, vars: &[(&str, &str)]) -> Result<Config, ConfigError> {
+        let path = temp_file(&format!("{}.toml", name), file);
+        let mut env: HashMap<String, String> = vars
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        env.insert(CONFIG_PATH_ENV.to_string(), path.display().to_string());
+        let config = Config::load_from(&env, false);
+        std::fs::remove_file(path).unwrap();
+        config
+    }
+
+    /// Problems `validate` finds in a configuration file
+    fn problems(name: &str, file: &str) -> Vec<String> {
+        match load(name, file, &[]) {
+            Err(ConfigError::Invalid(problems)) => problems,
+            other => panic!("expected validation problems, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn environment_overrides_file() {
+        let config = load(
+            "overrides",
+            "[presence]\nactivity = \"from file\"\nstatus = \"idle\"\n",
+            &[
+                ("COCO_PRESENCE__ACTIVITY", "from env"),
+                ("COCO_COOLDOWNS__FACT", "10"),
+                ("COCO_MODERATION__BAD_WORDS", r#"["spam", "eggs"]"#),
+                ("COCO_PREFIX__ENABLED", "false"),
+                ("COCO_HTTP__CACHE__TTL_SECS__XKCD", "60"),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(config.presence.activity, "from env");
+        assert_eq!(config.presence.status, StatusKind::Idle);
+        assert_eq!(config.cooldowns["fact"], 10);
+        assert_eq!(config.moderation.bad_words, ["spam", "eggs"]);
+        assert!(!config.prefix.enabled);
+        assert_eq!(config.http.cache.ttl_secs["xkcd"], 60);
+    }
+
+    #[test]
+    fn string_keys_stay_strings() {
+        let config = load(
+            "strings",
+            "",
+            &[
+                ("COCO_PREFIX__PREFIX", "true"),
+                ("COCO_PRESENCE__ACTIVITY", r#"["a", "b"]"#),
+                ("COCO_STORAGE__DATABASE_PATH", "42"),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(config.prefix.prefix, "true");
+        assert_eq!(config.presence.activity, r#"["a", "b"]"#);
+        assert_eq!(config.storage.database_path, Path::new("42"));
+    }
+
+    #[test]
+    fn reads_secrets_from_files() {
+        let secret = temp_file("secret", "  from secret\n");
+        let config = load(
+            "secrets",
+            "",
+            &[
+                ("COCO_PRESENCE__ACTIVITY", "from env"),
+                ("COCO_PRESENCE__ACTIVITY_FILE", secret.to_str().unwrap()),
+            ],
+        );
+        std::fs::remove_file(&secret).unwrap();
+        assert_eq!(config.unwrap().presence.activity, "from secret");
+
+        assert!(matches!(
+            load(
+                "missing_secret",
+                "",
+                &[("COCO_PRESENCE__ACTIVITY_FILE", "/nonexistent/secret")],
+            ),
+            Err(ConfigError::Io(..))
+        ));
+    }
+
+    #[test]
+    fn environment_token_beats_token_file() {
+        let secret = temp_file("token_file", "file.token.value\n");
+        let file = format!(
+            "[discord]\ntoken_file = {:?}\n",
+            secret.display().to_string()
+        );
+
+        let config = load("token_file", &file, &[]).unwrap();
+        assert_eq!(config.discord.token.as_deref(), Some("file.token.value"));
+        assert!(config.env_overrides.is_empty());
+
+        for key in ["DISCORD_TOKEN", "COCO_DISCORD__TOKEN"] {
+            let config = load("token_file", &file, &[(key, "env.token.value")]).unwrap();
+            assert_eq!(
+                config.discord.token.as_deref(),
+                Some("env.token.value"),
+                "{} lost to discord.token_file",
+                key
+            );
+            assert_eq!(config.env_overrides, ["discord.token"]);
+        }
+        std::fs::remove_file(&secret).unwrap();
+    }
+
+    #[test]
+    fn rejects_malformed_overrides() {
+        for (key, value) in [
+            ("COCO_PRESENCE____ACTIVITY", "hi"),
+            ("COCO_PRESENCE__NO_SUCH_KEY", "hi"),
+            ("COCO_METRICS__LISTEN", "0.0.0.0:9100"),
+            ("COCO_PRESENCE__ACTIVITY__NESTED", "hi"),
+            ("COCO_COOLDOWNS__FACT", "soon"),
+        ] {
+            assert!(
+                matches!(
+                    load("malformed", "", &[(key, value)]),
+                    Err(ConfigError::Parse(_))
+                ),
+                "accepted {}={}",
+                key,
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn validates_values() {
+        assert!(Config::default().validate(false).is_ok());
+        assert!(matches!(
+            Config::default().validate(true),
+            Err(ConfigError::Invalid(problems)) if problems[0].starts_with("discord.token is required")
+        ));
+
+        assert_eq!(
+            problems(
+                "invalid",
+                r#"
+                [apis]
+                xkcd = "ftp://xkcd.com"
+
+                [http]
+                timeout_secs = 0
+
+                [registration]
+                mode = "guilds"
+
+                [prefix]
+                prefix = "co co"
+
+                [analytics]
+                retention_days = 100000
+
+                [memes]
+                max_upload_bytes = 0
+
+                [metrics]
+                enabled = true
+                "#
+            ),
+            [
+                "apis.xkcd must be an http(s) URL",
+                "http.timeout_secs must be greater than 0",
+                "registration.dev_guilds must not be empty when registration.mode is \"guilds\"",
+                "prefix.prefix must not contain whitespace",
+                "analytics.retention_days must be at most 36500",
+                "memes.max_upload_bytes must be greater than 0",
+                "metrics.enabled requires server.enabled",
+            ]
+        );
+    }
+
+    #[test]
+    fn validates_sharding() {
+        let manual = |name: &str, rest: &str| {
+            load(
+                name,
+                &format!("[sharding]\nmode = \"manual\"\n{}", rest),
+                &[],
+            )
+        };
+        assert!(manual("sharding_all", "total = 4\n").is_ok());
+        assert!(manual("sharding_range", "total = 4\nrange = [2, 3]\n").is_ok());
+        assert!(manual("sharding_one", "total = 4\nrange = [1, 1]\n").is_ok());
+
+        let total_problem =
+            "sharding.total must be greater than 0 when sharding.mode is \"manual\"";
+        assert_eq!(
+            problems("sharding_no_total", "[sharding]\nmode = \"manual\"\n"),
+            [total_problem]
+        );
+        assert_eq!(
+            problems(
+                "sharding_zero_total",
+                "[sharding]\nmode = \"manual\"\ntotal = 0\n"
+            ),
+            [total_problem]
+        );
+
+        let range_problem =
+            "sharding.range must be [first, last] with first <= last < sharding.total (4)";
+        for range in ["[3, 2]", "[2, 4]"] {
+            assert_eq!(
+                problems(
+                    "sharding_bad_range",
+                    &format!(
+                        "[sharding]\nmode = \"manual\"\ntotal = 4\nrange = {}\n",
+                        range
+                    )
+                ),
+                [range_problem],
+                "accepted range {}",
+                range
+            );
+        }
+
+        let unused_problem =
+            "sharding.total and sharding.range are only used when sharding.mode is \"manual\"";
+        for (mode, rest) in [("auto", "total = 4"), ("single", "range = [0, 0]")] {
+            assert_eq!(
+                problems(
+                    "sharding_unused",
+                    &format!("[sharding]\nmode = \"{}\"\n{}\n", mode, rest)
+                ),
+                [unused_problem],
+                "accepted {} in {} mode",
+                rest,
+                mode
+            );
+        }
+    }
+}
@@ -0,0 +1,180 @@
+use crate::bot_lib::checks::CommandUnavailable;
+use crate::bot_lib::http::UpstreamError;
+use crate::bot_lib::i18n::tr;
+use crate::bot_lib::invocation;
+use crate::bot_lib::logging::sensitive;
+use crate::{Context, Data, Error};
+use poise::serenity_prelude as serenity;
+use tracing::{error, warn};
+
+/// Generates a short random ID shown to the user and attached to the log entry
+fn correlation_id() -> String {
+    format!("{:08x}", rand::random::<u32>())
+}
+
+/// Sends an ephemeral error embed, logging (but otherwise ignoring) delivery failures
+async fn reply_with_embed(ctx: Context<'_>, title: String, description: String, id: Option<&str>) {
+    let mut embed = serenity::CreateEmbed::default()
+        .title(title)
+        .description(description)
+        .color(serenity::Color::from_rgb(220, 53, 69)); // Red color for errors
+
+    if let Some(id) = id {
+        embed = embed.footer(serenity::CreateEmbedFooter::new(tr!(
+            ctx,
+            "error-id",
+            id = id
+        )));
+    }
+
+    let reply = poise::CreateReply::default().embed(embed).ephemeral(true);
+    if let Err(e) = ctx.send(reply).await {
+        error!(error = %e, "Failed to send error reply");
+    }
+}
+
+/// Central handler for every error raised by the framework
+///
+/// Cooldowns and bad arguments get a friendly explanation, while upstream API failures and
+/// internal errors are logged in full along with a correlation ID which is also shown to the user.
+pub async fn on_error(error: poise::FrameworkError<'_, Data, Error>) {
+    match error {
+        poise::FrameworkError::CooldownHit {
+            remaining_cooldown,
+            ctx,
+            ..
+        } => {
+            invocation::finish(ctx, "cooldown").await;
+            let available_at =
+                serenity::Timestamp::now().timestamp() + remaining_cooldown.as_secs() as i64 + 1;
+            reply_with_embed(
+                ctx,
+                tr!(ctx, "error-cooldown-title"),
+                tr!(
+                    ctx,
+                    "error-cooldown",
+                    command = ctx.command().qualified_name.to_string(),
+                    available_at = available_at
+                ),
+                None,
+            )
+            .await;
+        }
+        poise::FrameworkError::ArgumentParse {
+            error, input, ctx, ..
+        } => {
+            invocation::finish(ctx, "invalid_argument").await;
+            warn!(
+                command = %ctx.command().qualified_name,
+                input = ?sensitive(&input),
+                error = %error,
+                "Failed to parse command argument"
+            );
+            let description = match input {
+                Some(input) => tr!(
+                    ctx,
+                    "error-argument",
+                    input = input,
+                    error = error.to_string()
+                ),
+                None => tr!(ctx, "error-arguments", error = error.to_string()),
+            };
+            reply_with_embed(ctx, tr!(ctx, "error-argument-title"), description, None).await;
+        }
+        poise::FrameworkError::Command { error, ctx, .. } => {
+            let id = correlation_id();
+            let command = &ctx.command().qualified_name;
+
+            if let Some(upstream) = error.downcast_ref::<UpstreamError>() {
+                invocation::finish(ctx, "upstream_error").await;
+                warn!(
+                    correlation_id = %id,
+                    command = %command,
+                    provider = upstream.provider,
+                    error = %upstream,
+                    "Upstream API failure"
+                );
+                reply_with_embed(
+                    ctx,
+                    tr!(ctx, "error-upstream-title"),
+                    tr!(ctx, "error-upstream", provider = upstream.provider),
+                    Some(&id),
+                )
+                .await;
+            } else {
+                invocation::finish(ctx, "error").await;
+                error!(
+                    correlation_id = %id,
+                    command = %command,
+                    error = ?error,
+                    "Command failed"
+                );
+                reply_with_embed(
+                    ctx,
+                    tr!(ctx, "error-internal-title"),
+                    tr!(ctx, "error-internal"),
+                    Some(&id),
+                )
+                .await;
+            }
+        }
+        poise::FrameworkError::CommandCheckFailed {
+            error: Some(error),
+            ctx,
+            ..
+        } if !error.is::<CommandUnavailable>() => {
+            invocation::finish(ctx, "error").await;
+            let id = correlation_id();
+            error!(
+                correlation_id = %id,
+                command = %ctx.command().qualified_name,
+                error = ?error,
+                "Command check failed"
+            );
+            reply_with_embed(
+                ctx,
+                tr!(ctx, "error-internal-title"),
+                tr!(ctx, "error-internal"),
+                Some(&id),
+            )
+            .await;
+        }
+        poise::FrameworkError::CommandCheckFailed { error, ctx, .. } => {
+            invocation::finish(ctx, "check_failed").await;
+            let description = match error {
+                Some(error) => {
+                    warn!(
+                        command = %ctx.command().qualified_name,
+                        error = %error,
+                        "Command check refused invocation"
+                    );
+                    error.to_string()
+                }
+                None => tr!(ctx, "error-unavailable"),
+            };
+            reply_with_embed(ctx, tr!(ctx, "error-unavailable-title"), description, None).await;
+        }
+        poise::FrameworkError::CommandPanic { payload, ctx, .. } => {
+            invocation::finish(ctx, "panic").await;
+            let id = correlation_id();
+            error!(
+                correlation_id = %id,
+                command = %ctx.command().qualified_name,
+                payload = ?payload,
+                "Command panicked"
+            );
+            reply_with_embed(
+                ctx,
+                tr!(ctx, "error-internal-title"),
+                tr!(ctx, "error-internal"),
+                Some(&id),
+            )
+            .await;
+        }
+        other => {
+            if let Err(e) = poise::builtins::on_error(other).await {
+                error!(error = %e, "Error while handling error");
+            }
+        }
+    }
+}
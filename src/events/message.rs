@@ -1,4 +1,5 @@
-use crate::Error;
+use crate::bot_lib::logging::sensitive;
+use crate::{Data, Error};
 use poise::serenity_prelude as serenity;
 use tracing::{debug, error, warn};
 
@@ -8,26 +9,33 @@ pub async fn message(ctx: serenity::Context, msg: serenity::Message) -> Result<(
         return Ok(());
     }
 
-    // Log all messages (optional - can be removed in production)
+    // Content and author are only logged with `logging.log_sensitive` enabled
     debug!(
-        user = %msg.author.name,
-        user_id = %msg.author.id,
-        content = %msg.content,
+        user = %sensitive(&msg.author.name),
+        user_id = %sensitive(msg.author.id),
+        content = %sensitive(&msg.content),
         "Received message"
     );
 
+    let data = ctx.data::<Data>();
+    let moderation = &data.config.moderation;
+    let content = msg.content.to_lowercase();
+
     // Example: React to messages containing "hello"
-    if msg.content.to_lowercase().contains("hello") {
+    if moderation.greet && content.contains("hello") {
         if let Err(e) = msg.react(&ctx.http, '👋').await {
             error!(error = %e, "Failed to react to message");
         }
     }
 
     // Example: Auto-delete messages containing bad words
-    let bad_words = vec!["spam", "badword"]; // Add your own filter
-    for word in bad_words {
-        if msg.content.to_lowercase().contains(word) {
-            warn!(word = %word, user = %msg.author.name, "Bad word detected, deleting message");
+    for word in &moderation.bad_words {
+        if content.contains(&word.to_lowercase()) {
+            warn!(
+                word = %word,
+                user = %sensitive(&msg.author.name),
+                "Bad word detected, deleting message"
+            );
             if let Err(e) = msg.delete(&ctx.http, Some("Bad word detected")).await {
                 error!(error = %e, "Failed to delete message");
             }
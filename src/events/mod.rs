@@ -1,6 +1,8 @@
+pub mod error;
 mod message;
 mod ready;
 
+use crate::Data;
 use poise::serenity_prelude as serenity;
 use serenity::async_trait;
 
@@ -16,6 +18,11 @@ impl serenity::EventHandler for Handler {
             serenity::FullEvent::Ready { data_about_bot, .. } => {
                 ready::ready(ctx.clone(), data_about_bot.clone()).await;
             }
+            serenity::FullEvent::ShardStageUpdate { event, .. } => {
+                ctx.data::<Data>()
+                    .shards
+                    .set_stage(event.shard_id, event.new);
+            }
             _ => {}
         }
     }
@@ -1,32 +1,47 @@
-use crate::commands;
-use poise::{builtins::register_globally, serenity_prelude as serenity};
-use tracing::info;
+use crate::bot_lib::registration;
+use crate::{Data, commands};
+use poise::serenity_prelude as serenity;
+use tracing::{info, warn};
 
 pub async fn ready(ctx: serenity::Context, ready: serenity::Ready) {
     info!(
+        shard_id = %ctx.shard_id,
         "Bot is ready! Logged in as {}#{:?}",
         ready.user.name,
         ready.user.discriminator.unwrap()
     );
     info!(
+        shard_id = %ctx.shard_id,
         guild_count = ready.guilds.len(),
         "Connected to {} guilds",
         ready.guilds.len()
     );
 
-    let all_commands = commands::all_commands();
-    register_globally(&ctx.http, &all_commands)
-        .await
-        .expect("Failed to register commands globally");
+    let data = ctx.data::<Data>();
+    data.shards
+        .set_stage(ctx.shard_id, serenity::ConnectionStage::Connected);
 
-    info!(
-        command_count = all_commands.len(),
-        "Commands registered globally"
-    );
+    if data.health.start_registration() {
+        let all_commands = commands::all_commands();
+        let summary = registration::sync_commands(&ctx.http, &data, &all_commands, false).await;
+
+        data.health.finish_registration(summary.failed.is_empty());
+        if summary.failed.is_empty() {
+            info!(
+                registered = summary.registered.len(),
+                unchanged = summary.unchanged.len(),
+                "Command registration finished"
+            );
+        } else {
+            warn!(
+                failed = summary.failed.len(),
+                "Command registration failed in some scopes, continuing with previously registered commands until the next shard becomes ready"
+            );
+        }
+    }
 
     // Set bot activity/status
-    let activity = serenity::ActivityData::playing("with Rust!");
-    let status = serenity::OnlineStatus::Online;
+    let presence = data.presence.read().expect("Presence lock poisoned");
 
-    ctx.set_presence(Some(activity), status);
+    ctx.set_presence(presence.activity_data(), presence.online_status());
 }
@@ -0,0 +1,378 @@
+# Message catalogue for German. Messages missing here fall back to `en-US.ftl`.
+
+language-name = Deutsch
+
+## Command metadata
+
+about = über
+    .description = Informationen über den Bot anzeigen.
+admin =
+    .description = Bot-Verwaltung (nur für Besitzer).
+admin-register =
+    .description = Alle Slash-Befehle erneut registrieren.
+admin-unregister =
+    .description = Alle Slash-Befehle von Discord entfernen.
+admin-reload_templates =
+    .description = Die Verzeichnisse der Meme-Vorlagen neu einlesen.
+admin-presence =
+    .description = Den Status des Bots bis zum nächsten Neustart ändern.
+    .activity_type-description = Art der Aktivität
+    .activity-description = Text der Aktivität, leer lassen zum Entfernen
+    .status-description = Online-Status
+admin-stats =
+    .description = Laufzeitstatistiken anzeigen.
+admin-toggle =
+    .description = Einen Befehl überall aktivieren oder deaktivieren.
+    .command-description = Befehl, der umgeschaltet werden soll
+admin-shutdown =
+    .description = Den Bot geordnet herunterfahren.
+advice = ratschlag
+    .description = Einen zufälligen Ratschlag erhalten.
+caption_meme = meme_beschriften
+    .description = Eine Meme-Vorlage mit so vielen Texten beschriften, wie sie Textfelder hat.
+    .template = vorlage
+    .template-description = Name der Meme-Vorlage
+    .captions = texte
+    .captions-description = Texte getrennt durch |, leer lassen, um sie in einem Formular einzugeben
+config =
+    .description = Den Bot für diesen Server einrichten.
+config-commands =
+    .description = Festlegen, welche Befehle wo verwendet werden können.
+config-commands-allow =
+    .description = Einen Befehl oder eine Kategorie erlauben und allgemeinere Regeln überschreiben.
+    .target-description = Befehl oder Kategorie
+    .channel-description = Nur in diesem Kanal, leer lassen für den ganzen Server
+config-commands-deny =
+    .description = Einen Befehl oder eine Kategorie deaktivieren.
+    .target-description = Befehl oder Kategorie
+    .channel-description = Nur in diesem Kanal, leer lassen für den ganzen Server
+config-commands-reset =
+    .description = Eine Regel entfernen, sodass allgemeinere Regeln gelten.
+    .target-description = Befehl oder Kategorie
+    .channel-description = Kanal der Regel, leer lassen für die serverweite Regel
+config-commands-list =
+    .description = Die Befehlsregeln dieses Servers auflisten.
+config-content = inhalte
+    .description = Eigene Einträge zu den Inhalten hinzufügen, die bei nicht erreichbaren Diensten verwendet werden.
+config-content-add = hinzufügen
+    .description = Einen Eintrag zu einem Inhaltspaket hinzufügen.
+    .pack = paket
+    .pack-description = Inhaltspaket
+    .entry = eintrag
+    .entry-description = Hinzuzufügender Text
+config-content-remove = entfernen
+    .description = Einen Eintrag aus einem Inhaltspaket entfernen.
+    .pack = paket
+    .pack-description = Inhaltspaket
+    .number = nummer
+    .number-description = Nummer des Eintrags laut Liste
+config-content-list = liste
+    .description = Die Einträge auflisten, die dieser Server zu einem Inhaltspaket hinzugefügt hat.
+    .pack = paket
+    .pack-description = Inhaltspaket
+config-language =
+    .description = Die Sprache für Textbefehle und Mitglieder festlegen, deren Sprache nicht unterstützt wird.
+    .language-description = Sprache der Antworten
+config-prefix =
+    .description = Das Präfix für Textbefehle auf diesem Server festlegen, leer lassen zum Zurücksetzen.
+    .prefix-description = Neues Präfix, z. B. `!`
+dad_joke = flachwitz
+    .description = Einen zufälligen Flachwitz für einen besseren Tag!
+fact = fakt
+    .description = Einen nutzlosen Fakt erhalten.
+    .mode = modus
+    .mode-description = Art des Fakts
+fortune = glückskeks
+    .description = Die Botschaft eines Glückskekses erhalten.
+    .mode = modus
+    .mode-description = Art des Glückskekses
+generate_meme = meme_erstellen
+    .description = Ein Meme aus einer Vorlage, einem Bild oder dem Avatar eines Mitglieds erstellen.
+    .template = vorlage
+    .template-description = Name der Meme-Vorlage
+    .top_text = text_oben
+    .top_text-description = Text oben im Meme
+    .bottom_text = text_unten
+    .bottom_text-description = Text unten im Meme
+    .image = bild
+    .image-description = Bild, das statt einer Vorlage verwendet wird
+    .image_url = bild_link
+    .image_url-description = Link zu einem Bild, das statt einer Vorlage verwendet wird
+    .member = mitglied
+    .member-description = Mitglied, dessen Avatar statt einer Vorlage verwendet wird
+let_me_google_that =
+    .description = Lass mich das für dich oder jemand anderen googeln
+    .search = suche
+    .search-description = Was soll ich googeln?
+    .target = ziel
+    .target-description = Wen soll ich erwähnen, damit er sich das Ergebnis ansieht?
+pickup_line = anmachspruch
+    .description = Einen Anmach- oder Schlussmachspruch erhalten.
+    .line_type = art
+    .line_type-description = Welche Art von Spruch angezeigt werden soll
+ping =
+    .description = Den Bot anpingen, um die Latenz zu prüfen.
+say = sag
+    .description = Eine eigene Nachricht mit einer ASCII-Figur sagen.
+    .message = nachricht
+    .message-description = Zu sagende Nachricht
+    .character = figur
+    .character-description = Welche Figur?
+stats = statistik
+    .description = Anzeigen, wie die Befehle des Bots auf diesem Server verwendet werden.
+    .window = zeitraum
+    .window-description = Zeitraum, standardmäßig die letzten 7 Tage
+user_info = nutzerinfo
+    .description = Informationen über ein Mitglied des Servers oder dich selbst erhalten
+    .guild_member = mitglied
+    .guild_member-description = Ausgewähltes Mitglied
+xkcd =
+    .description = Einen xkcd-Comic abrufen: den neuesten, eine bestimmte Nummer oder einen zufälligen.
+    .comic_number = nummer
+    .comic_number-description = Nummer des Comics (leer lassen für den neuesten)
+    .random = zufällig
+    .random-description = Einen zufälligen xkcd-Comic erhalten
+
+## Choices
+
+choice-random = Zufällig
+choice-today = Heute
+choice-pickup = Anmachspruch
+choice-breakup = Schlussmachspruch
+choice-advice = Ratschlag
+choice-dad-joke = Flachwitz
+choice-fact = Fakt
+choice-fortune = Glückskeks
+choice-day = Tag
+choice-week = Woche
+choice-month = Monat
+choice-all-time = Gesamter Zeitraum
+
+## Shared
+
+source = Quelle: { $source }
+view-source = 🔗 Quelle ansehen
+content-pack-footer = 📦 Aus dem Offline-Inhaltspaket
+content-pack-name = { $pack ->
+        [advice] Ratschläge
+        [dad_joke] Flachwitze
+        [fact] Fakten
+        [fortune] Glückskekse
+        [pickup] Anmachsprüche
+       *[breakup] Schlussmachsprüche
+    }
+
+## Errors
+
+error-id = Fehler-ID: { $id }
+error-cooldown-title = ⏳ Langsam!
+error-cooldown = Du kannst `/{ $command }` <t:{ $available_at }:R> wieder verwenden.
+error-argument-title = ❓ Ungültiges Argument
+error-argument = `{ $input }` konnte nicht verstanden werden: { $error }
+error-arguments = Die Argumente konnten nicht verstanden werden: { $error }
+error-upstream-title = 🌐 Dienst nicht verfügbar
+error-upstream = Der Dienst { $provider } hat gerade Probleme. Bitte versuche es später noch einmal.
+error-internal-title = 💥 Etwas ist schiefgelaufen
+error-internal = Beim Ausführen dieses Befehls ist ein unerwarteter Fehler aufgetreten.
+error-unavailable-title = 🚫 Nicht verfügbar
+error-unavailable = Du kannst diesen Befehl hier nicht verwenden.
+check-disabled = `/{ $command }` ist derzeit deaktiviert.
+check-denied = `/{ $command }` wurde hier von den Moderatoren des Servers deaktiviert.
+
+## /about
+
+about-title = Über { $name }
+about-intro = Dieser Bot ist eine Rust-Portierung von [KittyBot](https://github.com/olliequ/KittyBot) für den Discord-Server von CS@unimelb.
+about-version = Version
+about-git-hash = Git-Hash
+about-git-branch = Git-Branch
+about-dependencies = 🦀 Abhängigkeiten
+about-build-info = 🔧 Build-Informationen
+about-built = Erstellt
+about-target = Ziel
+about-footer = Mit Rust gebaut 🦀 • { $version } ({ $git_hash })
+about-source-code = 📂 Quellcode von Coco Bot
+about-kittybot-source-code = 🐱 Quellcode von KittyBot
+
+## /admin
+
+admin-scope-global = global
+admin-scope-guild = im Server { $guild }
+admin-registered = ✅ { $scope } registriert
+admin-unregistered = ✅ { $scope } entfernt
+admin-unchanged = ➖ { $scope } unverändert
+admin-removed = 🧹 { $scope } entfernt, da nicht mehr konfiguriert
+admin-failed = ❌ { $scope } fehlgeschlagen: { $error }
+admin-register-again = Mit `/admin register` oder einem Neustart des Bots werden sie wieder registriert.
+admin-templates-reloaded = 🖼️ { $count ->
+        [one] 1 Meme-Vorlage
+       *[other] { $count } Meme-Vorlagen
+    } geladen.
+admin-templates-failed = ❌ Meme-Vorlagen konnten nicht neu geladen werden: { $error }
+admin-presence-updated = ✅ Status aktualisiert.
+admin-stats-title = 📊 Laufzeitstatistik
+admin-stats-uptime = Laufzeit
+admin-stats-latency = Gateway-Latenz
+admin-stats-guilds = Server
+admin-stats-commands = Befehle
+admin-stats-templates = Meme-Vorlagen
+admin-stats-disabled = Deaktivierte Befehle
+admin-stats-none = Keine
+admin-unknown-command = ❌ Unbekannter Befehl `{ $command }`.
+admin-toggle-protected = ❌ Der Admin-Befehl kann nicht deaktiviert werden.
+admin-enabled = ✅ `/{ $command }` ist jetzt aktiviert.
+admin-disabled = ✅ `/{ $command }` ist jetzt deaktiviert.
+admin-shutting-down = 👋 Wird heruntergefahren...
+
+## /caption_meme
+
+caption-meme-form-title = { $template } beschriften
+caption-meme-field = Text { $number }
+caption-meme-too-many = ❌ { $template } hat Platz für { $boxes ->
+        [one] 1 Text
+       *[other] { $boxes } Texte
+    }, aber es wurden { $captions } angegeben.
+caption-meme-missing = ❌ Gib die Texte getrennt durch `|` an, z. B. "Text oben | Text unten".
+
+## /config
+
+config-unknown-target = ❌ Unbekannter Befehl oder unbekannte Kategorie `{ $target }`.
+config-target-command = `/{ $name }`
+config-target-category = Kategorie **{ $name }**
+config-target-category-choice = { $name } (Kategorie)
+config-scope-channel = in <#{ $channel }>
+config-scope-guild = auf dem ganzen Server
+config-rule-set = ✅ { $target } ist jetzt { $scope } { $effect ->
+        [allow] erlaubt
+       *[deny] deaktiviert
+    }.
+config-rule-missing = ℹ️ Es gibt keine Regel für { $target } { $scope }.
+config-rule-removed = ✅ Die Regel für { $target } { $scope } wurde entfernt.
+config-rules-title = ⚙️ Befehlsregeln
+config-rules-empty = Keine Regeln festgelegt, jeder Befehl ist überall verfügbar.
+config-rules-footer = Kanalregeln haben Vorrang vor serverweiten Regeln, Befehlsregeln vor Kategorieregeln.
+config-prefix-invalid = ❌ Das Präfix { $reason ->
+        [empty] darf nicht leer sein
+        [whitespace] darf keine Leerzeichen enthalten
+       *[too-long] darf höchstens { $max } Zeichen lang sein
+    }.
+config-prefix-set = ✅ Textbefehle verwenden jetzt `{ $prefix }`, z. B. `{ $prefix }xkcd`.
+config-prefix-reset = ✅ Textbefehle verwenden wieder das Standardpräfix `{ $prefix }`.
+config-prefix-disabled = Textbefehle sind für diesen Bot derzeit deaktiviert.
+config-language-unknown = ❌ Unbekannte Sprache `{ $language }`.
+config-language-set = ✅ Der Bot antwortet auf diesem Server jetzt auf { $language }, sofern er die Discord-Sprache eines Mitglieds nicht spricht.
+config-language-reset = ✅ Der Bot antwortet auf diesem Server jetzt auf Englisch, sofern er die Discord-Sprache eines Mitglieds nicht spricht.
+config-content-added = ✅ Der Eintrag wurde zum Paket „{ $pack }“ hinzugefügt.
+config-content-invalid = ❌ Der Eintrag { $reason ->
+        [empty] darf nicht leer sein
+        [too-long] darf höchstens { $max_len } Zeichen lang sein
+        [duplicate] ist bereits im Paket
+       *[full] passt nicht mehr, das Paket hat bereits { $max_entries } Einträge
+    }.
+config-content-removed = ✅ „{ $entry }“ wurde aus dem Paket „{ $pack }“ entfernt.
+config-content-missing = ❌ Das Paket „{ $pack }“ hat keinen Eintrag { $number }.
+config-content-title = 📦 Inhaltspaket: { $pack }
+config-content-empty = Dieser Server hat noch keine Einträge hinzugefügt.
+config-content-footer = Wird zusammen mit { $count } mitgelieferten Einträgen verwendet, wenn der Dienst nicht erreichbar ist.
+
+## /dad_joke
+
+dad-joke-title = Hier ist dein Flachwitz
+dad-joke-footer = Witz-ID: { $id } | Bereitgestellt von icanhazdadjoke.com
+dad-joke-view = 🔗 Auf der Website ansehen
+
+## /fact
+
+fact-title = 🤓 Nutzloser Fakt
+fact-title-today = 🧠 Nutzloser Fakt des Tages
+fact-title-random = 🤓 Zufälliger nutzloser Fakt
+
+## /fortune
+
+fortune-title = 🥠 Glückskeks
+fortune-title-today = 🔮 Glückskeks des Tages
+fortune-title-random = 🥠 Dein Glückskeks
+fortune-lucky-numbers = 🍀 Glückszahlen
+fortune-invalid-mode = ❌ Ungültiger Modus. Bitte wähle 'random' oder 'today'.
+
+## /generate_meme
+
+meme-title = Erstelltes Meme
+meme-template = Vorlage: `{ $template }`
+meme-error = Das Meme konnte nicht erstellt werden: { $error }
+meme-unknown-template = ❌ Es gibt keine Meme-Vorlage namens `{ $template }`.
+meme-image = Bild: { $image }
+meme-base-invalid = ❌ { $count ->
+        [0] Wähle eine Vorlage, ein Bild, einen Bild-Link oder ein Mitglied für das Meme.
+       *[other] Wähle nur eines aus Vorlage, Bild, Bild-Link und Mitglied.
+    }
+meme-image-invalid = ❌ Das Bild kann nicht verwendet werden: { $reason ->
+        [url] nur http(s)-Links zu öffentlichen Hosts sind erlaubt
+        [size] es ist größer als { $max_mb } MB
+        [format] es ist kein PNG-, JPEG-, GIF-, WebP- oder BMP-Bild
+        [dimensions] es ist breiter oder höher als { $max_dimension } Pixel oder hat zu viele Einzelbilder
+       *[download] es konnte nicht heruntergeladen werden
+    }.
+
+## /let_me_google_that
+
+lmgtfy-reply = Bitte sehr: { $url }
+lmgtfy-reply-target = { $target }, bitte sehr: { $url }
+
+## /ping
+
+ping-pong = Pong!
+ping-latency = Pong! Latenz: { $latency } ms
+ping-shard = Shard { $shard }: { $stage ->
+        [connected] verbunden
+        [disconnected] getrennt
+       *[connecting] verbindet
+    }, { $reconnects ->
+        [one] 1 Neuverbindung
+       *[other] { $reconnects } Neuverbindungen
+    }
+ping-shard-heartbeat = , Heartbeat { $heartbeat } ms
+ping-shards-connected = { $connected } von { $total } Shards in diesem Prozess verbunden
+
+## /say
+
+say-too-long = Diese Nachricht war zu lang.
+
+## /stats
+
+stats-title = 📊 Befehlsnutzung ({ $window ->
+        [day] letzte 24 Stunden
+        [week] letzte 7 Tage
+        [month] letzte 30 Tage
+       *[all] gesamter Zeitraum
+    })
+stats-empty = In diesem Zeitraum wurden keine Befehle verwendet.
+stats-summary = { $total ->
+        [one] 1 Befehl
+       *[other] { $total } Befehle
+    } verwendet, { $failed } fehlgeschlagen ({ $rate } %).
+stats-top-commands = 🏆 Häufigste Befehle
+stats-top-command = { $rank }. `/{ $command }` — { $total ->
+        [one] 1-mal
+       *[other] { $total }-mal
+    }
+stats-top-users = 👥 Aktivste Mitglieder
+stats-top-user = { $rank }. <@{ $user }> — { $total ->
+        [one] 1 Befehl
+       *[other] { $total } Befehle
+    }
+stats-failure-rates = ⚠️ Fehlerquoten
+stats-failure-rate = `/{ $command }` — { $rate } % ({ $failed } von { $total })
+stats-no-failures = Keine Fehler 🎉
+
+## /user_info
+
+user-info-title = Über { $name }
+user-info-joined =
+    Discord beigetreten am: <t:{ $created }:F> (<t:{ $created }:R>)
+    Diesem Server beigetreten am: <t:{ $joined }:F> (<t:{ $joined }:R>)
+
+## /xkcd
+
+xkcd-comic = Comic Nr. { $number }
+xkcd-explain = xkcd erklären
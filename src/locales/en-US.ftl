@@ -0,0 +1,344 @@
+# Message catalogue for English (United States), the fallback for every other locale.
+#
+# Commands are described by a message named after the command, with subcommands joined by
+# a dash (e.g. `config-commands-allow`). Its attributes hold the command description and the
+# description of each parameter as `.<parameter>-description`. Other catalogues may also give
+# localized names as the message value and `.<parameter>` attributes.
+
+language-name = English
+
+## Command metadata
+
+about =
+    .description = Get information about the bot.
+admin =
+    .description = Bot administration (owners only).
+admin-register =
+    .description = Force re-registration of all slash commands.
+admin-unregister =
+    .description = Remove all slash commands from Discord.
+admin-reload_templates =
+    .description = Rescan the meme template directories.
+admin-presence =
+    .description = Change the bot's presence until the next restart.
+    .activity_type-description = Kind of activity
+    .activity-description = Activity text, leave empty to clear
+    .status-description = Online status
+admin-stats =
+    .description = Show runtime statistics.
+admin-toggle =
+    .description = Enable or disable a command everywhere.
+    .command-description = Command to toggle
+admin-shutdown =
+    .description = Gracefully shut the bot down.
+advice =
+    .description = Get a random piece of advice.
+caption_meme =
+    .description = Caption a meme template with as many captions as it has text boxes.
+    .template-description = Name of the meme template
+    .captions-description = Captions separated by |, leave empty to fill them in a form
+config =
+    .description = Configure the bot for this server.
+config-commands =
+    .description = Choose which commands can be used where.
+config-commands-allow =
+    .description = Allow a command or category, overriding broader rules.
+    .target-description = Command or category
+    .channel-description = Only in this channel, leave empty for the whole server
+config-commands-deny =
+    .description = Disable a command or category.
+    .target-description = Command or category
+    .channel-description = Only in this channel, leave empty for the whole server
+config-commands-reset =
+    .description = Remove a rule, falling back to broader rules.
+    .target-description = Command or category
+    .channel-description = Channel of the rule, leave empty for the server-wide rule
+config-commands-list =
+    .description = List the command rules of this server.
+config-content =
+    .description = Add your own entries to the content used when a service is unavailable.
+config-content-add =
+    .description = Add an entry to a content pack.
+    .pack-description = Content pack
+    .entry-description = Text to add
+config-content-remove =
+    .description = Remove an entry from a content pack.
+    .pack-description = Content pack
+    .number-description = Number of the entry, as shown by the list
+config-content-list =
+    .description = List the entries this server added to a content pack.
+    .pack-description = Content pack
+config-language =
+    .description = Set the language for text commands and members whose own language isn't supported.
+    .language-description = Language to reply in
+config-prefix =
+    .description = Set the prefix for text commands in this server, leave empty to reset it.
+    .prefix-description = New prefix, e.g. `!`
+dad_joke =
+    .description = Get a random dad joke to brighten your day!
+fact =
+    .description = Get a useless fact.
+    .mode-description = Type of fact
+fortune =
+    .description = Get a fortune cookie message.
+    .mode-description = Type of fortune
+generate_meme =
+    .description = Generate a meme from a template, an image or a member's avatar.
+    .template-description = Name of the meme template
+    .top_text-description = Text to display at the top of the meme
+    .bottom_text-description = Text to display at the bottom of the meme
+    .image-description = Image to use instead of a template
+    .image_url-description = Link to an image to use instead of a template
+    .member-description = Member whose avatar to use instead of a template
+let_me_google_that =
+    .description = Let me Google that for you or someone else
+    .search-description = What should I google?
+    .target-description = Whom should I ping to look at the result?
+pickup_line =
+    .description = Get a pickup or breakup line.
+    .line_type-description = Which type of lines to show
+ping =
+    .description = Ping the bot to check latency.
+say =
+    .description = Say a custom message with an ASCII character.
+    .message-description = Message to say
+    .character-description = Which character?
+stats =
+    .description = Show how the bot's commands are used in this server.
+    .window-description = Time period, the last 7 days by default
+user_info =
+    .description = Get information about a user in the server or yourself
+    .guild_member-description = Selected user
+xkcd =
+    .description = Fetch an xkcd comic. Leave empty for latest comic, provide a number, or get a random one.
+    .comic_number-description = Comic number (leave empty for latest)
+    .random-description = Get a random xkcd comic
+
+## Shared
+
+source = Source: { $source }
+view-source = 🔗 View Source
+content-pack-footer = 📦 From the offline content pack
+content-pack-name = { $pack ->
+        [advice] advice
+        [dad_joke] dad joke
+        [fact] fact
+        [fortune] fortune
+        [pickup] pickup line
+       *[breakup] breakup line
+    }
+
+## Errors
+
+error-id = Error ID: { $id }
+error-cooldown-title = ⏳ Slow down!
+error-cooldown = You can use `/{ $command }` again <t:{ $available_at }:R>.
+error-argument-title = ❓ Invalid argument
+error-argument = Couldn't understand `{ $input }`: { $error }
+error-arguments = Couldn't understand the arguments: { $error }
+error-upstream-title = 🌐 Service unavailable
+error-upstream = The { $provider } service is having trouble right now. Please try again later.
+error-internal-title = 💥 Something went wrong
+error-internal = An unexpected error occurred while running this command.
+error-unavailable-title = 🚫 Not available
+error-unavailable = You can't use this command here.
+check-disabled = `/{ $command }` is currently disabled.
+check-denied = `/{ $command }` is disabled here by the server's moderators.
+
+## /about
+
+about-title = About { $name }
+about-intro = This bot is a Rust port of [KittyBot](https://github.com/olliequ/KittyBot) for the CS@unimelb Discord server.
+about-version = Version
+about-git-hash = Git Hash
+about-git-branch = Git Branch
+about-dependencies = 🦀 Dependencies
+about-build-info = 🔧 Build Info
+about-built = Built
+about-target = Target
+about-footer = Built with Rust 🦀 • { $version } ({ $git_hash })
+about-source-code = 📂 Coco Bot Source Code
+about-kittybot-source-code = 🐱 KittyBot Source Code
+
+## /admin
+
+admin-scope-global = globally
+admin-scope-guild = in guild { $guild }
+admin-registered = ✅ Registered { $scope }
+admin-unregistered = ✅ Unregistered { $scope }
+admin-unchanged = ➖ Unchanged { $scope }
+admin-removed = 🧹 Removed { $scope }, which is no longer configured
+admin-failed = ❌ Failed { $scope }: { $error }
+admin-register-again = Use `/admin register` or restart the bot to register them again.
+admin-templates-reloaded = 🖼️ Loaded { $count ->
+        [one] 1 meme template
+       *[other] { $count } meme templates
+    }.
+admin-templates-failed = ❌ Failed to reload meme templates: { $error }
+admin-presence-updated = ✅ Presence updated.
+admin-stats-title = 📊 Runtime Statistics
+admin-stats-uptime = Uptime
+admin-stats-latency = Gateway Latency
+admin-stats-guilds = Guilds
+admin-stats-commands = Commands
+admin-stats-templates = Meme Templates
+admin-stats-disabled = Disabled Commands
+admin-stats-none = None
+admin-unknown-command = ❌ Unknown command `{ $command }`.
+admin-toggle-protected = ❌ The admin command can't be disabled.
+admin-enabled = ✅ `/{ $command }` is now enabled.
+admin-disabled = ✅ `/{ $command }` is now disabled.
+admin-shutting-down = 👋 Shutting down...
+
+## /caption_meme
+
+caption-meme-form-title = Caption { $template }
+caption-meme-field = Caption { $number }
+caption-meme-too-many = ❌ { $template } has room for { $boxes ->
+        [one] 1 caption
+       *[other] { $boxes } captions
+    }, but { $captions } were given.
+caption-meme-missing = ❌ Give the captions separated by `|`, e.g. "top text | bottom text".
+
+## /config
+
+config-unknown-target = ❌ Unknown command or category `{ $target }`.
+config-target-command = `/{ $name }`
+config-target-category = category **{ $name }**
+config-target-category-choice = { $name } (category)
+config-scope-channel = in <#{ $channel }>
+config-scope-guild = server-wide
+config-rule-set = ✅ { $target } is now { $effect ->
+        [allow] allowed
+       *[deny] disabled
+    } { $scope }.
+config-rule-missing = ℹ️ There is no rule for { $target } { $scope }.
+config-rule-removed = ✅ Removed the rule for { $target } { $scope }.
+config-rules-title = ⚙️ Command Rules
+config-rules-empty = No rules set, every command is available everywhere.
+config-rules-footer = Channel rules beat server-wide rules, command rules beat category rules.
+config-prefix-invalid = ❌ The prefix { $reason ->
+        [empty] must not be empty
+        [whitespace] must not contain whitespace
+       *[too-long] must be at most { $max } characters long
+    }.
+config-prefix-set = ✅ Text commands now use `{ $prefix }`, e.g. `{ $prefix }xkcd`.
+config-prefix-reset = ✅ Text commands use the default prefix `{ $prefix }` again.
+config-prefix-disabled = Text commands are currently disabled for this bot.
+config-language-unknown = ❌ Unknown language `{ $language }`.
+config-language-set = ✅ The bot now replies in { $language } in this server, unless it speaks a member's own Discord language.
+config-language-reset = ✅ The bot now replies in English in this server, unless it speaks a member's own Discord language.
+config-content-added = ✅ Added the entry to the { $pack } pack.
+config-content-invalid = ❌ The entry { $reason ->
+        [empty] must not be empty
+        [too-long] must be at most { $max_len } characters long
+        [duplicate] is already in the pack
+       *[full] doesn't fit, the pack already has { $max_entries } entries
+    }.
+config-content-removed = ✅ Removed "{ $entry }" from the { $pack } pack.
+config-content-missing = ❌ There is no entry { $number } in the { $pack } pack.
+config-content-title = 📦 Content pack: { $pack }
+config-content-empty = This server hasn't added any entries yet.
+config-content-footer = Used along with { $count } bundled entries whenever the service is unavailable.
+
+## /dad_joke
+
+dad-joke-title = Here's your Dad Joke
+dad-joke-footer = Joke ID: { $id } | Powered by icanhazdadjoke.com
+dad-joke-view = 🔗 View on Website
+
+## /fact
+
+fact-title = 🤓 Useless Fact
+fact-title-today = 🧠 Useless Fact of the Day
+fact-title-random = 🤓 Random Useless Fact
+
+## /fortune
+
+fortune-title = 🥠 Fortune Cookie
+fortune-title-today = 🔮 Fortune Cookie of the Day
+fortune-title-random = 🥠 Your Fortune Cookie
+fortune-lucky-numbers = 🍀 Lucky Numbers
+fortune-invalid-mode = ❌ Invalid mode. Please choose 'random' or 'today'.
+
+## /generate_meme
+
+meme-title = Generated Meme
+meme-template = Template: `{ $template }`
+meme-error = Failed to generate meme: { $error }
+meme-unknown-template = ❌ There is no meme template called `{ $template }`.
+meme-image = Image: { $image }
+meme-base-invalid = ❌ { $count ->
+        [0] Choose a template, an image, an image link or a member to make the meme from.
+       *[other] Choose only one of a template, an image, an image link or a member.
+    }
+meme-image-invalid = ❌ That image can't be used: { $reason ->
+        [url] only http(s) links to public hosts are allowed
+        [size] it's larger than { $max_mb } MB
+        [format] it isn't a PNG, JPEG, GIF, WebP or BMP image
+        [dimensions] it's wider or taller than { $max_dimension } pixels or has too many frames
+       *[download] it couldn't be downloaded
+    }.
+
+## /let_me_google_that
+
+lmgtfy-reply = Here you go: { $url }
+lmgtfy-reply-target = { $target }, here you go: { $url }
+
+## /ping
+
+ping-pong = Pong!
+ping-latency = Pong! Latency: { $latency }ms
+ping-shard = Shard { $shard }: { $stage ->
+        [connected] connected
+        [disconnected] disconnected
+       *[connecting] connecting
+    }, { $reconnects ->
+        [one] 1 reconnect
+       *[other] { $reconnects } reconnects
+    }
+ping-shard-heartbeat = , heartbeat { $heartbeat }ms
+ping-shards-connected = { $connected } of { $total } shards in this process connected
+
+## /say
+
+say-too-long = That message was too long.
+
+## /stats
+
+stats-title = 📊 Command usage ({ $window ->
+        [day] last 24 hours
+        [week] last 7 days
+        [month] last 30 days
+       *[all] all time
+    })
+stats-empty = No commands were used in this period.
+stats-summary = { $total ->
+        [one] 1 command
+       *[other] { $total } commands
+    } used, { $failed } failed ({ $rate }%).
+stats-top-commands = 🏆 Top commands
+stats-top-command = { $rank }. `/{ $command }` — { $total ->
+        [one] 1 use
+       *[other] { $total } uses
+    }
+stats-top-users = 👥 Top users
+stats-top-user = { $rank }. <@{ $user }> — { $total ->
+        [one] 1 command
+       *[other] { $total } commands
+    }
+stats-failure-rates = ⚠️ Failure rates
+stats-failure-rate = `/{ $command }` — { $rate }% ({ $failed } of { $total })
+stats-no-failures = No failures 🎉
+
+## /user_info
+
+user-info-title = About { $name }
+user-info-joined =
+    Joined Discord on: <t:{ $created }:F> (<t:{ $created }:R>)
+    Joined this server on: <t:{ $joined }:F> (<t:{ $joined }:R>)
+
+## /xkcd
+
+xkcd-comic = Comic #{ $number }
+xkcd-explain = Explain xkcd
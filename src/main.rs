@@ -1,28 +1,174 @@
 use dotenv::dotenv;
 use poise::serenity_prelude as serenity;
-use tracing::{error, info};
+use std::collections::HashSet;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+use tracing::{debug, error, info, warn};
 mod bot_lib;
 mod commands;
+mod config;
 mod events;
+mod repl;
+mod storage;
 
-struct Data {} // User data, which is stored and accessible in all command invocations
+// User data, which is stored and accessible in all command invocations
+struct Data {
+    config: config::Config,
+    http: bot_lib::http::HttpClient,
+    /// Client for images linked by users, see [`bot_lib::meme_images::client`]
+    image_http: bot_lib::http::HttpClient,
+    storage: storage::Storage,
+    started_at: Instant,
+    /// Current presence, initially taken from the configuration and changeable at runtime
+    presence: RwLock<config::PresenceConfig>,
+    /// Commands disabled at runtime by an owner
+    disabled_commands: RwLock<HashSet<String>>,
+    meme_templates: RwLock<Vec<bot_lib::meme_templates::MemeTemplate>>,
+    /// Notified to request a graceful shutdown
+    shutdown: Arc<Notify>,
+    /// Commands currently running, awaited on shutdown
+    in_flight: Arc<bot_lib::shutdown::InFlight>,
+    /// Per-guild prefix overrides
+    prefixes: bot_lib::prefix::GuildPrefixes,
+    /// Per-guild language overrides
+    locales: bot_lib::i18n::GuildLocales,
+    /// Per-guild command rules
+    command_rules: bot_lib::command_rules::GuildCommandRules,
+    health: bot_lib::health::Health,
+    /// Connection state of the shards run by this process
+    shards: bot_lib::shards::Shards,
+}
 type Error = Box<dyn std::error::Error + Send + Sync>;
 type Context<'a> = poise::Context<'a, Data, Error>;
 
+impl Data {
+    /// Sets up the state commands run with, loading what was persisted or found on disk
+    async fn load(config: config::Config, storage: storage::Storage) -> Self {
+        let http =
+            bot_lib::http::HttpClient::new(&config.http).expect("Failed to create HTTP client");
+        let image_http = bot_lib::meme_images::client(&config.http)
+            .expect("Failed to create image download client");
+        let disabled_commands: HashSet<String> = match storage.state("disabled_commands").await {
+            Ok(Some(json)) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                warn!(error = %e, "Ignoring malformed list of disabled commands");
+                HashSet::new()
+            }),
+            Ok(None) => HashSet::new(),
+            Err(e) => {
+                warn!(error = %e, "Failed to load disabled commands");
+                HashSet::new()
+            }
+        };
+        let meme_templates = bot_lib::meme_templates::load().unwrap_or_else(|e| {
+            warn!(error = %e, "No meme templates found");
+            Vec::new()
+        });
+
+        Self {
+            presence: RwLock::new(config.presence.clone()),
+            config,
+            http,
+            image_http,
+            storage,
+            started_at: Instant::now(),
+            disabled_commands: RwLock::new(disabled_commands),
+            meme_templates: RwLock::new(meme_templates),
+            shutdown: Arc::new(Notify::new()),
+            health: bot_lib::health::Health::default(),
+            shards: bot_lib::shards::Shards::default(),
+            in_flight: Arc::default(),
+            prefixes: bot_lib::prefix::GuildPrefixes::default(),
+            locales: bot_lib::i18n::GuildLocales::default(),
+            command_rules: bot_lib::command_rules::GuildCommandRules::default(),
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() {
     dotenv().ok();
-    // Initialize tracing
-    tracing_subscriber::fmt::init();
+    let repl = std::env::args().any(|arg| arg == "--repl");
+
+    // Logging is configured by the configuration, so problems loading it can only be printed
+    let config = match config::Config::load(!repl) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    let log_guard = match bot_lib::logging::init(&config.logging) {
+        Ok(guard) => guard,
+        Err(e) => {
+            eprintln!("Failed to set up logging: {}", e);
+            std::process::exit(1);
+        }
+    };
+    match &config.source {
+        Some(path) => info!(path = %path.display(), "Configuration loaded"),
+        None => info!("No configuration file found, using defaults"),
+    }
+    for key in &config.env_overrides {
+        debug!(%key, "Applied configuration override from environment");
+    }
+
+    let storage = match storage::Storage::connect(&config.storage).await {
+        Ok(storage) => storage,
+        Err(e) => {
+            error!(error = %e, "Failed to open database");
+            std::process::exit(1);
+        }
+    };
+    let data = Arc::new(Data::load(config, storage).await);
 
-    let token = serenity::all::Token::from_env("DISCORD_TOKEN")
-        .expect("Expected a DISCORD_TOKEN in the environment");
+    if repl {
+        repl::run(data.clone()).await;
+        data.storage.close().await;
+        return;
+    }
+
+    let token = data.config.token();
     let mut intents = serenity::GatewayIntents::non_privileged();
     intents.insert(serenity::GatewayIntents::MESSAGE_CONTENT);
 
+    let mut commands = commands::all_commands();
+    data.config.apply_cooldowns(&mut commands);
+
+    if data.config.server.enabled {
+        tokio::spawn(bot_lib::server::serve(data.clone()));
+    }
+    if data.config.analytics.enabled && data.config.analytics.retention_days > 0 {
+        bot_lib::usage::spawn_pruner(data.storage.clone(), data.config.analytics.retention_days);
+    }
+
+    let prefix_config = &data.config.prefix;
+    let prefix_options = if prefix_config.enabled {
+        poise::PrefixFrameworkOptions {
+            dynamic_prefix: Some(|ctx| Box::pin(bot_lib::prefix::dynamic_prefix(ctx))),
+            mention_as_prefix: prefix_config.mention,
+            edit_tracker: (prefix_config.edit_tracking_secs > 0).then(|| {
+                Arc::new(poise::EditTracker::for_timespan(Duration::from_secs(
+                    prefix_config.edit_tracking_secs,
+                )))
+            }),
+            ..Default::default()
+        }
+    } else {
+        poise::PrefixFrameworkOptions {
+            mention_as_prefix: false,
+            ..Default::default()
+        }
+    };
+
     let framework = poise::Framework::builder()
         .options(poise::FrameworkOptions {
-            commands: commands::all_commands(),
+            commands,
+            on_error: |error| Box::pin(events::error::on_error(error)),
+            command_check: Some(|ctx| Box::pin(bot_lib::checks::command_check(ctx))),
+            pre_command: |ctx| Box::pin(bot_lib::invocation::pre_command(ctx)),
+            post_command: |ctx| Box::pin(bot_lib::invocation::post_command(ctx)),
+            prefix_options,
             ..Default::default()
         })
         .initialize_owners(true)
@@ -31,10 +177,47 @@ async fn main() {
 
     let client = serenity::ClientBuilder::new(token, intents)
         .framework(framework)
+        .data(data.clone())
         .event_handler(events::Handler)
         .await;
     info!("Client created");
-    if let Err(why) = client.unwrap().start().await {
+    let mut client = client.unwrap();
+
+    bot_lib::metrics::spawn_sampler(
+        data.clone(),
+        client.shard_manager.clone(),
+        client.cache.clone(),
+    );
+
+    // Stop the gateway on a termination signal or `/admin shutdown`, which makes `start` return
+    let shard_manager = client.shard_manager.clone();
+    let shutdown = data.shutdown.clone();
+    tokio::spawn(async move {
+        tokio::select! {
+            _ = bot_lib::shutdown::signal() => {}
+            _ = shutdown.notified() => {}
+        }
+        info!("Shutting down...");
+        shard_manager.shutdown_all().await;
+    });
+
+    if let Err(why) = bot_lib::shards::start(&mut client, &data.config.sharding).await {
         error!(error = %why, "Error with client");
     }
+
+    if !data
+        .in_flight
+        .wait_idle(bot_lib::shutdown::GRACE_PERIOD)
+        .await
+    {
+        warn!(
+            running = data.in_flight.count(),
+            "Commands still running after the grace period, exiting anyway"
+        );
+    }
+    data.storage.close().await;
+    info!("Shutdown complete");
+
+    // Flush buffered log lines before exiting
+    drop(log_guard);
 }
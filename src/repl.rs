@@ -0,0 +1,437 @@
+use crate::bot_lib::fake_context::{FakeContext, SentReply};
+use crate::{Data, Error, commands};
+use poise::serenity_prelude as serenity;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+use std::io::{BufRead, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+/// Directory attachments are saved to, relative to the working directory
+const OUTPUT_DIR: &str = "repl-output";
+
+type Commands = [poise::Command<Data, Error>];
+
+/// Options of an invocation by parameter name, checked against the command's parameters
+///
+/// Options are marked as read when looked up, so ones a command ignores can be reported.
+#[derive(Debug, Default)]
+pub struct Arguments {
+    values: HashMap<String, String>,
+    read: Mutex<HashSet<String>>,
+}
+
+impl Arguments {
+    #[cfg(test)]
+    pub fn new(values: impl IntoIterator<Item = (String, String)>) -> Self {
+        Self {
+            values: values.into_iter().collect(),
+            ..Self::default()
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.read
+            .lock()
+            .expect("Argument lock poisoned")
+            .insert(name.to_string());
+        self.values.get(name).map(String::as_str)
+    }
+
+    /// Options which were given but never looked up, sorted by name
+    pub fn unread(&self) -> Vec<&str> {
+        let read = self.read.lock().expect("Argument lock poisoned");
+        let mut unread: Vec<&str> = self
+            .values
+            .keys()
+            .filter(|name| !read.contains(*name))
+            .map(String::as_str)
+            .collect();
+        unread.sort();
+        unread
+    }
+
+    pub fn required(&self, name: &str) -> Result<&str, Error> {
+        self.get(name)
+            .ok_or_else(|| format!("Missing option `{}`", name).into())
+    }
+
+    pub fn parse<T>(&self, name: &str) -> Result<Option<T>, Error>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        self.get(name)
+            .map(|value| {
+                value
+                    .parse()
+                    .map_err(|e| format!("Invalid value for `{}`: {}", name, e).into())
+            })
+            .transpose()
+    }
+
+    pub fn choice<T: poise::ChoiceParameter>(&self, name: &str) -> Result<Option<T>, Error> {
+        self.get(name)
+            .map(|value| {
+                T::from_name(value).ok_or_else(|| format!("Invalid choice for `{}`", name).into())
+            })
+            .transpose()
+    }
+
+    /// A user given by ID or mention
+    pub fn user(&self, name: &str) -> Result<Option<serenity::UserId>, Error> {
+        self.get(name)
+            .map(|value| {
+                let id = value
+                    .trim_start_matches("<@")
+                    .trim_start_matches('!')
+                    .trim_end_matches('>');
+                match id.parse::<u64>() {
+                    Ok(id) if id != 0 => Ok(serenity::UserId::new(id)),
+                    _ => Err(format!("`{}` must be a user ID or mention", name).into()),
+                }
+            })
+            .transpose()
+    }
+}
+
+/// Splits a line at whitespace, except inside double quotes, where `\` escapes the next character
+fn split_words(line: &str) -> Result<Vec<String>, Error> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quoted = false;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                word.get_or_insert_with(String::new);
+            }
+            '\\' if quoted => {
+                let escaped = chars.next().ok_or("Unterminated escape")?;
+                word.get_or_insert_with(String::new).push(escaped);
+            }
+            c if c.is_whitespace() && !quoted => words.extend(word.take()),
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if quoted {
+        return Err("Unterminated quote".into());
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+/// Parses an invocation like `/xkcd comic_number:353` against the command definitions,
+/// returning the command name and its options
+fn parse_invocation(line: &str, commands: &Commands) -> Result<(String, Arguments), Error> {
+    let words = split_words(line)?;
+    let Some((name, options)) = words.split_first() else {
+        return Err("Expected a command".into());
+    };
+    let name = name.trim_start_matches('/');
+    let command = commands
+        .iter()
+        .find(|command| command.name == name)
+        .ok_or_else(|| format!("Unknown command `/{}`, see `.help`", name))?;
+    if !command.subcommands.is_empty() {
+        return Err(format!("/{} needs Discord and can't run locally", name).into());
+    }
+
+    let mut arguments = Arguments::default();
+    for option in options {
+        let (key, value) = option
+            .split_once(':')
+            .ok_or_else(|| format!("Expected `option:value`, got `{}`", option))?;
+        let parameter = command
+            .parameters
+            .iter()
+            .find(|parameter| parameter.name == key)
+            .ok_or_else(|| {
+                format!(
+                    "/{} has no option `{}`, only {}",
+                    name,
+                    key,
+                    option_names(command)
+                )
+            })?;
+
+        // Choices are matched case-insensitively, but passed on the way they're declared
+        let value = if parameter.choices.is_empty() {
+            value.to_string()
+        } else {
+            parameter
+                .choices
+                .iter()
+                .find(|choice| choice.name.eq_ignore_ascii_case(value))
+                .map(|choice| choice.name.to_string())
+                .ok_or_else(|| {
+                    format!(
+                        "`{}` must be one of {}",
+                        key,
+                        parameter
+                            .choices
+                            .iter()
+                            .map(|choice| format!("`{}`", choice.name))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )
+                })?
+        };
+        if arguments.values.insert(key.to_string(), value).is_some() {
+            return Err(format!("`{}` was given twice", key).into());
+        }
+    }
+
+    for parameter in &command.parameters {
+        if parameter.required && !arguments.values.contains_key(&*parameter.name) {
+            return Err(format!("/{} needs the option `{}`", name, parameter.name).into());
+        }
+    }
+    Ok((name.to_string(), arguments))
+}
+
+fn option_names(command: &poise::Command<Data, Error>) -> String {
+    if command.parameters.is_empty() {
+        return "none".to_string();
+    }
+    command
+        .parameters
+        .iter()
+        .map(|parameter| format!("`{}`", parameter.name))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn print_help(commands: &Commands) {
+    println!("Commands, with optional options in brackets:");
+    for command in commands {
+        let mut usage = format!("  /{}", command.name);
+        for parameter in &command.parameters {
+            let value = if parameter.choices.is_empty() {
+                "…".to_string()
+            } else {
+                parameter
+                    .choices
+                    .iter()
+                    .map(|choice| choice.name.to_string())
+                    .collect::<Vec<_>>()
+                    .join("|")
+            };
+            if parameter.required {
+                usage.push_str(&format!(" {}:{}", parameter.name, value));
+            } else {
+                usage.push_str(&format!(" [{}:{}]", parameter.name, value));
+            }
+        }
+        if let Some(description) = &command.description {
+            usage.push_str(&format!(" — {}", description));
+        }
+        println!("{}", usage);
+    }
+    println!("Values with spaces go in double quotes, e.g. top_text:\"hello there\".");
+    println!("  .guild <id|none>      run commands as if in the server with this ID");
+    println!("  .locale <locale|none> reply in another language, e.g. de");
+    println!("  .quit                 exit, as does the end of input");
+}
+
+/// Prints an embed as indented text
+fn print_embed(embed: &Value) {
+    let text = |value: &Value| value.as_str().map(str::to_string);
+
+    if let Some(title) = text(&embed["title"]) {
+        println!("┃ {}", title);
+    }
+    if let Some(description) = text(&embed["description"]) {
+        for line in description.lines() {
+            println!("┃ {}", line);
+        }
+    }
+    for field in embed["fields"].as_array().into_iter().flatten() {
+        println!("┃ ▸ {}", text(&field["name"]).unwrap_or_default());
+        for line in text(&field["value"]).unwrap_or_default().lines() {
+            println!("┃   {}", line);
+        }
+    }
+    if let Some(image) = text(&embed["image"]["url"]) {
+        println!("┃ 🖼 {}", image);
+    }
+    if let Some(footer) = text(&embed["footer"]["text"]) {
+        println!("┃ — {}", footer);
+    }
+}
+
+/// Prints a reply and saves its attachments
+fn print_reply(reply: &SentReply) {
+    if reply.ephemeral {
+        println!("(only visible to you)");
+    }
+    if let Some(content) = &reply.content {
+        println!("{}", content);
+    }
+    for embed in &reply.embeds {
+        print_embed(embed);
+    }
+    for row in &reply.components {
+        for component in row["components"].as_array().into_iter().flatten() {
+            let label = component["label"].as_str().unwrap_or_default();
+            match component["url"].as_str() {
+                Some(url) => println!("[{}] → {}", label, url),
+                None => println!("[{}]", label),
+            }
+        }
+    }
+    for attachment in &reply.attachments {
+        // Only keep the file name, as it comes from the command
+        let filename = Path::new(&attachment.filename)
+            .file_name()
+            .map_or_else(|| "attachment".into(), |name| name.to_string_lossy());
+        let path = Path::new(OUTPUT_DIR).join(&*filename);
+        let saved = std::fs::create_dir_all(OUTPUT_DIR)
+            .and_then(|()| std::fs::write(&path, &attachment.data));
+        match saved {
+            Ok(()) => println!("📎 Saved {}", path.display()),
+            Err(e) => eprintln!("Failed to save {}: {}", path.display(), e),
+        }
+    }
+}
+
+/// Reads the next line from standard input, or `None` at its end
+fn read_line() -> Option<String> {
+    print!("> ");
+    std::io::stdout().flush().ok()?;
+    let mut line = String::new();
+    match std::io::stdin().lock().read_line(&mut line) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(line),
+    }
+}
+
+/// Runs the invocations read from standard input against a fake context until end of input
+pub async fn run(data: Arc<Data>) {
+    let commands = commands::all_commands();
+    let mut guild_id = None;
+    let mut locale = None;
+    println!("Type a command like /xkcd comic_number:353, .help for help or .quit to exit.");
+
+    while let Ok(Some(line)) = tokio::task::spawn_blocking(read_line).await {
+        let line = line.trim();
+        let (word, rest) = line.split_once(' ').unwrap_or((line, ""));
+        match (word, rest.trim()) {
+            ("", _) => {}
+            (".quit" | ".exit", _) => break,
+            (".help", _) => print_help(&commands),
+            (".guild", "none") => guild_id = None,
+            (".guild", id) => match id.parse::<u64>() {
+                Ok(id) if id != 0 => guild_id = Some(serenity::GuildId::new(id)),
+                _ => eprintln!("Expected a server ID or `none`"),
+            },
+            (".locale", "none") => locale = None,
+            (".locale", tag) => locale = Some(tag.to_string()),
+            _ => match parse_invocation(line, &commands) {
+                Ok((name, arguments)) => {
+                    let mut ctx = FakeContext::with_data(data.clone());
+                    ctx.guild_id = guild_id;
+                    ctx.locale = locale.clone();
+
+                    let result = commands::run_local(&ctx, &name, &arguments).await;
+                    if ctx.deferred() {
+                        println!("(deferred)");
+                    }
+                    for reply in ctx.replies() {
+                        print_reply(&reply);
+                    }
+                    if let Err(e) = result {
+                        eprintln!("Error: {}", e);
+                    }
+                }
+                Err(e) => eprintln!("{}", e),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_quoted_words() {
+        assert_eq!(
+            split_words(r#"/generate_meme template:"Lion sitting Facing Front.jpg"  top_text:hi"#)
+                .unwrap(),
+            vec![
+                "/generate_meme",
+                "template:Lion sitting Facing Front.jpg",
+                "top_text:hi"
+            ]
+        );
+        assert_eq!(
+            split_words(r#"/say message:"say \"moo\"" x:"""#).unwrap(),
+            vec!["/say", r#"message:say "moo""#, "x:"]
+        );
+        assert!(split_words(r#"/say message:"moo"#).is_err());
+    }
+
+    #[test]
+    fn parses_invocations_against_commands() {
+        let commands = commands::all_commands();
+
+        let (name, arguments) = parse_invocation(
+            r#"/generate_meme template:"Lion sitting Facing Front.jpg" top_text:hi bottom_text:there"#,
+            &commands,
+        )
+        .unwrap();
+        assert_eq!(name, "generate_meme");
+        assert_eq!(
+            arguments.get("template"),
+            Some("Lion sitting Facing Front.jpg")
+        );
+        assert_eq!(arguments.get("bottom_text"), Some("there"));
+
+        let (_, arguments) = parse_invocation("fortune mode:TODAY", &commands).unwrap();
+        assert_eq!(arguments.get("mode"), Some("today"));
+
+        let (_, arguments) = parse_invocation("/xkcd comic_number:353", &commands).unwrap();
+        assert_eq!(arguments.parse::<u32>("comic_number").unwrap(), Some(353));
+        assert_eq!(arguments.parse::<bool>("random").unwrap(), None);
+
+        for invalid in [
+            "/nope",
+            "/xkcd number:353",
+            "/xkcd comic_number",
+            "/fortune",
+            "/fortune mode:tomorrow",
+            "/fortune mode:today mode:random",
+            "/config",
+        ] {
+            assert!(
+                parse_invocation(invalid, &commands).is_err(),
+                "{} was accepted",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn parses_users() {
+        let arguments = Arguments::new([
+            ("id".to_string(), "42".to_string()),
+            ("mention".to_string(), "<@!42>".to_string()),
+            ("zero".to_string(), "0".to_string()),
+        ]);
+        assert_eq!(
+            arguments.user("id").unwrap(),
+            Some(serenity::UserId::new(42))
+        );
+        assert_eq!(
+            arguments.user("mention").unwrap(),
+            Some(serenity::UserId::new(42))
+        );
+        assert!(arguments.user("zero").is_err());
+        assert_eq!(arguments.user("missing").unwrap(), None);
+    }
+}
@@ -0,0 +1,6 @@
+-- Bot-wide key/value state, e.g. the last registered command set
+CREATE TABLE IF NOT EXISTS bot_state (
+    key TEXT PRIMARY KEY NOT NULL,
+    value TEXT NOT NULL,
+    updated_at INTEGER NOT NULL DEFAULT (unixepoch())
+);
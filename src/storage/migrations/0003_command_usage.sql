@@ -0,0 +1,17 @@
+-- One row per finished command invocation, for usage statistics
+CREATE TABLE IF NOT EXISTS command_usage (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    command TEXT NOT NULL,
+    -- NULL in direct messages
+    guild_id INTEGER,
+    channel_id INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    outcome TEXT NOT NULL,
+    failed INTEGER NOT NULL,
+    -- NULL if the command was refused before it started, e.g. by a cooldown
+    latency_ms INTEGER,
+    invoked_at INTEGER NOT NULL DEFAULT (unixepoch())
+);
+
+CREATE INDEX IF NOT EXISTS command_usage_guild_time ON command_usage (guild_id, invoked_at);
+CREATE INDEX IF NOT EXISTS command_usage_time ON command_usage (invoked_at);
@@ -0,0 +1,398 @@
+use crate::config::StorageConfig;
+use poise::serenity_prelude as serenity;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePool, SqlitePoolOptions};
+use std::time::Duration;
+use tracing::info;
+
+/// Persistent storage backed by an embedded SQLite database
+///
+/// Cloning is cheap, all clones share the same connection pool.
+#[derive(Debug, Clone)]
+pub struct Storage {
+    pool: SqlitePool,
+}
+
+/// Discord IDs are `u64` while SQLite integers are `i64`, so store the bit pattern
+fn to_db_id(id: u64) -> i64 {
+    id as i64
+}
+
+impl Storage {
+    /// Opens (creating if needed) the database at the configured path and runs pending migrations
+    pub async fn connect(config: &StorageConfig) -> Result<Self, sqlx::Error> {
+        if let Some(parent) = config.database_path.parent() {
+            if !parent.as_os_str().is_empty() {
+                std::fs::create_dir_all(parent)?;
+            }
+        }
+
+        let options = SqliteConnectOptions::new()
+            .filename(&config.database_path)
+            .create_if_missing(true)
+            .journal_mode(SqliteJournalMode::Wal)
+            .busy_timeout(Duration::from_secs(5));
+        let pool = SqlitePoolOptions::new()
+            .max_connections(config.max_connections)
+            .connect_with(options)
+            .await?;
+        info!(path = %config.database_path.display(), "Opened database");
+
+        let storage = Self { pool };
+        storage.migrate().await?;
+        Ok(storage)
+    }
+
+    /// Opens a private in-memory database, mainly useful for tests
+    #[cfg(test)]
+    pub async fn in_memory() -> Result<Self, sqlx::Error> {
+        // A single connection, as every in-memory connection would otherwise get its own database
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await?;
+        let storage = Self { pool };
+        storage.migrate().await?;
+        Ok(storage)
+    }
+
+    /// Waits for pending writes and closes all connections
+    pub async fn close(&self) {
+        self.pool.close().await;
+        info!("Database closed");
+    }
+
+    async fn migrate(&self) -> Result<(), sqlx::Error> {
+        sqlx::migrate!("./src/storage/migrations")
+            .run(&self.pool)
+            .await?;
+        info!("Database migrations applied");
+        Ok(())
+    }
+}
+
+/// Bot-wide state which isn't tied to a guild or user
+impl Storage {
+    /// Returns a bot state value, if set
+    pub async fn state(&self, key: &str) -> Result<Option<String>, sqlx::Error> {
+        sqlx::query_scalar("SELECT value FROM bot_state WHERE key = ?")
+            .bind(key)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    /// Creates or replaces a bot state value
+    pub async fn set_state(&self, key: &str, value: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO bot_state (key, value) VALUES (?, ?)
+             ON CONFLICT (key) DO UPDATE SET value = excluded.value, updated_at = unixepoch()",
+        )
+        .bind(key)
+        .bind(value)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Returns all bot state values whose key starts with `prefix`, ordered by key
+    pub async fn states(&self, prefix: &str) -> Result<Vec<(String, String)>, sqlx::Error> {
+        sqlx::query_as(
+            "SELECT key, value FROM bot_state WHERE substr(key, 1, length(?1)) = ?1 ORDER BY key",
+        )
+        .bind(prefix)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Removes a bot state value
+    pub async fn delete_state(&self, key: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM bot_state WHERE key = ?")
+            .bind(key)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Generic key/value repository for per-guild settings
+impl Storage {
+    /// Returns a guild setting, if set
+    pub async fn guild_setting(
+        &self,
+        guild_id: serenity::GuildId,
+        key: &str,
+    ) -> Result<Option<String>, sqlx::Error> {
+        sqlx::query_scalar("SELECT value FROM guild_settings WHERE guild_id = ? AND key = ?")
+            .bind(to_db_id(guild_id.get()))
+            .bind(key)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    /// Creates or replaces a guild setting
+    pub async fn set_guild_setting(
+        &self,
+        guild_id: serenity::GuildId,
+        key: &str,
+        value: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO guild_settings (guild_id, key, value) VALUES (?, ?, ?)
+             ON CONFLICT (guild_id, key) DO UPDATE SET value = excluded.value, updated_at = unixepoch()",
+        )
+        .bind(to_db_id(guild_id.get()))
+        .bind(key)
+        .bind(value)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Removes a guild setting, returning whether it existed
+    pub async fn delete_guild_setting(
+        &self,
+        guild_id: serenity::GuildId,
+        key: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM guild_settings WHERE guild_id = ? AND key = ?")
+            .bind(to_db_id(guild_id.get()))
+            .bind(key)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+/// A finished command invocation, recorded for usage statistics
+#[derive(Debug, Clone)]
+pub struct CommandUsage {
+    /// Qualified name, e.g. `config prefix`
+    pub command: String,
+    pub guild_id: Option<serenity::GuildId>,
+    pub channel_id: serenity::ChannelId,
+    pub user_id: serenity::UserId,
+    /// Same as the outcome label of the command metrics, e.g. `success` or `cooldown`
+    pub outcome: String,
+    pub failed: bool,
+    /// Run time, if the command got to start
+    pub latency: Option<Duration>,
+}
+
+/// How often a command was used within a time window
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandCount {
+    pub command: String,
+    pub total: i64,
+    pub failed: i64,
+    /// Mean run time in milliseconds of the invocations which got to start
+    pub mean_latency_ms: Option<f64>,
+}
+
+/// Command usage analytics
+impl Storage {
+    /// Records a finished command invocation
+    pub async fn record_usage(&self, usage: &CommandUsage) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO command_usage
+                (command, guild_id, channel_id, user_id, outcome, failed, latency_ms)
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&usage.command)
+        .bind(usage.guild_id.map(|id| to_db_id(id.get())))
+        .bind(to_db_id(usage.channel_id.get()))
+        .bind(to_db_id(usage.user_id.get()))
+        .bind(&usage.outcome)
+        .bind(usage.failed)
+        .bind(
+            usage
+                .latency
+                .map(|latency| latency.as_millis().min(i64::MAX as u128) as i64),
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Counts the invocations of every command in a guild since the given Unix timestamp,
+    /// most used first
+    pub async fn command_counts(
+        &self,
+        guild_id: serenity::GuildId,
+        since: i64,
+    ) -> Result<Vec<CommandCount>, sqlx::Error> {
+        let rows: Vec<(String, i64, i64, Option<f64>)> = sqlx::query_as(
+            "SELECT command, COUNT(*) AS total, SUM(failed), AVG(latency_ms)
+             FROM command_usage
+             WHERE guild_id = ? AND invoked_at >= ?
+             GROUP BY command
+             ORDER BY total DESC, command",
+        )
+        .bind(to_db_id(guild_id.get()))
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(command, total, failed, mean_latency_ms)| CommandCount {
+                command,
+                total,
+                failed,
+                mean_latency_ms,
+            })
+            .collect())
+    }
+
+    /// Returns the users with the most invocations in a guild since the given Unix timestamp
+    pub async fn top_users(
+        &self,
+        guild_id: serenity::GuildId,
+        since: i64,
+        limit: u32,
+    ) -> Result<Vec<(serenity::UserId, i64)>, sqlx::Error> {
+        let rows: Vec<(i64, i64)> = sqlx::query_as(
+            "SELECT user_id, COUNT(*) AS total
+             FROM command_usage
+             WHERE guild_id = ? AND invoked_at >= ?
+             GROUP BY user_id
+             ORDER BY total DESC, user_id
+             LIMIT ?",
+        )
+        .bind(to_db_id(guild_id.get()))
+        .bind(since)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(user_id, total)| (serenity::UserId::new(user_id as u64), total))
+            .collect())
+    }
+
+    /// Deletes invocations recorded before the given Unix timestamp, returning how many
+    pub async fn prune_usage(&self, before: i64) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM command_usage WHERE invoked_at < ?")
+            .bind(before)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn lists_states_by_prefix() {
+        let storage = Storage::in_memory().await.unwrap();
+        for key in [
+            "registered_commands:guild:1",
+            "registered_commands:global",
+            "other",
+        ] {
+            storage.set_state(key, "value").await.unwrap();
+        }
+
+        let keys: Vec<String> = storage
+            .states("registered_commands:")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect();
+        assert_eq!(
+            keys,
+            ["registered_commands:global", "registered_commands:guild:1"]
+        );
+    }
+
+    #[tokio::test]
+    async fn guild_settings_round_trip() {
+        let storage = Storage::in_memory().await.unwrap();
+        let guild = serenity::GuildId::new(u64::MAX);
+
+        assert_eq!(storage.guild_setting(guild, "prefix").await.unwrap(), None);
+
+        storage
+            .set_guild_setting(guild, "prefix", "!")
+            .await
+            .unwrap();
+        storage
+            .set_guild_setting(guild, "prefix", "?")
+            .await
+            .unwrap();
+        assert_eq!(
+            storage.guild_setting(guild, "prefix").await.unwrap(),
+            Some("?".to_string())
+        );
+
+        assert!(storage.delete_guild_setting(guild, "prefix").await.unwrap());
+        assert!(!storage.delete_guild_setting(guild, "prefix").await.unwrap());
+    }
+
+    fn usage(command: &str, user: u64, outcome: &str, latency_ms: Option<u64>) -> CommandUsage {
+        CommandUsage {
+            command: command.to_string(),
+            guild_id: Some(serenity::GuildId::new(1)),
+            channel_id: serenity::ChannelId::new(2),
+            user_id: serenity::UserId::new(user),
+            outcome: outcome.to_string(),
+            failed: outcome == "error",
+            latency: latency_ms.map(Duration::from_millis),
+        }
+    }
+
+    #[tokio::test]
+    async fn aggregates_command_usage() {
+        let storage = Storage::in_memory().await.unwrap();
+        let guild = serenity::GuildId::new(1);
+
+        for usage in [
+            usage("xkcd", 10, "success", Some(100)),
+            usage("xkcd", 10, "success", Some(300)),
+            usage("xkcd", 11, "error", Some(200)),
+            usage("fact", 11, "cooldown", None),
+            CommandUsage {
+                guild_id: None,
+                ..usage("fact", 12, "success", Some(50))
+            },
+        ] {
+            storage.record_usage(&usage).await.unwrap();
+        }
+
+        assert_eq!(
+            storage.command_counts(guild, 0).await.unwrap(),
+            vec![
+                CommandCount {
+                    command: "xkcd".to_string(),
+                    total: 3,
+                    failed: 1,
+                    mean_latency_ms: Some(200.0),
+                },
+                CommandCount {
+                    command: "fact".to_string(),
+                    total: 1,
+                    failed: 0,
+                    mean_latency_ms: None,
+                },
+            ]
+        );
+        assert_eq!(
+            storage.top_users(guild, 0, 1).await.unwrap(),
+            vec![(serenity::UserId::new(10), 2)]
+        );
+
+        // Everything was recorded just now, so a window starting in the future is empty
+        let future = i64::MAX;
+        assert!(
+            storage
+                .command_counts(guild, future)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(storage.prune_usage(future).await.unwrap(), 5);
+        assert!(storage.command_counts(guild, 0).await.unwrap().is_empty());
+    }
+}